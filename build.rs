@@ -0,0 +1,11 @@
+// Lets the `loom` and `shuttle` features stand in for `--cfg loom` and
+// `--cfg shuttle`, which is what the code checks
+fn main() {
+    println!("cargo::rerun-if-changed=build.rs");
+
+    for model in ["loom", "shuttle"] {
+        if std::env::var_os(format!("CARGO_FEATURE_{}", model.to_uppercase())).is_some() {
+            println!("cargo::rustc-cfg={model}");
+        }
+    }
+}
@@ -0,0 +1,100 @@
+#ifndef SLOTH_H
+#define SLOTH_H
+
+#include <stdint.h>
+#include <stddef.h>
+
+/*
+ * C API of the sloth cache, built with `make build-ffi`. Regenerate with
+ * `make header` after changing src/ffi.rs.
+ *
+ * A SlothCache holds one byte buffer. Any number of threads may call
+ * sloth_cache_get, sloth_cache_update and sloth_cache_generation on the same
+ * cache concurrently; readers never block. Each function spells out what its
+ * pointer arguments must satisfy.
+ */
+
+typedef struct SlothBytes SlothBytes;
+
+typedef struct SlothCache SlothCache;
+
+/**
+ * Copies `len` bytes at `data` as the initial value. Free the cache with
+ * sloth_cache_free.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes, or may be NULL when `len` is 0.
+ */
+SlothCache *sloth_cache_new(const uint8_t *data, size_t len);
+
+/**
+ * The current value. Never blocks. Free the handle with sloth_bytes_free;
+ * it stays valid after the cache is updated or freed.
+ *
+ * # Safety
+ *
+ * `cache` must come from sloth_cache_new and not have been freed.
+ */
+SlothBytes *sloth_cache_get(const SlothCache *cache);
+
+/**
+ * Copies `len` bytes at `data` and publishes them. Safe to call from any
+ * number of threads at once.
+ *
+ * # Safety
+ *
+ * `cache` must come from sloth_cache_new and not have been freed. `data`
+ * must point to `len` readable bytes, or may be NULL when `len` is 0.
+ */
+void sloth_cache_update(const SlothCache *cache, const uint8_t *data, size_t len);
+
+/**
+ * Bumped by every update.
+ *
+ * # Safety
+ *
+ * `cache` must come from sloth_cache_new and not have been freed.
+ */
+uint64_t sloth_cache_generation(const SlothCache *cache);
+
+/**
+ * NULL is ignored, like with free.
+ *
+ * # Safety
+ *
+ * `cache` must be NULL or come from sloth_cache_new and not have been
+ * freed, and no other thread may still be using it.
+ */
+void sloth_cache_free(SlothCache *cache);
+
+/**
+ * The bytes of the value, or NULL for an empty one.
+ *
+ * # Safety
+ *
+ * `bytes` must come from sloth_cache_get and not have been freed. The
+ * returned pointer is valid until `bytes` is freed.
+ */
+const uint8_t *sloth_bytes_data(const SlothBytes *bytes);
+
+/**
+ * The length of the value.
+ *
+ * # Safety
+ *
+ * `bytes` must come from sloth_cache_get and not have been freed.
+ */
+size_t sloth_bytes_len(const SlothBytes *bytes);
+
+/**
+ * NULL is ignored, like with free.
+ *
+ * # Safety
+ *
+ * `bytes` must be NULL or come from sloth_cache_get and not have been
+ * freed.
+ */
+void sloth_bytes_free(SlothBytes *bytes);
+
+#endif  /* SLOTH_H */
@@ -0,0 +1,96 @@
+// `#[derive(SlothSnapshot)]`, re-exported by `sloth` with the `derive`
+// feature.
+//
+// For a struct with named fields it generates `<Name>Cache`, holding each
+// field in its own `sloth::cache::Cache`, with `new`, `snapshot`, and a
+// getter and setter per field.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Fields, parse_macro_input};
+
+#[proc_macro_derive(SlothSnapshot)]
+pub fn derive_snapshot(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "SlothSnapshot does not support generic structs",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(unsupported(&input)),
+        },
+        _ => return Err(unsupported(&input)),
+    };
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let cache = format_ident!("{}Cache", name);
+
+    let names: Vec<_> = fields.iter().map(|field| &field.ident).collect();
+    let types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    let setters: Vec<_> = fields
+        .iter()
+        .map(|field| format_ident!("set_{}", field.ident.as_ref().unwrap()))
+        .collect();
+
+    Ok(quote! {
+        #vis struct #cache {
+            #(#names: ::sloth::cache::Cache<#types>,)*
+        }
+
+        impl #cache {
+            #vis fn new(data: #name) -> Self {
+                Self {
+                    #(#names: ::sloth::cache::Cache::new(data.#names),)*
+                }
+            }
+
+            // Fields are read one after the other, so updates landing
+            // meanwhile may show up in some fields and not others
+            #vis fn snapshot(&self) -> #name {
+                #name {
+                    #(#names: self.#names.get_data(),)*
+                }
+            }
+
+            #(
+                #vis fn #names(&self) -> #types {
+                    self.#names.get_data()
+                }
+
+                #vis fn #setters(&self, data: #types) {
+                    self.#names.update(data);
+                }
+            )*
+        }
+
+        impl ::core::convert::From<#name> for #cache {
+            fn from(data: #name) -> Self {
+                Self::new(data)
+            }
+        }
+    })
+}
+
+fn unsupported(input: &DeriveInput) -> Error {
+    Error::new(
+        Span::call_site(),
+        format!(
+            "SlothSnapshot only supports structs with named fields, `{}` is not one",
+            input.ident
+        ),
+    )
+}
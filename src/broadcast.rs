@@ -0,0 +1,278 @@
+use core::{error::Error, fmt};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+};
+
+use crate::cache::Cache;
+
+// Channel that hands every sent value to every receiver, keeping the last
+// `capacity` of them for receivers that fall behind. The latest value is
+// also published through a `Cache`, so reading it with `latest` stays
+// wait-free however far behind a receiver is.
+pub fn channel<T: Clone>(initial: T, capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "capacity must be at least 1");
+
+    let shared = Arc::new(Shared {
+        latest: Cache::new(initial),
+        backlog: Mutex::new(Backlog {
+            values: VecDeque::with_capacity(capacity),
+            next: 0,
+            closed: false,
+        }),
+        sent: Condvar::new(),
+        capacity,
+    });
+
+    let receiver = Receiver {
+        shared: shared.clone(),
+        next: 0,
+    };
+
+    (Sender { shared }, receiver)
+}
+
+struct Shared<T: Clone> {
+    latest: Cache<T>,
+    backlog: Mutex<Backlog<T>>,
+    sent: Condvar,
+    capacity: usize,
+}
+
+struct Backlog<T> {
+    // The last `capacity` values, the newest at the back
+    values: VecDeque<T>,
+    // Sequence number the next sent value gets
+    next: u64,
+    closed: bool,
+}
+
+impl<T> Backlog<T> {
+    fn first(&self) -> u64 {
+        self.next - self.values.len() as u64
+    }
+}
+
+impl<T: Clone> Shared<T> {
+    fn backlog(&self) -> MutexGuard<'_, Backlog<T>> {
+        self.backlog.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    // The receiver fell behind and this many values were dropped from the
+    // backlog before it could see them. The next receive picks up at the
+    // oldest value still kept.
+    Lagged(u64),
+    // The sender is gone and every value has been received
+    Closed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Lagged(u64),
+    Closed,
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvError::Lagged(skipped) => write!(f, "receiver lagged behind by {skipped} values"),
+            RecvError::Closed => f.write_str("broadcast channel closed"),
+        }
+    }
+}
+
+impl Error for RecvError {}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => f.write_str("no value to receive"),
+            TryRecvError::Lagged(skipped) => RecvError::Lagged(*skipped).fmt(f),
+            TryRecvError::Closed => RecvError::Closed.fmt(f),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
+
+pub struct Sender<T: Clone> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Clone> Sender<T> {
+    pub fn send(&self, data: T) {
+        let mut backlog = self.shared.backlog();
+
+        if backlog.values.len() == self.shared.capacity {
+            backlog.values.pop_front();
+        }
+
+        backlog.values.push_back(data.clone());
+        backlog.next += 1;
+
+        // Still under the backlog lock, so `latest` follows the send order
+        self.shared.latest.update(data);
+        drop(backlog);
+
+        self.shared.sent.notify_all();
+    }
+
+    // New receiver that only gets values sent from now on
+    pub fn subscribe(&self) -> Receiver<T> {
+        Receiver {
+            next: self.shared.backlog().next,
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.backlog().closed = true;
+        self.shared.sent.notify_all();
+    }
+}
+
+pub struct Receiver<T: Clone> {
+    shared: Arc<Shared<T>>,
+    // Sequence number of the next value to receive
+    next: u64,
+}
+
+impl<T: Clone> Receiver<T> {
+    // Most recently sent value, or the initial one, without receiving it
+    pub fn latest(&self) -> T {
+        self.shared.latest.get_data()
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let backlog = self.shared.backlog();
+
+        match take(&mut self.next, &backlog) {
+            Some(result) => result.map_err(TryRecvError::Lagged),
+            None if backlog.closed => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    // Blocks until the next value is sent
+    pub fn recv(&mut self) -> Result<T, RecvError> {
+        let mut backlog = self.shared.backlog();
+
+        loop {
+            match take(&mut self.next, &backlog) {
+                Some(result) => return result.map_err(RecvError::Lagged),
+                None if backlog.closed => return Err(RecvError::Closed),
+                None => {
+                    backlog = self
+                        .shared
+                        .sent
+                        .wait(backlog)
+                        .unwrap_or_else(|err| err.into_inner());
+                }
+            }
+        }
+    }
+}
+
+// `None` if nothing new was sent, `Err` with the number of values missed if
+// they already left the backlog
+fn take<T: Clone>(next: &mut u64, backlog: &Backlog<T>) -> Option<Result<T, u64>> {
+    if *next == backlog.next {
+        return None;
+    }
+
+    let first = backlog.first();
+
+    if *next < first {
+        let skipped = first - *next;
+        *next = first;
+
+        return Some(Err(skipped));
+    }
+
+    let value = backlog.values[(*next - first) as usize].clone();
+    *next += 1;
+
+    Some(Ok(value))
+}
+
+impl<T: Clone> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            next: self.next,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_broadcast() {
+        let (sender, mut receiver) = channel(0, 4);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(receiver.latest(), 0);
+
+        sender.send(1);
+        sender.send(2);
+        let mut late = sender.subscribe();
+        sender.send(3);
+
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.recv(), Ok(2));
+        assert_eq!(late.recv(), Ok(3));
+        assert_eq!(late.try_recv(), Err(TryRecvError::Empty));
+
+        // Values 3 to 5 fall out of the backlog
+        for i in 4..=9 {
+            sender.send(i);
+        }
+        assert_eq!(receiver.latest(), 9);
+        assert_eq!(receiver.recv(), Err(RecvError::Lagged(3)));
+        assert_eq!(receiver.recv(), Ok(6));
+
+        drop(sender);
+        assert_eq!(receiver.recv(), Ok(7));
+        assert_eq!(receiver.try_recv(), Ok(8));
+        assert_eq!(receiver.recv(), Ok(9));
+        assert_eq!(receiver.recv(), Err(RecvError::Closed));
+        assert_eq!(late.try_recv(), Err(TryRecvError::Lagged(2)));
+    }
+
+    #[test]
+    fn test_every_receiver_sees_every_value() {
+        let (sender, receiver) = channel(0, 1_000);
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                let mut receiver = receiver.clone();
+
+                s.spawn(move || {
+                    let mut expected = 1;
+
+                    while let Ok(value) = receiver.recv() {
+                        assert_eq!(value, expected);
+                        expected += 1;
+                    }
+
+                    assert_eq!(expected, 501);
+                });
+            }
+
+            for i in 1..=500 {
+                sender.send(i);
+            }
+
+            drop(sender);
+        });
+    }
+}
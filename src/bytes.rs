@@ -0,0 +1,411 @@
+use alloc::{boxed::Box, sync::Arc, vec, vec::Vec};
+use core::{
+    fmt,
+    ops::{Bound, Deref, RangeBounds},
+};
+
+use crate::cache::{Cache, PinGuard, Policy};
+
+// Shared, immutable byte buffer, like a minimal `bytes::Bytes`: cloning and
+// slicing only bump a reference count.
+#[derive(Clone)]
+pub struct Payload {
+    data: Arc<[u8]>,
+    start: usize,
+    end: usize,
+}
+
+impl Payload {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    // Sub-range of this payload sharing the same buffer. Panics if the
+    // range is out of bounds, like slice indexing.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Self {
+        let start = match range.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(end) => end + 1,
+            Bound::Excluded(end) => *end,
+            Bound::Unbounded => self.len(),
+        };
+
+        assert!(
+            start <= end && end <= self.len(),
+            "range {start}..{end} out of bounds for payload of length {}",
+            self.len()
+        );
+
+        Self {
+            data: self.data.clone(),
+            start: self.start + start,
+            end: self.start + end,
+        }
+    }
+}
+
+impl Deref for Payload {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.data[self.start..self.end]
+    }
+}
+
+impl From<Vec<u8>> for Payload {
+    fn from(data: Vec<u8>) -> Self {
+        let end = data.len();
+
+        Self {
+            data: data.into(),
+            start: 0,
+            end,
+        }
+    }
+}
+
+impl From<&[u8]> for Payload {
+    fn from(data: &[u8]) -> Self {
+        Self::from(data.to_vec())
+    }
+}
+
+impl PartialEq for Payload {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for Payload {}
+
+impl fmt::Debug for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Payload").field("len", &self.len()).finish()
+    }
+}
+
+// Cache for serialized payloads served to many readers. Reads clone a
+// `Payload`, which is a reference count bump however large the buffer.
+pub struct BytesCache<const LEN: usize = 4> {
+    cache: Cache<Payload, LEN>,
+}
+
+impl<const LEN: usize> BytesCache<LEN> {
+    pub fn new(data: impl Into<Payload>) -> Self {
+        Self::with_policy(data, Policy::default())
+    }
+
+    pub fn with_policy(data: impl Into<Payload>, policy: Policy) -> Self {
+        Self {
+            cache: Cache::with_policy(data.into(), policy),
+        }
+    }
+
+    pub fn get(&self) -> Payload {
+        self.cache.get_data()
+    }
+
+    // Sub-range of the current payload, without copying
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Payload {
+        self.get().slice(range)
+    }
+
+    pub fn update(&self, data: impl Into<Payload>) {
+        self.cache.update(data.into());
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.cache.generation()
+    }
+}
+
+// Byte cache for feeds that can't afford an allocation per update. Every
+// slot owns a buffer of `capacity` bytes allocated up front; writers fill
+// the free one in place with `write` and readers borrow the current one
+// through a `BytesGuard`.
+//
+// A guard pins its slot like a reader inside `get_data` does, just for
+// longer. Writers wait for pinned slots once all of them are taken, so drop
+// guards promptly.
+pub struct BytesRingCache<const LEN: usize = 4> {
+    cache: Cache<Frame, LEN>,
+    capacity: usize,
+}
+
+#[derive(Clone)]
+struct Frame {
+    buf: Box<[u8]>,
+    len: usize,
+}
+
+impl Frame {
+    fn new(capacity: usize) -> Self {
+        Self {
+            buf: vec![0; capacity].into_boxed_slice(),
+            len: 0,
+        }
+    }
+}
+
+impl<const LEN: usize> BytesRingCache<LEN> {
+    // Starts out holding an empty payload
+    pub fn new(capacity: usize) -> Self {
+        let cache = Cache::new(Frame::new(capacity));
+        cache.fill_spare_slots(|| Frame::new(capacity));
+
+        Self { cache, capacity }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn read(&self) -> BytesGuard<'_, LEN> {
+        BytesGuard {
+            guard: self.cache.pin_current(),
+        }
+    }
+
+    // Lets `f` fill the buffer of a free slot and publishes the first `len`
+    // bytes, where `len` is what `f` returns. Panics if `len` exceeds the
+    // capacity. The buffer still holds an older payload when `f` gets it.
+    pub fn write(&self, f: impl FnOnce(&mut [u8]) -> usize) {
+        let capacity = self.capacity;
+
+        self.cache.update_recycle(|old| {
+            let mut frame = old.unwrap_or_else(|| Frame::new(capacity));
+
+            let len = f(&mut frame.buf);
+            assert!(
+                len <= capacity,
+                "wrote {len} bytes into a buffer of {capacity}"
+            );
+            frame.len = len;
+
+            frame
+        });
+    }
+
+    // Copies `data` in, for payloads that already exist elsewhere
+    pub fn update(&self, data: &[u8]) {
+        self.write(|buf| {
+            buf[..data.len()].copy_from_slice(data);
+            data.len()
+        });
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.cache.generation()
+    }
+}
+
+// The current payload of a `BytesRingCache` at the time `read` was called
+pub struct BytesGuard<'a, const LEN: usize> {
+    guard: PinGuard<'a, Frame>,
+}
+
+impl<const LEN: usize> Deref for BytesGuard<'_, LEN> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        let frame = self
+            .guard
+            .data()
+            .as_ref()
+            .expect("cache always holds a frame");
+
+        &frame.buf[..frame.len]
+    }
+}
+
+// Encoded message, such as a protobuf, cached as bytes for proxying along
+// with a lazily decoded copy. `get_bytes` never decodes; `get_message` runs
+// `decode` once per generation and memoizes the result, so readers racing
+// on a fresh update may each decode it. Failed decodes are not memoized.
+pub struct EncodedCache<M, D, const LEN: usize = 4>
+where
+    M: Clone,
+{
+    bytes: Cache<Payload, LEN>,
+    decode: D,
+    memo: Cache<(M, u64)>,
+}
+
+impl<M, D, E, const LEN: usize> EncodedCache<M, D, LEN>
+where
+    M: Clone,
+    D: Fn(&[u8]) -> Result<M, E>,
+{
+    pub fn new(data: impl Into<Payload>, decode: D) -> Self {
+        Self {
+            bytes: Cache::new(data.into()),
+            decode,
+            memo: Cache::empty(Policy::default()),
+        }
+    }
+
+    pub fn get_bytes(&self) -> Payload {
+        self.bytes.get_data()
+    }
+
+    pub fn get_message(&self) -> Result<M, E> {
+        let generation = self.bytes.generation();
+
+        if let Some((message, decoded)) = self.memo.try_get_data()
+            && decoded == generation
+        {
+            return Ok(message);
+        }
+
+        let (bytes, generation) = self
+            .bytes
+            .try_get_with_generation()
+            .expect("cache is empty");
+        let message = (self.decode)(&bytes)?;
+
+        self.memo.update_with(|current| match current {
+            Some((current, decoded)) if *decoded > generation => (current.clone(), *decoded),
+            _ => (message.clone(), generation),
+        });
+        // Without this the copies decoded for older generations would stay
+        // in the memo's spare slots until the ring came back to them
+        self.memo.drop_stale();
+
+        Ok(message)
+    }
+
+    // The decoded copy of the previous bytes is dropped on the next
+    // `get_message`
+    pub fn update(&self, data: impl Into<Payload>) {
+        self.bytes.update(data.into());
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.bytes.generation()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes_cache() {
+        let cache: BytesCache = BytesCache::new(b"hello world".to_vec());
+
+        let payload = cache.get();
+        assert_eq!(&*payload, b"hello world");
+
+        let world = cache.slice(6..);
+        assert_eq!(&*world, b"world");
+        assert_eq!(&*world.slice(..=2), b"wor");
+        assert!(Arc::ptr_eq(&payload.data, &world.data));
+
+        cache.update(&b"bye"[..]);
+        assert_eq!(&*cache.get(), b"bye");
+        assert_eq!(&*world, b"world");
+    }
+
+    #[test]
+    fn test_bytes_ring_cache() {
+        let cache: BytesRingCache = BytesRingCache::new(8);
+        assert!(cache.read().is_empty());
+
+        let guard = cache.read();
+        let first = guard.as_ptr();
+
+        cache.update(b"tick 1");
+        cache.write(|buf| {
+            buf[..6].copy_from_slice(b"tick 2");
+            6
+        });
+
+        // The guard keeps its slot and payload while writers move on
+        assert!(guard.is_empty());
+        assert_eq!(&*cache.read(), b"tick 2");
+        drop(guard);
+
+        // Every slot was allocated up front and is reused from now on
+        let mut buffers = Vec::new();
+
+        for i in 0..8u8 {
+            cache.update(&[i; 3]);
+
+            let guard = cache.read();
+            assert_eq!(&*guard, &[i; 3]);
+            buffers.push(guard.as_ptr());
+        }
+
+        buffers.push(first);
+        buffers.sort();
+        buffers.dedup();
+        assert_eq!(buffers.len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "wrote 9 bytes into a buffer of 8")]
+    fn test_bytes_ring_cache_overflow() {
+        BytesRingCache::<4>::new(8).write(|_| 9);
+    }
+
+    #[test]
+    fn test_encoded_cache() {
+        use core::{cell::Cell, str};
+
+        let decodes = Cell::new(0);
+        let cache: EncodedCache<u32, _> = EncodedCache::new(b"42".to_vec(), |bytes: &[u8]| {
+            decodes.set(decodes.get() + 1);
+            str::from_utf8(bytes).unwrap().parse::<u32>()
+        });
+
+        assert_eq!(&*cache.get_bytes(), b"42");
+        assert_eq!(decodes.get(), 0);
+
+        assert_eq!(cache.get_message(), Ok(42));
+        assert_eq!(cache.get_message(), Ok(42));
+        assert_eq!(decodes.get(), 1);
+
+        cache.update(b"x".to_vec());
+        assert!(cache.get_message().is_err());
+        assert!(cache.get_message().is_err());
+        assert_eq!(&*cache.get_bytes(), b"x");
+
+        cache.update(b"7".to_vec());
+        assert_eq!(cache.get_message(), Ok(7));
+        assert_eq!(decodes.get(), 4);
+    }
+
+    #[test]
+    fn test_encoded_cache_drops_old_messages() {
+        // Every decoded message holds a clone of `token`
+        let token = Arc::new(());
+        let cache: EncodedCache<Arc<()>, _> =
+            EncodedCache::new(b"a".to_vec(), |_: &[u8]| Ok::<_, ()>(Arc::clone(&token)));
+
+        drop(cache.get_message());
+        assert_eq!(Arc::strong_count(&token), 2);
+
+        for data in [b"b", b"c", b"d"] {
+            cache.update(data.to_vec());
+            drop(cache.get_message());
+
+            // Only the memoized copy of the current message is left
+            assert_eq!(Arc::strong_count(&token), 2);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_slice_out_of_bounds() {
+        Payload::from(b"abc".to_vec()).slice(1..5);
+    }
+}
@@ -0,0 +1,27 @@
+use crate::utils::Backoff;
+
+// How writers wait while the writer lock is held or every inactive slot is
+// still pinned by readers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackoffPolicy {
+    // Exponential busy-wait. Lowest latency, but keeps the core busy.
+    #[default]
+    Spin,
+    // Exponential busy-wait that starts yielding the thread to the OS
+    // scheduler once spinning stops paying off. Better on shared hosts.
+    Yield,
+}
+
+impl BackoffPolicy {
+    pub(crate) fn wait(self, backoff: &Backoff) {
+        // Spinning only slows the model checker down
+        if cfg!(any(loom, shuttle)) {
+            return crate::sync::spin_loop();
+        }
+
+        match self {
+            BackoffPolicy::Spin => backoff.spin(),
+            BackoffPolicy::Yield => backoff.snooze(),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use core::marker::PhantomData;
+
+use super::{BackoffPolicy, Cache, LockPolicy, Policy};
+
+// Step-by-step `Cache` configuration, see `Cache::builder`. The slot count
+// is part of the cache type, so `slots` changes the builder's type too.
+pub struct CacheBuilder<T, const LEN: usize = 4> {
+    policy: Policy,
+    data: PhantomData<fn() -> T>,
+}
+
+impl<T: Clone> Cache<T> {
+    pub fn builder() -> CacheBuilder<T> {
+        CacheBuilder {
+            policy: Policy::default(),
+            data: PhantomData,
+        }
+    }
+}
+
+impl<T: Clone, const LEN: usize> CacheBuilder<T, LEN> {
+    // Must be a power of two, checked when the cache is built
+    pub fn slots<const SLOTS: usize>(self) -> CacheBuilder<T, SLOTS> {
+        CacheBuilder {
+            policy: self.policy,
+            data: PhantomData,
+        }
+    }
+
+    pub fn backoff(mut self, backoff: BackoffPolicy) -> Self {
+        self.policy.backoff = backoff;
+        self
+    }
+
+    pub fn lock(mut self, lock: LockPolicy) -> Self {
+        self.policy.lock = lock;
+        self
+    }
+
+    pub fn coalesce(mut self, coalesce: bool) -> Self {
+        self.policy.coalesce = coalesce;
+        self
+    }
+
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn build(self, data: T) -> Cache<T, LEN> {
+        Cache::with_policy(data, self.policy)
+    }
+}
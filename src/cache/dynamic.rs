@@ -0,0 +1,335 @@
+use alloc::boxed::Box;
+use core::{fmt, marker::PhantomData, ptr};
+
+use crate::{
+    sync::{
+        self,
+        atomic::{AtomicPtr, AtomicUsize},
+    },
+    utils::{Backoff, Padded},
+};
+
+use super::{
+    BackoffPolicy, LockPolicy,
+    item::{Item, PinGuard},
+    lock::WriteLock,
+    ordering::{ACQUIRE, RELAXED, RELEASE},
+};
+
+// Most banks a `DynCache` can grow to. Bank 0 holds the initial slots and
+// every further bank doubles the total.
+const MAX_BANKS: usize = 8;
+
+// Rounds of finding every inactive slot pinned before a writer grows the
+// cache rather than keep waiting. A few microseconds of spinning.
+const GROW_AFTER: u32 = 16;
+
+// Like `Cache`, but with as many slots as the readers need. When every
+// inactive slot stays pinned for a few rounds of backoff, the writer allocates a
+// new bank of slots and publishes into it instead of waiting, doubling the
+// slot count up to the chosen maximum. Slots are never freed before the
+// cache is dropped, so the count only ever grows.
+pub struct DynCache<T> {
+    // Generation of the current value above the slot holding it
+    index: Padded<AtomicUsize>,
+    banks: [AtomicPtr<Item<T>>; MAX_BANKS],
+    // Only changed with the writer lock held
+    slots: AtomicUsize,
+    initial: usize,
+    max: usize,
+    slot_bits: u32,
+    writing: WriteLock,
+    backoff: BackoffPolicy,
+    _marker: PhantomData<Box<[Item<T>]>>,
+}
+
+// Safety: see `Cache`, the banks are only freed on drop
+unsafe impl<T: Clone + Send + Sync> Sync for DynCache<T> {}
+
+impl<T: Clone> DynCache<T> {
+    // Starts with 4 slots and grows up to 16
+    pub fn new(data: T) -> Self {
+        Self::with_slots(data, 4, 16)
+    }
+
+    // Starts with `initial` slots and grows up to `max`. Both must be powers
+    // of two, with `initial` at least 2 and `max` at most `initial << 7`.
+    pub fn with_slots(data: T, initial: usize, max: usize) -> Self {
+        assert!(
+            initial >= 2 && initial.is_power_of_two(),
+            "initial slot count must be a power of two of at least 2"
+        );
+        assert!(
+            max >= initial && max.is_power_of_two() && max <= initial << (MAX_BANKS - 1),
+            "max slot count must be a power of two between initial and initial << 7"
+        );
+
+        let banks = core::array::from_fn(|_| AtomicPtr::new(ptr::null_mut()));
+        let cache = Self {
+            index: Padded::new(AtomicUsize::new(0)),
+            banks,
+            slots: AtomicUsize::new(initial),
+            initial,
+            max,
+            slot_bits: max.trailing_zeros(),
+            writing: WriteLock::new(LockPolicy::default()),
+            backoff: BackoffPolicy::default(),
+            _marker: PhantomData,
+        };
+
+        cache.banks[0].store(Self::alloc_bank(initial), RELEASE);
+
+        let item = cache.item(0);
+        item.set_generation(0);
+        // Safety: nobody else can see the cache yet
+        unsafe { item.replace(Some(data)) };
+
+        cache
+    }
+
+    pub fn get_data(&self) -> T {
+        let guard = self.pin();
+
+        match guard.data() {
+            Some(data) => data.clone(),
+            None => unreachable!("the current slot always holds a value"),
+        }
+    }
+
+    pub fn update(&self, data: T) {
+        let (_guard, _) = self.writing.lock(self.backoff);
+
+        let index = self.next_free_slot();
+        let item = self.item(index);
+
+        // Safety: the slot is retired and drained
+        let old = unsafe { item.replace(Some(data)) };
+
+        let generation =
+            ((self.index.load(RELAXED) >> self.slot_bits) + 1) & (usize::MAX >> self.slot_bits);
+
+        item.set_generation(generation);
+        self.index
+            .store((generation << self.slot_bits) | index, RELEASE);
+        item.release();
+
+        drop(old);
+    }
+
+    // Generation of the current value. Bumped by every `update`.
+    pub fn generation(&self) -> u64 {
+        (self.index.load(ACQUIRE) >> self.slot_bits) as u64
+    }
+
+    // Slots allocated so far
+    pub fn slots(&self) -> usize {
+        self.slots.load(RELAXED)
+    }
+
+    pub fn max_slots(&self) -> usize {
+        self.max
+    }
+
+    fn pin(&self) -> PinGuard<'_, T> {
+        let mask = self.max - 1;
+
+        loop {
+            // Acquire even with the `fences` feature: a stamp may point into
+            // a bank allocated after this thread last looked at `banks`
+            let stamp = self.index.load(ACQUIRE);
+            let item = self.item(stamp & mask);
+
+            if let Some(guard) = item.pin_generation(stamp >> self.slot_bits) {
+                return guard;
+            }
+
+            // As in `Cache::pin`, wait with loads rather than pinning again
+            // and again while the writer drains the slot
+            while self.index.load(ACQUIRE) == stamp && item.is_retiring() {
+                sync::spin_loop();
+            }
+        }
+    }
+
+    fn item(&self, index: usize) -> &Item<T> {
+        let (bank, offset) = self.locate(index);
+        let items = self.banks[bank].load(ACQUIRE);
+
+        // Safety: only slots of published banks are ever handed out, and
+        // banks live as long as the cache
+        unsafe { &*items.add(offset) }
+    }
+
+    fn alloc_bank(len: usize) -> *mut Item<T> {
+        let items: Box<[Item<T>]> = (0..len).map(|_| Item::new()).collect();
+
+        Box::into_raw(items).cast()
+    }
+
+    // Must be called with the writer lock held. Returns a retired and
+    // drained slot, growing the cache if readers keep every slot pinned.
+    fn next_free_slot(&self) -> usize {
+        let current = self.index.load(RELAXED) & (self.max - 1);
+        let slots = self.slots.load(RELAXED);
+        let backoff = Backoff::new();
+        let mut rounds = 0;
+
+        loop {
+            for offset in 1..slots {
+                let index = (current + offset) % slots;
+                let item = self.item(index);
+
+                if item.retire() {
+                    return index;
+                }
+
+                item.release();
+            }
+
+            rounds += 1;
+
+            if rounds >= GROW_AFTER && slots < self.max {
+                return self.grow(slots);
+            }
+
+            self.backoff.wait(&backoff);
+        }
+    }
+
+    // Allocates the next bank and returns its first slot
+    fn grow(&self, slots: usize) -> usize {
+        let (bank, _) = self.locate(slots);
+
+        self.banks[bank].store(Self::alloc_bank(self.bank_len(bank)), RELEASE);
+        self.slots.store(slots * 2, RELAXED);
+
+        let item = self.item(slots);
+        item.retire();
+
+        slots
+    }
+}
+
+impl<T> DynCache<T> {
+    // Bank and offset within it of slot `index`
+    fn locate(&self, index: usize) -> (usize, usize) {
+        if index < self.initial {
+            return (0, index);
+        }
+
+        let bank = (index / self.initial).ilog2() as usize + 1;
+
+        (bank, index - (self.initial << (bank - 1)))
+    }
+
+    fn bank_len(&self, bank: usize) -> usize {
+        if bank == 0 {
+            self.initial
+        } else {
+            self.initial << (bank - 1)
+        }
+    }
+}
+
+impl<T> Drop for DynCache<T> {
+    fn drop(&mut self) {
+        for bank in 0..MAX_BANKS {
+            let items = self.banks[bank].load(RELAXED);
+
+            if items.is_null() {
+                break;
+            }
+
+            let len = self.bank_len(bank);
+
+            // Safety: allocated by `alloc_bank` with this length
+            drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(items, len)) });
+        }
+    }
+}
+
+impl<T: Clone + fmt::Debug> fmt::Debug for DynCache<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynCache")
+            .field("data", &self.get_data())
+            .field("generation", &self.generation())
+            .field("slots", &self.slots())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Barrier, thread};
+
+    use super::*;
+
+    #[test]
+    fn test_dyn_cache() {
+        let cache = DynCache::new(String::from("a"));
+        assert_eq!(cache.get_data(), "a");
+
+        for i in 0..10 {
+            cache.update(i.to_string());
+        }
+        assert_eq!(cache.get_data(), "9");
+        assert_eq!(cache.generation(), 10);
+        assert_eq!(cache.slots(), 4);
+    }
+
+    #[test]
+    fn test_dyn_cache_grows_under_pinned_slots() {
+        let cache = DynCache::with_slots(0u64, 2, 8);
+
+        // Readers parked on every slot force the writer to grow instead of
+        // waiting for them
+        let mut guards = vec![cache.pin()];
+
+        for value in 1..=7 {
+            cache.update(value);
+            guards.push(cache.pin());
+        }
+
+        assert_eq!(cache.slots(), 8);
+        assert_eq!(cache.get_data(), 7);
+        assert_eq!(
+            guards
+                .iter()
+                .map(|guard| guard.data().unwrap_or_default())
+                .collect::<Vec<_>>(),
+            (0..=7).collect::<Vec<_>>()
+        );
+
+        // Full, so the next writer has to wait for a reader to leave
+        let barrier = Barrier::new(2);
+        thread::scope(|s| {
+            s.spawn(|| {
+                barrier.wait();
+                cache.update(8);
+            });
+
+            barrier.wait();
+            guards.remove(1);
+        });
+
+        assert_eq!(cache.get_data(), 8);
+        assert_eq!(cache.slots(), 8);
+        drop(guards);
+
+        let cache = DynCache::new(vec![0u8; 64]);
+        thread::scope(|s| {
+            for _ in 0..2 {
+                s.spawn(|| {
+                    for _ in 0..1_000 {
+                        assert_eq!(cache.get_data().len(), 64);
+                    }
+                });
+            }
+
+            for value in 0..1_000 {
+                cache.update(vec![value as u8; 64]);
+            }
+        });
+        assert!(cache.slots() <= 16);
+    }
+}
@@ -0,0 +1,126 @@
+use core::{error::Error, fmt};
+
+// The value could not be published in time by `Cache::update_timeout`, see
+// `UpdateError`. Gives the value back to the caller.
+#[derive(Clone, PartialEq, Eq)]
+pub struct UpdateTimeout<T>(pub T);
+
+impl<T> UpdateTimeout<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for UpdateTimeout<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpdateTimeout").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for UpdateTimeout<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("timed out waiting for the writer lock or a free slot")
+    }
+}
+
+impl<T> Error for UpdateTimeout<T> {}
+
+// Returned by `Cache::try_publish` when the validator refuses the value.
+// Gives the value back along with the validator's error.
+#[cfg(feature = "std")]
+pub struct Rejected<T> {
+    pub data: T,
+    pub error: Box<dyn Error + Send + Sync>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Rejected<T> {
+    pub fn into_inner(self) -> T {
+        self.data
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> fmt::Debug for Rejected<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rejected")
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> fmt::Display for Rejected<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value rejected by the validator: {}", self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Error for Rejected<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.error)
+    }
+}
+
+// Returned by `Cache::update_timeout`, which gives the value back whether it
+// timed out or the validator refused it
+#[cfg(feature = "std")]
+pub enum UpdateError<T> {
+    Timeout(UpdateTimeout<T>),
+    Rejected(Rejected<T>),
+}
+
+#[cfg(feature = "std")]
+impl<T> UpdateError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            UpdateError::Timeout(timeout) => timeout.into_inner(),
+            UpdateError::Rejected(rejected) => rejected.into_inner(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> fmt::Debug for UpdateError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateError::Timeout(timeout) => f.debug_tuple("Timeout").field(timeout).finish(),
+            UpdateError::Rejected(rejected) => f.debug_tuple("Rejected").field(rejected).finish(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> fmt::Display for UpdateError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateError::Timeout(timeout) => timeout.fmt(f),
+            UpdateError::Rejected(rejected) => rejected.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Error for UpdateError<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UpdateError::Timeout(_) => None,
+            UpdateError::Rejected(rejected) => rejected.source(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> From<UpdateTimeout<T>> for UpdateError<T> {
+    fn from(timeout: UpdateTimeout<T>) -> Self {
+        UpdateError::Timeout(timeout)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> From<Rejected<T>> for UpdateError<T> {
+    fn from(rejected: Rejected<T>) -> Self {
+        UpdateError::Rejected(rejected)
+    }
+}
@@ -0,0 +1,55 @@
+use crate::sync::Mutex;
+
+use super::Cache;
+
+// Group generation of the last update committed through an `UpdateGroup`,
+// along with the cache generation it was published under. Like the
+// provenance tag, updates made outside a group are told apart by their
+// generation.
+pub(crate) struct GroupTag {
+    last: Mutex<Option<(u64, usize)>>,
+}
+
+impl GroupTag {
+    pub(crate) fn new() -> Self {
+        Self {
+            last: Mutex::new(None),
+        }
+    }
+
+    // Must be called with the writer lock held, ahead of publishing
+    // `generation`
+    pub(crate) fn set(&self, group: u64, generation: usize) {
+        *self.last.lock().unwrap_or_else(|err| err.into_inner()) = Some((group, generation));
+    }
+
+    fn get(&self, generation: u64) -> Option<u64> {
+        match *self.last.lock().unwrap_or_else(|err| err.into_inner()) {
+            Some((group, tagged)) if tagged as u64 == generation => Some(group),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    // Generation of the `UpdateGroup` commit that published the current
+    // value, `None` if it was published by a plain update. Caches
+    // returning the same group generation hold values committed together.
+    pub fn group_generation(&self) -> Option<u64> {
+        loop {
+            let generation = self.generation();
+            let group = self.group.get(generation);
+
+            if self.generation() == generation {
+                return group;
+            }
+        }
+    }
+
+    // The current value along with its group generation, read together
+    pub fn get_with_group(&self) -> Option<(T, Option<u64>)> {
+        let (data, generation) = self.try_get_with_generation()?;
+
+        Some((data, self.group.get(generation)))
+    }
+}
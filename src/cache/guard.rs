@@ -0,0 +1,194 @@
+use core::ops::Deref;
+#[cfg(all(feature = "log", debug_assertions))]
+use std::time::Instant;
+#[cfg(feature = "log")]
+use std::{sync::atomic::AtomicU64, time::Duration};
+
+#[cfg(feature = "log")]
+use super::ordering::RELAXED;
+use super::{Cache, PinGuard};
+
+// Borrow of the current value from `Cache::read`, keeping its slot pinned
+// until dropped. A writer lapping the ring back to the slot waits for the
+// guard, so code holding one for long should `refresh` it now and then.
+pub struct Guard<'a, T: Clone, const LEN: usize = 4> {
+    cache: &'a Cache<T, LEN>,
+    guard: PinGuard<'a, T>,
+    generation: usize,
+    #[cfg(all(feature = "log", debug_assertions))]
+    pinned: Instant,
+}
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    // Pins the current value and borrows it. Panics if the cache is empty.
+    pub fn read(&self) -> Guard<'_, T, LEN> {
+        let (guard, generation) = self.pin();
+        self.counters.read();
+
+        assert!(guard.data().is_some(), "cache is empty");
+
+        Guard {
+            cache: self,
+            guard,
+            generation,
+            #[cfg(all(feature = "log", debug_assertions))]
+            pinned: Instant::now(),
+        }
+    }
+}
+
+impl<'a, T: Clone, const LEN: usize> Guard<'a, T, LEN> {
+    // Moves the guard to the current value, letting writers have the slot
+    // it was pinning. Returns whether the value changed. Panics if the cache
+    // was emptied in the meantime.
+    pub fn refresh(&mut self) -> bool {
+        let refreshed = self.cache.read();
+        let changed = refreshed.generation != self.generation;
+
+        // Dropping the old guard unpins its slot
+        *self = refreshed;
+
+        changed
+    }
+
+    // Generation of the value the guard borrows
+    pub fn generation(&self) -> u64 {
+        self.generation as u64
+    }
+
+    #[cfg(all(feature = "log", debug_assertions))]
+    fn check_held(&self) {
+        let threshold = GUARD_WARNING.load(RELAXED);
+
+        if threshold == 0 {
+            return;
+        }
+
+        let held = self.pinned.elapsed();
+
+        if held > Duration::from_nanos(threshold) {
+            log::warn!(
+                target: "sloth",
+                "cache guard held for {held:?}, writers lapping the ring wait for it; \
+                 consider Guard::refresh"
+            );
+        }
+    }
+}
+
+impl<T: Clone, const LEN: usize> Deref for Guard<'_, T, LEN> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self.guard.data() {
+            Some(data) => data,
+            None => unreachable!("checked by `Cache::read`"),
+        }
+    }
+}
+
+impl<T: Clone, const LEN: usize> Drop for Guard<'_, T, LEN> {
+    fn drop(&mut self) {
+        #[cfg(all(feature = "log", debug_assertions))]
+        self.check_held();
+    }
+}
+
+// Threshold in nanoseconds, zero when off
+#[cfg(feature = "log")]
+static GUARD_WARNING: AtomicU64 = AtomicU64::new(0);
+
+// Makes debug builds log a warning, under the `sloth` target, whenever a
+// `Guard` is dropped or refreshed after being held longer than `threshold`,
+// to find the readers that hold writers up. `None` turns it off again.
+// Release builds ignore it.
+#[cfg(feature = "log")]
+pub fn warn_on_long_guards(threshold: Option<Duration>) {
+    let nanos = threshold.map_or(0, |threshold| {
+        u64::try_from(threshold.as_nanos())
+            .unwrap_or(u64::MAX)
+            .max(1)
+    });
+
+    GUARD_WARNING.store(nanos, RELAXED);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_guard_refresh() {
+        let cache: Cache<String, 2> = Cache::new(String::from("a"));
+
+        let mut guard = cache.read();
+        assert_eq!(*guard, "a");
+        assert!(!guard.refresh());
+
+        // A writer lapping the ring waits for the guard until it refreshes
+        thread::scope(|s| {
+            let writer = s.spawn(|| {
+                cache.update(String::from("b"));
+                cache.update(String::from("c"));
+            });
+
+            while cache.generation() < 1 {
+                thread::yield_now();
+            }
+            assert!(!writer.is_finished());
+            assert_eq!(guard.len(), 1);
+
+            assert!(guard.refresh());
+            assert_eq!(*guard, "b");
+        });
+
+        assert!(guard.refresh());
+        assert_eq!((guard.as_str(), guard.generation()), ("c", 2));
+    }
+
+    #[cfg(all(feature = "log", debug_assertions))]
+    #[test]
+    fn test_warn_on_long_guards() {
+        use std::{sync::Mutex, time::Duration};
+
+        struct Recorder(Mutex<Vec<String>>);
+
+        impl log::Log for Recorder {
+            fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record<'_>) {
+                if record.target() == "sloth" {
+                    let mut records = self.0.lock().unwrap();
+                    records.push(format!("{} {}", record.level(), record.args()));
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+
+        log::set_logger(&RECORDER).unwrap();
+        log::set_max_level(log::LevelFilter::Warn);
+
+        let cache: Cache<u64, 2> = Cache::new(0);
+
+        warn_on_long_guards(Some(Duration::from_millis(10)));
+        let guard = cache.read();
+        thread::sleep(Duration::from_millis(20));
+        drop(guard);
+        warn_on_long_guards(None);
+
+        let records = RECORDER.0.lock().unwrap();
+        assert!(
+            records
+                .iter()
+                .any(|record| record.starts_with("WARN cache guard held for")),
+            "{records:?}"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use core::fmt;
+
+use super::{Cache, item::INVALID_GENERATION};
+
+impl<T: Clone + Default, const LEN: usize> Default for Cache<T, LEN> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Clone, const LEN: usize> From<T> for Cache<T, LEN> {
+    fn from(data: T) -> Self {
+        Self::new(data)
+    }
+}
+
+// Fresh cache holding the current value, with the same policy and validator
+impl<T: Clone, const LEN: usize> Clone for Cache<T, LEN> {
+    fn clone(&self) -> Self {
+        let cache = match self.try_get_data() {
+            Some(data) => Self::with_policy(data, self.policy()),
+            None => Self::empty(self.policy()),
+        };
+
+        #[cfg(feature = "std")]
+        self.copy_validator(&cache);
+
+        cache
+    }
+}
+
+// Shows where the current value lives and what each slot is doing, without
+// needing `T: Debug`
+impl<T: Clone, const LEN: usize> fmt::Debug for Cache<T, LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Slot {
+            readers: usize,
+            retiring: bool,
+            generation: Option<usize>,
+        }
+
+        impl fmt::Debug for Slot {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("Slot")
+                    .field("readers", &self.readers)
+                    .field("retiring", &self.retiring)
+                    .field("generation", &self.generation)
+                    .finish()
+            }
+        }
+
+        let slots = self.items.iter().map(|item| {
+            let (readers, retiring, generation) = item.state();
+
+            Slot {
+                readers,
+                retiring,
+                generation: (generation != INVALID_GENERATION).then_some(generation),
+            }
+        });
+
+        f.debug_struct("Cache")
+            .field("index", &self.index())
+            .field("generation", &self.generation())
+            .field("policy", &self.policy())
+            .field("slots", &slots.collect::<alloc::vec::Vec<_>>())
+            .finish()
+    }
+}
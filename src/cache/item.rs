@@ -0,0 +1,258 @@
+use crate::{sync::UnsafeCell, utils::Padded};
+
+#[cfg(target_has_atomic = "64")]
+use crate::sync::atomic::AtomicU64 as AtomicWord;
+#[cfg(not(target_has_atomic = "64"))]
+use crate::sync::atomic::AtomicUsize as AtomicWord;
+
+use super::ordering::{self, ACQ_REL, ACQUIRE, PIN, RELAXED, RELEASE, SEQ_CST, UNPIN};
+
+// A slot of the ring. Readers pin it through the reader count in `state`;
+// writers may only touch `data` and the generation once they have marked it
+// retiring and seen the count drop to zero.
+//
+// The reader count, the retiring flag and the generation share one word, so
+// a reader pins the slot, checks it isn't retiring and checks it holds the
+// generation it is after with a single `fetch_add`. The writer sets the flag
+// with a `fetch_or` on the same word that also returns the count, and since
+// all of them modify a single location, one of the two always sees the
+// other: either the reader bounces, or the writer waits for it to finish.
+pub(crate) struct Item<T> {
+    state: Padded<AtomicWord>,
+    data: UnsafeCell<Option<T>>,
+}
+
+#[cfg(target_has_atomic = "64")]
+type Word = u64;
+#[cfg(not(target_has_atomic = "64"))]
+type Word = usize;
+
+// From the bottom up: the reader count, the retiring flag, the generation.
+// With 64-bit atomics that is 32 bits of readers and 31 of generation,
+// otherwise 16 and 15.
+const COUNT_BITS: u32 = Word::BITS / 2;
+const COUNT_MASK: Word = (1 << COUNT_BITS) - 1;
+const RETIRING: Word = 1 << COUNT_BITS;
+const GENERATION_SHIFT: u32 = COUNT_BITS + 1;
+
+// Readers past this many are turned away until others unpin, so the count
+// never carries into the retiring flag. Each of them bumps the count before
+// seeing it and takes it back, which leaves the other half of the count for
+// readers racing in at once. Only reachable with 16 bits of readers, as
+// 32768 guards held on one slot.
+const MAX_READERS: Word = COUNT_MASK / 2;
+
+// Generations are stored modulo this, which leaves the all-ones pattern free
+// for `INVALID_GENERATION`. A reader holding a stamp that old would need
+// billions of publications to land between reading the index and pinning
+// the slot to be fooled.
+const GENERATION_MODULUS: Word = (1 << (Word::BITS - GENERATION_SHIFT)) - 1;
+
+// Generation of a slot whose value doesn't belong to any publication
+pub(crate) const INVALID_GENERATION: usize = usize::MAX;
+
+fn pack(generation: usize) -> Word {
+    let generation = if generation == INVALID_GENERATION {
+        GENERATION_MODULUS
+    } else {
+        generation as Word % GENERATION_MODULUS
+    };
+
+    generation << GENERATION_SHIFT
+}
+
+fn unpack(state: Word) -> usize {
+    match state >> GENERATION_SHIFT {
+        GENERATION_MODULUS => INVALID_GENERATION,
+        generation => generation as usize,
+    }
+}
+
+impl<T> Item<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Padded::new(AtomicWord::new(pack(INVALID_GENERATION))),
+            data: UnsafeCell::new(None),
+        }
+    }
+
+    // Returns `None` if the slot is being retired by a writer, in which case
+    // the caller must not touch `data` and should re-read the index.
+    #[cfg(test)]
+    pub(crate) fn pin(&self) -> Option<PinGuard<'_, T>> {
+        let state = self.state.fetch_add(1, PIN);
+
+        let guard = PinGuard { item: self };
+
+        if state & RETIRING != 0 {
+            return None;
+        }
+
+        ordering::pinned();
+        Some(guard)
+    }
+
+    // Like `pin`, but also returns `None` if the slot doesn't hold the value
+    // published under `generation`, e.g. because writers lapped the ring
+    // since the caller read the index, or if `MAX_READERS` already pin it
+    pub(crate) fn pin_generation(&self, generation: usize) -> Option<PinGuard<'_, T>> {
+        let state = self.state.fetch_add(1, PIN);
+
+        let guard = PinGuard { item: self };
+
+        if state & COUNT_MASK >= MAX_READERS
+            || state & RETIRING != 0
+            || state & !(COUNT_MASK | RETIRING) != pack(generation)
+        {
+            return None;
+        }
+
+        ordering::pinned();
+        Some(guard)
+    }
+
+    // Marks the slot as retiring, turning away new readers. Returns `true`
+    // once no reader is left, at which point the writer owns `data`.
+    pub(crate) fn retire(&self) -> bool {
+        self.state.fetch_or(RETIRING, ACQ_REL) & COUNT_MASK == 0
+    }
+
+    // SeqCst for registered readers, see `Readers::announced`
+    pub(crate) fn is_retiring(&self) -> bool {
+        self.state.load(SEQ_CST) & RETIRING != 0
+    }
+
+    pub(crate) fn is_drained(&self) -> bool {
+        self.state.load(ACQUIRE) & COUNT_MASK == 0
+    }
+
+    // Lets readers back in, publishing any write made to `data` meanwhile
+    pub(crate) fn release(&self) {
+        self.state.fetch_and(!RETIRING, RELEASE);
+    }
+
+    // Safety: the slot must be retired and drained, see `retire`
+    pub(crate) unsafe fn replace(&self, data: Option<T>) -> Option<T> {
+        self.data
+            .with_mut(|slot| core::mem::replace(unsafe { &mut *slot }, data))
+    }
+
+    // Safety: see `replace`
+    pub(crate) unsafe fn peek(&self) -> &Option<T> {
+        self.data.with(|data| unsafe { &*data })
+    }
+
+    // Safety: see `replace`
+    pub(crate) unsafe fn take(&self) -> Option<T> {
+        unsafe { self.replace(None) }
+    }
+
+    // Generation under which `data` was published, modulo
+    // `GENERATION_MODULUS`
+    pub(crate) fn generation(&self) -> usize {
+        unpack(self.state.load(ACQUIRE))
+    }
+
+    // Readers bouncing off the slot keep changing the count, so the
+    // generation is swapped in with a CAS
+    pub(crate) fn set_generation(&self, generation: usize) {
+        let generation = pack(generation);
+        let mut state = self.state.load(RELAXED);
+
+        loop {
+            let new = (state & (COUNT_MASK | RETIRING)) | generation;
+
+            match self
+                .state
+                .compare_exchange_weak(state, new, RELEASE, RELAXED)
+            {
+                Ok(_) => return,
+                Err(current) => state = current,
+            }
+        }
+    }
+
+    // Snapshot for `Debug`, stale as soon as it is taken
+    pub(crate) fn state(&self) -> (usize, bool, usize) {
+        let state = self.state.load(ACQUIRE);
+
+        (
+            (state & COUNT_MASK) as usize,
+            state & RETIRING != 0,
+            unpack(state),
+        )
+    }
+
+    #[cfg(test)]
+    pub(crate) fn readers(&self) -> &AtomicWord {
+        &self.state
+    }
+}
+
+// Unpins the slot when dropped, so a panicking `T::clone` can't leave it
+// pinned forever
+pub(crate) struct PinGuard<'a, T> {
+    item: &'a Item<T>,
+}
+
+impl<T> PinGuard<'_, T> {
+    pub(crate) fn data(&self) -> &Option<T> {
+        // Safety: the slot is pinned and wasn't retiring when it got pinned,
+        // so no writer touches `data` until the guard is dropped
+        self.item.data.with(|data| unsafe { &*data })
+    }
+}
+
+impl<T> Drop for PinGuard<'_, T> {
+    fn drop(&mut self) {
+        ordering::unpinning();
+        self.item.state.fetch_sub(1, UNPIN);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packed_state() {
+        let item: Item<u32> = Item::new();
+        assert_eq!(item.state(), (0, false, INVALID_GENERATION));
+        assert!(item.pin_generation(INVALID_GENERATION).is_some());
+
+        item.set_generation(7);
+        let reader = item.pin_generation(7).unwrap();
+        assert!(item.pin_generation(8).is_none());
+
+        // The generation is swapped in under pinned readers
+        assert!(!item.retire());
+        item.set_generation(GENERATION_MODULUS as usize + 8);
+        assert_eq!(item.state(), (1, true, 8));
+
+        drop(reader);
+        assert!(item.is_drained());
+        item.release();
+
+        // Stamps a whole modulus apart land on the same packed generation,
+        // but never on the invalid one
+        assert!(item.pin_generation(8).is_some());
+        item.set_generation(GENERATION_MODULUS as usize);
+        assert_eq!(item.generation(), 0);
+    }
+
+    #[test]
+    fn test_reader_limit() {
+        let item: Item<u32> = Item::new();
+        item.readers().fetch_add(MAX_READERS, RELAXED);
+
+        // Turned away and its pin taken back, the flag stays clear
+        assert!(item.pin_generation(INVALID_GENERATION).is_none());
+        assert_eq!(
+            item.state(),
+            (MAX_READERS as usize, false, INVALID_GENERATION)
+        );
+
+        item.readers().fetch_sub(1, RELAXED);
+        assert!(item.pin_generation(INVALID_GENERATION).is_some());
+    }
+}
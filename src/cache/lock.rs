@@ -0,0 +1,238 @@
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+
+#[cfg(feature = "std")]
+use crate::sync::{
+    Arc, Mutex,
+    thread::{self, Thread},
+};
+use crate::{
+    sync::atomic::{AtomicBool, AtomicUsize},
+    utils::{Backoff, Padded},
+};
+
+use super::{
+    BackoffPolicy,
+    ordering::{ACQUIRE, RELAXED, RELEASE},
+};
+
+// How writers wait for each other on the writer lock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LockPolicy {
+    // Writers wait on the lock according to the cache's `BackoffPolicy`.
+    #[default]
+    Spin,
+    // Writers spin for a bounded number of rounds and then park. Parked
+    // writers are woken in FIFO order and the lock is handed to them
+    // directly, so spinning writers can't barge ahead of them.
+    #[cfg(feature = "std")]
+    Park,
+    // Writers take a ticket and are served strictly in arrival order, so
+    // updates are published in the order their `update` calls started.
+    Ticket,
+}
+
+pub(crate) enum WriteLock {
+    Spin {
+        locked: Padded<AtomicBool>,
+    },
+    #[cfg(feature = "std")]
+    Park {
+        locked: Padded<AtomicBool>,
+        parked: Mutex<VecDeque<Waiter>>,
+    },
+    Ticket {
+        next: Padded<AtomicUsize>,
+        serving: Padded<AtomicUsize>,
+    },
+}
+
+#[cfg(feature = "std")]
+pub(crate) struct Waiter {
+    thread: Thread,
+    granted: Arc<AtomicBool>,
+}
+
+impl WriteLock {
+    pub(crate) fn new(policy: LockPolicy) -> Self {
+        match policy {
+            LockPolicy::Spin => WriteLock::Spin {
+                locked: Padded::new(AtomicBool::new(false)),
+            },
+            #[cfg(feature = "std")]
+            LockPolicy::Park => WriteLock::Park {
+                locked: Padded::new(AtomicBool::new(false)),
+                parked: Mutex::new(VecDeque::new()),
+            },
+            LockPolicy::Ticket => WriteLock::Ticket {
+                next: Padded::new(AtomicUsize::new(0)),
+                serving: Padded::new(AtomicUsize::new(0)),
+            },
+        }
+    }
+
+    pub(crate) fn policy(&self) -> LockPolicy {
+        match self {
+            WriteLock::Spin { .. } => LockPolicy::Spin,
+            #[cfg(feature = "std")]
+            WriteLock::Park { .. } => LockPolicy::Park,
+            WriteLock::Ticket { .. } => LockPolicy::Ticket,
+        }
+    }
+
+    // Also returns how many rounds were spent waiting for the lock
+    pub(crate) fn lock(&self, backoff_policy: BackoffPolicy) -> (WriteGuard<'_>, u64) {
+        let spins = self.acquire(backoff_policy);
+
+        (WriteGuard { lock: self }, spins)
+    }
+
+    fn acquire(&self, backoff_policy: BackoffPolicy) -> u64 {
+        let backoff = Backoff::new();
+        let mut spins = 0;
+
+        match self {
+            WriteLock::Spin { locked } => {
+                while !Self::try_set(locked) {
+                    backoff_policy.wait(&backoff);
+                    spins += 1;
+                }
+            }
+            #[cfg(feature = "std")]
+            WriteLock::Park { locked, parked } => {
+                while !Self::try_set(locked) {
+                    if backoff.is_completed() {
+                        Self::park(locked, parked);
+                        break;
+                    }
+
+                    backoff_policy.wait(&backoff);
+                    spins += 1;
+                }
+            }
+            WriteLock::Ticket { next, serving } => {
+                let ticket = next.fetch_add(1, RELAXED);
+
+                while serving.load(ACQUIRE) != ticket {
+                    backoff_policy.wait(&backoff);
+                    spins += 1;
+                }
+            }
+        }
+
+        spins
+    }
+
+    pub(crate) fn try_lock(&self) -> Option<WriteGuard<'_>> {
+        if self.try_acquire() {
+            Some(WriteGuard { lock: self })
+        } else {
+            None
+        }
+    }
+
+    fn try_acquire(&self) -> bool {
+        match self {
+            WriteLock::Spin { locked } => Self::try_set(locked),
+            #[cfg(feature = "std")]
+            WriteLock::Park { locked, .. } => Self::try_set(locked),
+            WriteLock::Ticket { next, serving } => {
+                let ticket = serving.load(RELAXED);
+
+                next.compare_exchange(ticket, ticket + 1, ACQUIRE, RELAXED)
+                    .is_ok()
+            }
+        }
+    }
+
+    // Retries `try_lock` until `expired` returns `true`. Never parks or
+    // queues, so giving up leaves no trace in the lock.
+    #[cfg(feature = "std")]
+    pub(crate) fn try_lock_until(
+        &self,
+        expired: impl Fn() -> bool,
+        backoff_policy: BackoffPolicy,
+    ) -> Option<(WriteGuard<'_>, u64)> {
+        let backoff = Backoff::new();
+        let mut spins = 0;
+
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return Some((guard, spins));
+            }
+
+            if expired() {
+                return None;
+            }
+
+            backoff_policy.wait(&backoff);
+            spins += 1;
+        }
+    }
+
+    fn unlock(&self) {
+        match self {
+            WriteLock::Spin { locked } => locked.store(false, RELEASE),
+            #[cfg(feature = "std")]
+            WriteLock::Park { locked, parked } => {
+                let mut parked = parked.lock().unwrap_or_else(|e| e.into_inner());
+
+                match parked.pop_front() {
+                    // Hand the lock over without releasing it
+                    Some(waiter) => {
+                        waiter.granted.store(true, RELEASE);
+                        waiter.thread.unpark();
+                    }
+                    None => locked.store(false, RELEASE),
+                }
+            }
+            WriteLock::Ticket { serving, .. } => {
+                serving.fetch_add(1, RELEASE);
+            }
+        }
+    }
+
+    // Sets `locked` if it was clear. A failed compare-exchange only reads
+    // the flag, so spinning on a held lock doesn't keep writing its line,
+    // and loom can tell the spinner isn't making progress.
+    fn try_set(locked: &AtomicBool) -> bool {
+        locked
+            .compare_exchange(false, true, ACQUIRE, RELAXED)
+            .is_ok()
+    }
+
+    #[cfg(feature = "std")]
+    fn park(locked: &AtomicBool, parked: &Mutex<VecDeque<Waiter>>) {
+        let granted = Arc::new(AtomicBool::new(false));
+
+        {
+            let mut parked = parked.lock().unwrap_or_else(|e| e.into_inner());
+
+            // `unlock` releases the lock while holding `parked`, so either it
+            // is free now or the holder will see us in the queue.
+            if Self::try_set(locked) {
+                return;
+            }
+
+            parked.push_back(Waiter {
+                thread: thread::current(),
+                granted: granted.clone(),
+            });
+        }
+
+        while !granted.load(ACQUIRE) {
+            thread::park();
+        }
+    }
+}
+
+// Releases the writer lock when dropped, including during unwinding
+pub(crate) struct WriteGuard<'a> {
+    lock: &'a WriteLock,
+}
+
+impl Drop for WriteGuard<'_> {
+    fn drop(&mut self) {
+        self.lock.unlock();
+    }
+}
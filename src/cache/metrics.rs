@@ -0,0 +1,23 @@
+use std::sync::Arc;
+
+use crate::metrics::{Registry, Sample};
+
+use super::Cache;
+
+impl<T: Clone + Send + Sync + 'static, const LEN: usize> Cache<T, LEN> {
+    // Exposes the cache's stats through `registry`, labelled with `name`.
+    // The registry only keeps a weak reference to the cache.
+    pub fn describe_metrics(self: &Arc<Self>, registry: &Registry, name: &str) {
+        let cache = Arc::downgrade(self);
+
+        registry.register(name, move || {
+            let cache = cache.upgrade()?;
+
+            Some(Sample {
+                stats: cache.stats(),
+                generation: cache.generation(),
+                active_slot: cache.index(),
+            })
+        });
+    }
+}
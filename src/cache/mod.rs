@@ -1,108 +1,740 @@
-use std::{
-    array,
-    cell::UnsafeCell,
-    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+use alloc::vec::Vec;
+use core::{array, ptr};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::{
+    sync::{
+        self,
+        atomic::{self, AtomicUsize},
+    },
+    utils::{Backoff, Padded},
 };
 
-use crossbeam::utils::CachePadded;
+mod backoff;
+mod builder;
+mod dynamic;
+mod error;
+#[cfg(feature = "std")]
+mod grouped;
+mod guard;
+mod impls;
+mod item;
+mod lock;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "std")]
+mod named;
+mod ordering;
+mod pending;
+mod policy;
+#[cfg(feature = "std")]
+mod pooled;
+#[cfg(feature = "std")]
+mod prepare;
+#[cfg(feature = "std")]
+mod provenance;
+mod readers;
+mod recycle;
+#[cfg(feature = "std")]
+mod refresher;
+#[cfg(feature = "serde")]
+mod serialize;
+mod sink;
+mod stats;
+#[cfg(feature = "std")]
+mod timestamp;
+mod trace;
+mod uniform;
+#[cfg(feature = "std")]
+mod updates;
+mod urgent;
+#[cfg(feature = "std")]
+mod validate;
+
+pub use backoff::BackoffPolicy;
+pub use builder::CacheBuilder;
+pub use dynamic::DynCache;
+#[cfg(feature = "std")]
+pub use error::Rejected;
+#[cfg(feature = "std")]
+pub use error::UpdateError;
+pub use error::UpdateTimeout;
+pub use guard::Guard;
+#[cfg(feature = "log")]
+pub use guard::warn_on_long_guards;
+pub use lock::LockPolicy;
+pub use policy::Policy;
+#[cfg(feature = "std")]
+pub use prepare::PreparedUpdate;
+#[cfg(feature = "std")]
+pub use provenance::UpdateMeta;
+pub use readers::ReaderHandle;
+#[cfg(feature = "std")]
+pub use refresher::Refresher;
+pub use sink::CacheSink;
+#[cfg(feature = "stats")]
+pub use stats::{CacheStats, SlotStats};
+#[cfg(feature = "std")]
+pub use updates::{Next, Updates, WaitFor};
+
+#[cfg(feature = "std")]
+use grouped::GroupTag;
+pub(crate) use item::PinGuard;
+use item::{INVALID_GENERATION, Item};
+use lock::{WriteGuard, WriteLock};
+use ordering::{ACQ_REL, ACQUIRE, RELEASE, SEQ_CST, STAMP};
+use pending::Pending;
+#[cfg(feature = "std")]
+use provenance::Tag;
+use readers::Readers;
+use stats::Counters;
+#[cfg(feature = "std")]
+use timestamp::Timestamp;
+#[cfg(feature = "std")]
+use updates::Wakers;
+use urgent::Overflow;
+#[cfg(feature = "std")]
+use validate::Validator;
 
 pub struct Cache<T, const LEN: usize = 4>
 where
     T: Clone,
 {
-    index: CachePadded<AtomicUsize>,
-    writing: CachePadded<AtomicBool>,
+    // Packs the generation of the current value above the slot it lives in,
+    // see `stamp`
+    index: Padded<AtomicUsize>,
+    writing: WriteLock,
+    backoff: BackoffPolicy,
+    coalesce: bool,
+    pending: Pending<T>,
+    counters: Counters,
+    #[cfg(feature = "std")]
+    updated: Timestamp,
+    #[cfg(feature = "std")]
+    tag: Tag,
+    #[cfg(feature = "std")]
+    group: GroupTag,
+    #[cfg(feature = "std")]
+    validator: Validator<T>,
+    #[cfg(feature = "std")]
+    wakers: Wakers,
+    readers: Readers,
+    // Values from `update_urgent`, on their way to `overflow`
+    urgent: Pending<T>,
+    overflow: Overflow<T>,
     items: [Item<T>; LEN],
 }
 
-struct Item<T> {
-    count: CachePadded<AtomicUsize>,
-    data: UnsafeCell<Option<T>>,
-}
-
 // Safety: Cache is designed for concurrent access
 // - UnsafeCell is only accessed through atomic guards (count for reads, writing for writes)
-// - Reads increment/decrement count atomically around the UnsafeCell access
-// - Writes hold the writing lock and check count is zero before accessing UnsafeCell
-unsafe impl<T: Clone, const LEN: usize> Sync for Cache<T, LEN> {}
+// - Reads pin the slot through count and back off if it is retiring
+// - Writes hold the writing lock, retire the slot and check count is zero before accessing UnsafeCell
+// - Readers share `&T` across threads to clone it, and values are dropped by
+//   whichever writer overwrites them, hence `T: Send + Sync`
+unsafe impl<T: Clone + Send + Sync, const LEN: usize> Sync for Cache<T, LEN> {}
 
 impl<T: Clone, const LEN: usize> Cache<T, LEN> {
-    const CHECK_LEN_IS_POWER_OF_TWO: () = assert!(LEN.is_power_of_two() == true);
+    const CHECK_LEN_IS_POWER_OF_TWO: () = assert!(LEN.is_power_of_two());
     const LEN_MASK: usize = LEN - 1;
+    const SLOT_BITS: u32 = LEN.trailing_zeros();
+    const GENERATION_MASK: usize = usize::MAX >> (Self::SLOT_BITS + 1);
+    // Set in the index while the current value lives in an overflow slot,
+    // see `update_urgent`
+    const URGENT: usize = 1 << (usize::BITS - 1);
 
     pub fn new(data: T) -> Self {
-        let _ = Self::CHECK_LEN_IS_POWER_OF_TWO;
+        Self::with_backoff(data, BackoffPolicy::default())
+    }
 
-        let mut items = array::from_fn(|_| Item {
-            count: CachePadded::new(AtomicUsize::new(0)),
-            data: UnsafeCell::new(None),
-        });
+    pub fn with_backoff(data: T, backoff: BackoffPolicy) -> Self {
+        Self::with_policy(
+            data,
+            Policy {
+                backoff,
+                ..Policy::default()
+            },
+        )
+    }
+
+    pub fn with_policy(data: T, policy: Policy) -> Self {
+        let cache = Self::empty(policy);
 
-        *items[0].data.get_mut() = Some(data);
+        // Safety: nobody else can see the slot yet
+        unsafe { cache.items[0].replace(Some(data)) };
+
+        cache
+    }
+
+    // Cache holding no value yet, as if just cleared
+    pub(crate) fn empty(policy: Policy) -> Self {
+        let () = Self::CHECK_LEN_IS_POWER_OF_TWO;
+
+        let items: [Item<T>; LEN] = array::from_fn(|_| Item::new());
+        items[0].set_generation(0);
 
         Self {
-            index: CachePadded::new(AtomicUsize::new(0)),
-            writing: CachePadded::new(AtomicBool::new(false)),
+            index: Padded::new(AtomicUsize::new(0)),
+            writing: WriteLock::new(policy.lock),
+            backoff: policy.backoff,
+            coalesce: policy.coalesce,
+            pending: Pending::new(),
+            counters: Counters::new(),
+            #[cfg(feature = "std")]
+            updated: Timestamp::new(),
+            #[cfg(feature = "std")]
+            tag: Tag::new(),
+            #[cfg(feature = "std")]
+            group: GroupTag::new(),
+            #[cfg(feature = "std")]
+            validator: Validator::new(),
+            #[cfg(feature = "std")]
+            wakers: Wakers::new(),
+            readers: Readers::new(),
+            urgent: Pending::new(),
+            overflow: Overflow::new(),
             items,
         }
     }
 
     pub fn get_data(&self) -> T {
-        let index = self.index();
+        self.try_get_data().expect("cache is empty")
+    }
 
-        self.items[index].count.fetch_add(1, Ordering::Release);
+    // Returns `None` if the cache was emptied with `clear` and has not been
+    // updated since.
+    pub fn try_get_data(&self) -> Option<T> {
+        let (guard, _) = self.pin();
+        self.counters.read();
 
-        let data = unsafe {
-            (*self.items[index].data.get())
-                .as_ref()
-                .unwrap_unchecked()
-                .clone()
-        };
+        guard.data().clone()
+    }
 
-        self.items[index].count.fetch_sub(1, Ordering::Release);
+    // `N` clones of the current value, all taken under a single pin, for
+    // fan-out code handing a copy to each of its workers. Panics if the
+    // cache is empty.
+    pub fn get_many<const N: usize>(&self) -> [T; N] {
+        let (guard, _) = self.pin();
+        self.counters.read();
 
-        data
+        let data = guard.data().as_ref().expect("cache is empty");
+
+        array::from_fn(|_| data.clone())
+    }
+
+    // Runs `f` on the current value without cloning it. The slot stays
+    // pinned until `f` returns, so keep it short: a writer that laps the
+    // ring back to this slot waits for it, and so do `clear` and `take`.
+    // Panics if the cache is empty.
+    pub fn with_pinned<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let (guard, _) = self.pin();
+        self.counters.read();
+
+        f(guard.data().as_ref().expect("cache is empty"))
+    }
+
+    // Like `get_many`, with the count known at runtime
+    pub fn read_batch(&self, n: usize) -> Vec<T> {
+        let (guard, _) = self.pin();
+        self.counters.read();
+
+        let data = guard.data().as_ref().expect("cache is empty");
+
+        (0..n).map(|_| data.clone()).collect()
+    }
+
+    // Like `try_get_data`, along with the generation the value was
+    // published under
+    pub(crate) fn try_get_with_generation(&self) -> Option<(T, u64)> {
+        let (guard, generation) = self.pin();
+        self.counters.read();
+
+        guard.data().clone().map(|data| (data, generation as u64))
+    }
+
+    // Pins the slot holding the current value for a reader that borrows it
+    // instead of cloning
+    pub(crate) fn pin_current(&self) -> PinGuard<'_, T> {
+        let (guard, _) = self.pin();
+        self.counters.read();
+
+        guard
     }
 
     pub fn update(&self, data: T) {
-        while self.writing.swap(true, Ordering::Acquire) {
-            std::hint::spin_loop();
+        #[cfg(feature = "std")]
+        let Ok(data) = self.validator.admit(data) else {
+            return;
+        };
+
+        self.update_admitted(data);
+    }
+
+    // `update` for a value that passed the validator
+    fn update_admitted(&self, data: T) {
+        if self.coalesce {
+            return self.update_coalesced(data);
         }
 
-        let current_index = self.index.load(Ordering::Acquire);
-        let mut next_index = current_index;
+        let _guard = self.lock();
+        self.publish(data);
+    }
 
-        loop {
-            next_index = (next_index + 1) & Self::LEN_MASK;
+    // Like `update`, but gives up and hands the value back if the writer lock
+    // or a free slot can't be obtained within `timeout`. Never coalesces.
+    // A value refused by the validator is handed back too, as with
+    // `try_publish`.
+    #[cfg(feature = "std")]
+    pub fn update_timeout(&self, data: T, timeout: Duration) -> Result<(), UpdateError<T>> {
+        let data = self.validator.admit(data)?;
 
-            if next_index == current_index {
-                continue;
+        let deadline = Instant::now() + timeout;
+        let expired = || Instant::now() >= deadline;
+
+        let Some((guard, spins)) = self.writing.try_lock_until(expired, self.backoff) else {
+            return Err(UpdateTimeout(data).into());
+        };
+        let _guard = self.locked(guard);
+        self.counters.lock_spins(spins);
+        trace::lock_spins(self.id(), spins);
+
+        match self.next_free_slot(expired) {
+            Some(next_index) => {
+                self.publish_to(next_index, data);
+                Ok(())
             }
+            None => Err(UpdateTimeout(data).into()),
+        }
+    }
+
+    // Publishes the value computed by `f` from the current one, with the
+    // writer lock held so no other update lands in between. `f` sees `None`
+    // if the cache was cleared.
+    pub fn update_with(&self, f: impl FnOnce(Option<&T>) -> T) {
+        let _guard = self.lock();
+
+        // Coalesced updates that were left behind come first
+        while let Some(data) = self.pending.take() {
+            self.publish(data);
+        }
+
+        let (current, _) = self.pin();
+        let data = f(current.data().as_ref());
+        drop(current);
+
+        #[cfg(feature = "std")]
+        let data = self.validator.admit(data).ok();
+        #[cfg(not(feature = "std"))]
+        let data = Some(data);
+
+        if let Some(data) = data {
+            self.publish(data);
+        }
+    }
+
+    fn update_coalesced(&self, data: T) {
+        self.pending.put(data);
+        self.drain_pending();
+    }
 
-            let count = self.items[next_index].count.load(Ordering::Acquire);
+    fn drain_pending(&self) {
+        // Pairs with the fence after `unlock` below: either we see the lock
+        // free, or the holder sees our value in `pending`.
+        atomic::fence(SEQ_CST);
 
-            if count == 0 {
+        while let Some(guard) = self.writing.try_lock() {
+            while let Some(data) = self.pending.take() {
+                self.publish(data);
+            }
+
+            drop(guard);
+
+            atomic::fence(SEQ_CST);
+
+            if self.pending.is_empty() {
                 break;
             }
         }
+    }
+
+    // Retires slot `index` and returns whether no reader is left on it,
+    // pinned or registered
+    fn retire(&self, index: usize) -> bool {
+        self.items[index].retire() && !self.readers.announced(index)
+    }
+
+    fn is_drained(&self, index: usize) -> bool {
+        self.items[index].is_drained() && !self.readers.announced(index)
+    }
+
+    // Must be called with the writer lock held
+    fn publish(&self, data: T) {
+        let Some(next_index) = self.next_free_slot(|| false) else {
+            unreachable!("the scan only gives up once `expired` returns true");
+        };
+
+        self.publish_to(next_index, data);
+    }
+
+    // Must be called with the writer lock held. Returns `None` if `expired`
+    // returns `true` before an inactive slot is released by its readers.
+    //
+    // The returned slot is retiring and must be handed to `publish_to`.
+    fn next_free_slot(&self, expired: impl Fn() -> bool) -> Option<usize> {
+        let current_index = self.index();
+
+        for offset in 1..LEN {
+            let index = (current_index + offset) & Self::LEN_MASK;
+
+            if self.retire(index) {
+                return Some(index);
+            }
+
+            self.items[index].release();
+            self.counters.scan_skip();
+        }
+
+        // Every inactive slot is pinned. Keep the next one retiring so readers
+        // landing on it through a stale index bounce, and wait for the ones
+        // already inside to leave.
+        let index = (current_index + 1) & Self::LEN_MASK;
+        let item = &self.items[index];
+        let backoff = Backoff::new();
+
+        item.retire();
+        trace::scan_wrapped(self.id(), index);
+
+        while !self.is_drained(index) {
+            if expired() {
+                item.release();
+                return None;
+            }
+
+            // Urgent updates don't wait for us
+            self.publish_urgent();
+
+            self.backoff.wait(&backoff);
+        }
+
+        Some(index)
+    }
+
+    // Must be called with the writer lock held and `index` retired
+    fn publish_to(&self, index: usize, data: T) {
+        let old = unsafe { self.items[index].replace(Some(data)) };
+
+        self.flip_to(index);
+
+        // Only drop the old value once the cache is consistent again, in
+        // case its destructor panics
+        drop(old);
+    }
+
+    // Makes the retired slot `index` current, with whatever it holds. Must
+    // be called with the writer lock held.
+    fn flip_to(&self, index: usize) {
+        let generation = self.next_generation();
+
+        #[cfg(feature = "std")]
+        self.updated.touch();
+        self.items[index].set_generation(generation);
+        self.index.store(Self::stamp(generation, index), RELEASE);
+        self.items[index].release();
+        self.counters.write();
+        trace::published(self.id(), generation);
+
+        #[cfg(feature = "std")]
+        self.wakers.wake();
+    }
+
+    // Drops the values held by inactive slots that no reader is using,
+    // so old values don't stay alive until the ring wraps around to them.
+    pub fn drop_stale(&self) {
+        let _guard = self.lock();
+
+        let current_index = self.index();
+
+        for (index, item) in self.items.iter().enumerate() {
+            if index == current_index {
+                continue;
+            }
+
+            let mut stale = None;
+
+            if self.retire(index) {
+                stale = unsafe { item.take() };
+
+                // Readers still holding the stamp this value was published
+                // under must not mistake the empty slot for an empty cache
+                item.set_generation(INVALID_GENERATION);
+            }
+
+            item.release();
+
+            drop(stale);
+        }
+
+        self.drop_stale_overflow();
+    }
+
+    // Drops every value held by the cache, waiting for readers to release
+    // each slot first. Afterwards `try_get_data` returns `None` until the
+    // next `update`.
+    pub fn clear(&self) {
+        let _guard = self.lock();
+
+        let cleared: [Option<T>; LEN] = array::from_fn(|index| {
+            let item = &self.items[index];
+            let backoff = Backoff::new();
+
+            item.retire();
+
+            while !self.is_drained(index) {
+                // Overflow slots are only retired below
+                self.publish_urgent();
+
+                self.backoff.wait(&backoff);
+            }
+
+            item.set_generation(INVALID_GENERATION);
+
+            unsafe { item.take() }
+        });
+
+        let overflow = self.retire_overflow();
+
+        // Publish the emptiness as a new generation of the current slot
+        let index = self.index();
+        let generation = self.next_generation();
+
+        self.items[index].set_generation(generation);
+        self.index.store(Self::stamp(generation, index), RELEASE);
+
+        for item in &self.items {
+            item.release();
+        }
+        self.release_overflow();
+
+        drop(cleared);
+        drop(overflow);
+    }
+
+    // Removes the current value and returns it, leaving the cache empty as
+    // after `clear`. Readers are sent to an empty slot first and never wait,
+    // the value is moved out once the last of them has left its slot.
+    pub fn take(&self) -> Option<T> {
+        let guard = self.lock();
+
+        while let Some(data) = self.pending.take() {
+            self.publish(data);
+        }
+
+        let Some(next_index) = self.next_free_slot(|| false) else {
+            unreachable!("the scan only gives up once `expired` returns true");
+        };
+
+        let stale = unsafe { self.items[next_index].replace(None) };
+        let generation = self.next_generation();
+
+        self.items[next_index].set_generation(generation);
+
+        // The scan publishes urgent values while it waits for readers, so the
+        // value to take is the one this swap replaces, in the ring or not
+        let taken = self
+            .index
+            .swap(Self::stamp(generation, next_index), ACQ_REL);
+        self.items[next_index].release();
+
+        let Some(item) = self.item_of(taken) else {
+            unreachable!("an urgent stamp is only published with its slot");
+        };
+        let backoff = Backoff::new();
+
+        item.retire();
+
+        // Registered readers only ever read ring slots
+        let ring = taken & Self::URGENT == 0;
+
+        while !item.is_drained() || (ring && self.readers.announced(taken & Self::LEN_MASK)) {
+            // Publishing claims a drained overflow slot, which could be the
+            // one being emptied
+            if ring {
+                self.publish_urgent();
+            }
+
+            self.backoff.wait(&backoff);
+        }
+
+        item.set_generation(INVALID_GENERATION);
+        let data = unsafe { item.take() };
+        item.release();
+
+        drop(guard);
+        drop(stale);
+
+        data
+    }
 
-        unsafe {
-            drop((*self.items[next_index].data.get()).replace(data));
+    pub fn policy(&self) -> Policy {
+        Policy {
+            backoff: self.backoff,
+            lock: self.writing.policy(),
+            coalesce: self.coalesce,
         }
+    }
+
+    // Counters collected since the cache was created, along with the current
+    // pinning of each slot. Only as consistent as a handful of relaxed loads.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> CacheStats {
+        let current = self.index();
+
+        let slots = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| SlotStats {
+                readers: item.state().0,
+                current: index == current,
+            })
+            .collect();
 
-        self.index.store(next_index, Ordering::Release);
+        self.counters.snapshot(slots)
+    }
+
+    // When the current value was published by an update, or the cache was
+    // created if it hasn't been updated yet. `clear` and `take` don't count.
+    #[cfg(feature = "std")]
+    pub fn last_updated(&self) -> SystemTime {
+        self.updated.wall()
+    }
 
-        self.writing.store(false, Ordering::Release);
+    // Time since `last_updated`
+    #[cfg(feature = "std")]
+    pub fn age(&self) -> Duration {
+        self.updated.instant().elapsed()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.age() > max_age
+    }
+
+    // Generation of the current value. Bumped by every `update` and `clear`.
+    pub fn generation(&self) -> u64 {
+        Self::generation_of(self.index.load(ACQUIRE)) as u64
     }
 
     fn index(&self) -> usize {
-        self.index.load(Ordering::Acquire) & Self::LEN_MASK
+        self.index.load(ACQUIRE) & Self::LEN_MASK
+    }
+
+    fn stamp(generation: usize, index: usize) -> usize {
+        (generation << Self::SLOT_BITS) | index
+    }
+
+    fn generation_of(stamp: usize) -> usize {
+        (stamp >> Self::SLOT_BITS) & Self::GENERATION_MASK
+    }
+
+    // Must be called with the writer lock held
+    fn next_generation(&self) -> usize {
+        (Self::generation_of(self.index.load(ACQUIRE)) + 1) & Self::GENERATION_MASK
+    }
+
+    // Pins the slot holding the current value and returns it along with its
+    // generation, retrying until the pinned slot still holds the value
+    // published under the stamp that led to it.
+    fn pin(&self) -> (PinGuard<'_, T>, usize) {
+        loop {
+            let stamp = self.index.load(STAMP);
+
+            if let Some(guard) = self.pin_stamp(stamp) {
+                return (guard, Self::generation_of(stamp));
+            }
+
+            // Pinning again would keep bumping the count of a slot its writer
+            // waits on to drain, so wait with loads until the slot is
+            // released or the index moves on
+            while self.index.load(STAMP) == stamp
+                && self.item_of(stamp).is_some_and(Item::is_retiring)
+            {
+                sync::spin_loop();
+            }
+        }
+    }
+
+    // The slot may have been refilled by writers lapping the ring since the
+    // stamp was read, in which case this returns `None`
+    fn pin_stamp(&self, stamp: usize) -> Option<PinGuard<'_, T>> {
+        self.item_of(stamp)?
+            .pin_generation(Self::generation_of(stamp))
+    }
+
+    fn item_of(&self, stamp: usize) -> Option<&Item<T>> {
+        if stamp & Self::URGENT != 0 {
+            self.overflow.current()
+        } else {
+            Some(&self.items[stamp & Self::LEN_MASK])
+        }
+    }
+
+    fn lock(&self) -> Locked<'_, T, LEN> {
+        let (guard, spins) = self.writing.lock(self.backoff);
+        self.counters.lock_spins(spins);
+        trace::lock_spins(self.id(), spins);
+
+        self.locked(guard)
+    }
+
+    fn locked<'a>(&'a self, guard: WriteGuard<'a>) -> Locked<'a, T, LEN> {
+        Locked {
+            cache: self,
+            guard: Some(guard),
+        }
+    }
+
+    // Tells caches apart in `trace` events
+    fn id(&self) -> *const () {
+        ptr::from_ref(self).cast()
+    }
+}
+
+// The writer lock as held by the cache's writers. Values coalesced behind
+// the holder while it had the lock are published once it is released, so
+// none is left waiting for the next write.
+pub(crate) struct Locked<'a, T: Clone, const LEN: usize> {
+    cache: &'a Cache<T, LEN>,
+    guard: Option<WriteGuard<'a>>,
+}
+
+impl<T: Clone, const LEN: usize> Drop for Locked<'_, T, LEN> {
+    fn drop(&mut self) {
+        drop(self.guard.take());
+
+        // Publishing drops old values, which must not panic again
+        #[cfg(feature = "std")]
+        if std::thread::panicking() {
+            return;
+        }
+
+        if self.cache.coalesce {
+            self.cache.drain_pending();
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::sync::{Arc, atomic::AtomicU8};
+    use std::sync::{
+        Arc,
+        atomic::{AtomicU8, Ordering},
+    };
+    #[cfg(feature = "std")]
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        time::Duration,
+    };
 
     use super::*;
 
@@ -195,4 +827,707 @@ mod tests {
         drop(cache);
         assert_eq!(drop_count.load(Ordering::Acquire), 11);
     }
+
+    #[test]
+    fn test_drop_stale() {
+        let drop_count = Arc::new(AtomicU8::new(0));
+
+        let cache: Cache<Data<&str>> = Cache::new(Data("first", drop_count.clone()));
+        cache.update(Data("second", drop_count.clone()));
+        cache.update(Data("third", drop_count.clone()));
+        assert_eq!(drop_count.load(Ordering::Acquire), 0);
+
+        // "first" and "second" are stale, "third" is current
+        cache.drop_stale();
+        assert_eq!(drop_count.load(Ordering::Acquire), 2);
+
+        let retrieved = cache.get_data();
+        assert_eq!(retrieved.0, "third");
+        drop(retrieved);
+        assert_eq!(drop_count.load(Ordering::Acquire), 3);
+
+        // Nothing left to reclaim
+        cache.drop_stale();
+        assert_eq!(drop_count.load(Ordering::Acquire), 3);
+
+        // Emptied slots are reused by later updates
+        cache.update(Data("fourth", drop_count.clone()));
+        assert_eq!(drop_count.load(Ordering::Acquire), 3);
+        assert_eq!(cache.get_data().0, "fourth");
+        assert_eq!(drop_count.load(Ordering::Acquire), 4);
+
+        drop(cache);
+        assert_eq!(drop_count.load(Ordering::Acquire), 6);
+    }
+
+    #[test]
+    fn test_clear() {
+        let drop_count = Arc::new(AtomicU8::new(0));
+
+        let cache: Cache<Data<&str>> = Cache::new(Data("first", drop_count.clone()));
+        cache.update(Data("second", drop_count.clone()));
+
+        cache.clear();
+        assert_eq!(drop_count.load(Ordering::Acquire), 2);
+        assert!(cache.try_get_data().is_none());
+
+        cache.update(Data("third", drop_count.clone()));
+        let retrieved = cache.try_get_data().unwrap();
+        assert_eq!(retrieved.0, "third");
+        drop(retrieved);
+        assert_eq!(drop_count.load(Ordering::Acquire), 3);
+
+        drop(cache);
+        assert_eq!(drop_count.load(Ordering::Acquire), 4);
+    }
+
+    #[test]
+    fn test_backoff_policies() {
+        for policy in [BackoffPolicy::Spin, BackoffPolicy::Yield] {
+            let cache: Cache<usize, 2> = Cache::with_backoff(0, policy);
+
+            std::thread::scope(|s| {
+                for _ in 0..4 {
+                    s.spawn(|| {
+                        for i in 1..=1_000 {
+                            cache.update(i);
+                            assert!(cache.get_data() <= 1_000);
+                        }
+                    });
+                }
+            });
+
+            cache.update(usize::MAX);
+            assert_eq!(cache.get_data(), usize::MAX);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_park_lock_policy() {
+        let cache: Cache<usize, 2> = Cache::with_policy(
+            0,
+            Policy {
+                lock: LockPolicy::Park,
+                ..Policy::default()
+            },
+        );
+
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for i in 1..=500 {
+                        cache.update(i);
+                        assert!(cache.get_data() <= 500);
+                    }
+                });
+            }
+        });
+
+        cache.update(usize::MAX);
+        assert_eq!(cache.get_data(), usize::MAX);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_update_timeout() {
+        let cache: Cache<u64, 2> = Cache::new(1);
+
+        cache.update_timeout(2, Duration::from_millis(1)).unwrap();
+        assert_eq!(cache.get_data(), 2);
+
+        // The only inactive slot is pinned by a reader
+        cache.items[0].readers().fetch_add(1, Ordering::AcqRel);
+        let err = cache
+            .update_timeout(3, Duration::from_millis(1))
+            .unwrap_err();
+        assert_eq!(err.into_inner(), 3);
+        assert_eq!(cache.get_data(), 2);
+        cache.items[0].readers().fetch_sub(1, Ordering::AcqRel);
+
+        // The writer lock is held by someone else
+        let guard = cache.lock();
+        assert!(matches!(
+            cache.update_timeout(4, Duration::from_millis(1)),
+            Err(UpdateError::Timeout(UpdateTimeout(4)))
+        ));
+        drop(guard);
+
+        cache.update_timeout(5, Duration::from_millis(1)).unwrap();
+        assert_eq!(cache.get_data(), 5);
+
+        // Refused values come back as rejections, not as publishes
+        cache.set_validator(|data: &u64| if *data < 10 { Ok(()) } else { Err("too big") });
+        let err = cache
+            .update_timeout(11, Duration::from_millis(1))
+            .unwrap_err();
+        assert!(
+            matches!(&err, UpdateError::Rejected(rejected) if rejected.error.to_string() == "too big")
+        );
+        assert_eq!(err.into_inner(), 11);
+        assert_eq!(cache.get_data(), 5);
+    }
+
+    #[test]
+    fn test_readers_bounce_off_retiring_slot() {
+        let cache: Cache<u64, 2> = Cache::new(1);
+        cache.update(2);
+
+        // A reader holding a stale index keeps slot 0 busy
+        let reader = cache.items[0].pin().unwrap();
+
+        std::thread::scope(|s| {
+            let writer = s.spawn(|| cache.update(3));
+
+            // Wait for the writer to retire slot 0
+            while cache.items[0].pin().is_some() {
+                std::hint::spin_loop();
+            }
+
+            // New readers are turned away while the old one drains
+            assert_eq!(cache.get_data(), 2);
+            drop(reader);
+
+            writer.join().unwrap();
+        });
+
+        assert_eq!(cache.get_data(), 3);
+        assert!(cache.items[0].pin().is_some());
+    }
+
+    #[test]
+    fn test_batch_reads() {
+        let cache: Cache<String, 2> = Cache::new(String::from("a"));
+
+        assert_eq!(cache.get_many::<3>(), ["a", "a", "a"]);
+        assert_eq!(cache.read_batch(2), ["a", "a"]);
+        assert!(cache.read_batch(0).is_empty());
+
+        // The slot is only pinned while cloning
+        cache.update(String::from("b"));
+        cache.update(String::from("c"));
+        assert_eq!(cache.get_many::<2>(), ["c", "c"]);
+        assert_eq!(cache.items[0].state().0, 0);
+    }
+
+    #[test]
+    fn test_with_pinned() {
+        let cache: Cache<Vec<u32>, 2> = Cache::new(vec![1, 2, 3]);
+
+        let sum: u32 = (0..10).map(|i| cache.with_pinned(|data| data[i % 3])).sum();
+        assert_eq!(sum, 19);
+
+        // A writer lapping the ring waits for the pinned slot
+        std::thread::scope(|s| {
+            cache.with_pinned(|data| {
+                let writer = s.spawn(|| {
+                    cache.update(vec![4]);
+                    cache.update(vec![5]);
+                });
+
+                while cache.generation() < 1 {
+                    std::thread::yield_now();
+                }
+
+                assert!(!writer.is_finished());
+                assert_eq!(data, &[1, 2, 3]);
+            });
+        });
+
+        assert_eq!(cache.with_pinned(|data| data.clone()), [5]);
+    }
+
+    #[test]
+    fn test_generations() {
+        let cache: Cache<u64, 2> = Cache::new(1);
+        assert_eq!(cache.generation(), 0);
+
+        let first = cache.index.load(Ordering::Acquire);
+
+        cache.update(2);
+        assert_eq!(cache.generation(), 1);
+
+        cache.update(3);
+        assert_eq!(cache.generation(), 2);
+
+        // Slot 0 was refilled since `first` was read, so a reader holding it
+        // has to retry instead of returning the new value
+        assert_eq!(first & 1, cache.index());
+        assert!(cache.pin_stamp(first).is_none());
+
+        let current = cache.index.load(Ordering::Acquire);
+        assert_eq!(cache.pin_stamp(current).unwrap().data(), &Some(3));
+
+        // Stamps pointing at reclaimed slots are rejected too
+        let stale = cache.index.load(Ordering::Acquire);
+        cache.update(4);
+        cache.drop_stale();
+        assert!(cache.pin_stamp(stale).is_none());
+
+        cache.clear();
+        assert_eq!(cache.generation(), 4);
+        assert!(cache.try_get_data().is_none());
+    }
+
+    #[test]
+    fn test_ticket_lock_policy() {
+        let cache: Cache<usize, 2> = Cache::with_policy(
+            0,
+            Policy {
+                lock: LockPolicy::Ticket,
+                ..Policy::default()
+            },
+        );
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for i in 1..=500 {
+                        cache.update(i);
+                        assert!(cache.get_data() <= 500);
+                    }
+                });
+            }
+        });
+
+        // Every ticket was served exactly once
+        let guard = cache.writing.try_lock();
+        assert!(guard.is_some());
+        assert!(cache.writing.try_lock().is_none());
+        drop(guard);
+
+        cache.update(usize::MAX);
+        assert_eq!(cache.get_data(), usize::MAX);
+    }
+
+    #[test]
+    fn test_coalescing_publishes_latest() {
+        let cache: Cache<usize> = Cache::with_policy(
+            0,
+            Policy {
+                coalesce: true,
+                ..Policy::default()
+            },
+        );
+
+        // Uncontended updates are published right away
+        cache.update(1);
+        assert_eq!(cache.get_data(), 1);
+
+        // Contended updates are left behind, the latest one wins
+        let guard = cache.lock();
+        cache.update(2);
+        cache.update(3);
+        assert_eq!(cache.get_data(), 1);
+
+        // Published by the holder as it lets go of the lock
+        drop(guard);
+        assert_eq!(cache.get_data(), 3);
+        assert!(cache.pending.is_empty());
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for i in 0..500 {
+                        cache.update(i);
+                    }
+                });
+            }
+        });
+
+        assert!(cache.pending.is_empty());
+    }
+
+    // Every writer publishes the updates coalesced behind it once it lets
+    // go of the lock, not only the next writer
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_coalesced_behind_every_writer() {
+        let policy = Policy {
+            coalesce: true,
+            ..Policy::default()
+        };
+
+        // `write` is left waiting for readers of slot `pinned` to leave
+        // while an update coalesces behind it
+        fn behind(cache: &Cache<usize, 2>, pinned: usize, write: impl FnOnce() + Send) {
+            std::thread::scope(|s| {
+                let reader = cache.items[pinned].pin().unwrap();
+                s.spawn(write);
+
+                while cache.writing.try_lock().is_some() {
+                    std::thread::yield_now();
+                }
+
+                cache.update(5);
+                drop(reader);
+            });
+
+            assert_eq!(cache.get_data(), 5);
+            assert!(cache.pending.is_empty());
+        }
+
+        let cache: Cache<usize, 2> = Cache::with_policy(0, policy);
+        behind(&cache, 1, || {
+            cache.update_timeout(1, Duration::from_secs(60)).unwrap();
+        });
+
+        let cache: Cache<usize, 2> = Cache::with_policy(0, policy);
+        behind(&cache, 1, || cache.update_tagged(1, "test"));
+
+        let cache: Cache<usize, 2> = Cache::with_policy(0, policy);
+        behind(&cache, 0, || cache.clear());
+
+        let cache: Cache<usize, 2> = Cache::with_policy(0, policy);
+        cache.fill_spare_slots(|| {
+            cache.update(5);
+            0
+        });
+        assert_eq!(cache.get_data(), 5);
+        assert!(cache.pending.is_empty());
+
+        // The value `drop_stale` drops updates the cache
+        #[derive(Clone)]
+        struct OnDrop(Arc<dyn Fn() + Send + Sync>);
+
+        impl Drop for OnDrop {
+            fn drop(&mut self) {
+                (self.0)();
+            }
+        }
+
+        let cache: Arc<Cache<(usize, Option<OnDrop>)>> =
+            Arc::new(Cache::with_policy((0, None), policy));
+        let weak = Arc::downgrade(&cache);
+        let hook = OnDrop(Arc::new(move || {
+            if let Some(cache) = weak.upgrade() {
+                cache.update((5, None));
+            }
+        }));
+
+        cache.update((1, Some(hook)));
+        cache.update((2, None));
+        cache.drop_stale();
+        assert_eq!(cache.get_data().0, 5);
+        assert!(cache.pending.is_empty());
+    }
+
+    #[test]
+    fn test_update_with() {
+        let cache: Cache<u64, 2> = Cache::new(0);
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..500 {
+                        cache.update_with(|current| current.unwrap() + 1);
+                    }
+                });
+            }
+        });
+        assert_eq!(cache.get_data(), 2_000);
+
+        cache.clear();
+        cache.update_with(|current| {
+            assert!(current.is_none());
+            7
+        });
+        assert_eq!(cache.get_data(), 7);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_spawn_refresher() {
+        let cache: Arc<Cache<u64>> = Arc::new(Cache::new(0));
+
+        let mut next = 0;
+        let refresher = cache
+            .clone()
+            .spawn_refresher(Duration::from_millis(1), move || {
+                next += 1;
+                next
+            });
+
+        while cache.get_data() < 3 {
+            std::thread::yield_now();
+        }
+
+        refresher.stop();
+        let last = cache.get_data();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.get_data(), last);
+
+        // The thread gave its reference back
+        assert_eq!(Arc::strong_count(&cache), 1);
+    }
+
+    #[test]
+    fn test_take() {
+        let drop_count = Arc::new(AtomicU8::new(0));
+
+        let cache: Cache<Data<&str>, 2> = Cache::new(Data("first", drop_count.clone()));
+        cache.update(Data("second", drop_count.clone()));
+
+        // A reader pinning the value doesn't stop it from being taken
+        let taken = std::thread::scope(|s| {
+            let (reader, _) = cache.pin();
+            let taker = s.spawn(|| cache.take());
+
+            while cache.generation() < 2 {
+                std::hint::spin_loop();
+            }
+
+            assert_eq!(reader.data().as_ref().unwrap().0, "second");
+            drop(reader);
+
+            taker.join().unwrap()
+        });
+
+        assert_eq!(taken.unwrap().0, "second");
+        assert!(cache.take().is_none());
+        assert!(cache.try_get_data().is_none());
+        assert_eq!(drop_count.load(Ordering::Acquire), 2);
+
+        cache.update(Data("third", drop_count.clone()));
+        assert_eq!(cache.get_data().0, "third");
+    }
+
+    #[test]
+    fn test_take_during_update_urgent() {
+        let cache: Cache<u32, 2> = Cache::new(0);
+
+        // The pinned slot keeps `take` scanning for a free one, and the scan
+        // publishes the urgent value meanwhile, which is then the one taken
+        let taken = std::thread::scope(|s| {
+            let pinned = cache.pin_current();
+            cache.update(1);
+
+            let taker = s.spawn(|| cache.take());
+
+            while cache.writing.try_lock().is_some() {
+                std::thread::yield_now();
+            }
+
+            cache.update_urgent(2);
+            assert_eq!(cache.get_data(), 2);
+            drop(pinned);
+
+            taker.join().unwrap()
+        });
+
+        assert_eq!(taken, Some(2));
+        assert!(cache.try_get_data().is_none());
+
+        cache.update(3);
+        assert_eq!(cache.get_data(), 3);
+    }
+
+    #[test]
+    fn test_builder() {
+        let cache = Cache::builder()
+            .slots::<8>()
+            .backoff(BackoffPolicy::Yield)
+            .lock(LockPolicy::Ticket)
+            .coalesce(true)
+            .build(1_u64);
+
+        let _: &Cache<u64, 8> = &cache;
+        assert_eq!(cache.backoff, BackoffPolicy::Yield);
+        assert!(cache.coalesce);
+
+        cache.update(2);
+        assert_eq!(cache.get_data(), 2);
+
+        let cache = Cache::builder().build("default");
+        assert_eq!(cache.items.len(), 4);
+        assert!(!cache.coalesce);
+    }
+
+    #[test]
+    fn test_std_impls() {
+        let cache: Cache<Vec<u64>> = Cache::default();
+        assert!(cache.get_data().is_empty());
+
+        let cache: Cache<&str, 2> = Cache::from("first");
+        cache.update("second");
+
+        let debug = format!("{cache:?}");
+        assert!(debug.contains("index: 1"), "{debug}");
+        assert!(debug.contains("generation: Some(1)"), "{debug}");
+
+        // Clones start over from the current value
+        let clone = cache.clone();
+        assert_eq!(clone.get_data(), "second");
+        assert_eq!(clone.generation(), 0);
+
+        clone.update("third");
+        assert_eq!(cache.get_data(), "second");
+
+        cache.clear();
+        assert!(cache.clone().try_get_data().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct State {
+            name: Cache<String>,
+            limits: Cache<Vec<u64>, 2>,
+        }
+
+        let state = State {
+            name: Cache::new(String::from("first")),
+            limits: Cache::new(vec![1, 2]),
+        };
+        state.name.update(String::from("second"));
+
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(json, r#"{"name":"second","limits":[1,2]}"#);
+
+        let restored: State = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.name.get_data(), "second");
+        assert_eq!(restored.limits.get_data(), [1, 2]);
+
+        state.name.clear();
+        assert!(serde_json::to_string(&state).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_staleness() {
+        let cache: Cache<u64> = Cache::new(1);
+        let created = std::time::SystemTime::now();
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(cache.is_stale(Duration::from_millis(10)));
+        assert!(cache.last_updated() <= created);
+
+        cache.update(2);
+        assert!(!cache.is_stale(Duration::from_secs(60)));
+        assert!(cache.age() < Duration::from_secs(60));
+        assert!(cache.last_updated() > created);
+    }
+
+    #[test]
+    #[cfg(feature = "stats")]
+    fn test_stats() {
+        let cache: Cache<u64> = Cache::new(1);
+
+        // Slot 1 is next in line; pinning it makes the writer skip ahead
+        let reader = cache.items[1].pin().unwrap();
+        cache.update(2);
+
+        assert_eq!(cache.get_data(), 2);
+        assert_eq!(cache.try_get_data(), Some(2));
+
+        let stats = cache.stats();
+        assert_eq!(stats.reads, 2);
+        assert_eq!(stats.writes, 1);
+        assert_eq!(stats.lock_spins, 0);
+        assert_eq!(stats.scan_skips, 1);
+        assert_eq!(stats.slots.len(), 4);
+        assert_eq!(stats.slots[1].readers, 1);
+        assert!(stats.slots[2].current);
+
+        drop(reader);
+        assert_eq!(cache.stats().slots[1].readers, 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[derive(Debug, PartialEq)]
+    struct Fragile {
+        value: u64,
+        panic_on_clone: bool,
+        panic_on_drop: bool,
+    }
+
+    #[cfg(feature = "std")]
+    impl Fragile {
+        fn new(value: u64) -> Self {
+            Self {
+                value,
+                panic_on_clone: false,
+                panic_on_drop: false,
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl Clone for Fragile {
+        fn clone(&self) -> Self {
+            assert!(!self.panic_on_clone, "clone panicked");
+            Self::new(self.value)
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl Drop for Fragile {
+        fn drop(&mut self) {
+            if self.panic_on_drop && !std::thread::panicking() {
+                panic!("drop panicked");
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_panicking_clone_unpins_slot() {
+        let cache: Cache<Fragile, 2> = Cache::new(Fragile {
+            panic_on_clone: true,
+            ..Fragile::new(1)
+        });
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| cache.get_data()));
+        assert!(result.is_err());
+
+        // Slot 0 must not stay pinned, otherwise the second update can't
+        // reuse it
+        cache.update(Fragile::new(2));
+        cache
+            .update_timeout(Fragile::new(3), Duration::from_millis(100))
+            .unwrap();
+        assert_eq!(cache.get_data().value, 3);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_panicking_drop_releases_writer_lock() {
+        let cache: Cache<Fragile, 2> = Cache::new(Fragile {
+            panic_on_drop: true,
+            ..Fragile::new(1)
+        });
+        cache.update(Fragile::new(2));
+
+        // Overwriting slot 0 drops the panicking value
+        let result = panic::catch_unwind(AssertUnwindSafe(|| cache.update(Fragile::new(3))));
+        assert!(result.is_err());
+
+        // The value was published before the old one was dropped, and the
+        // writer lock was released on unwind
+        assert_eq!(cache.get_data().value, 3);
+        cache
+            .update_timeout(Fragile::new(4), Duration::from_millis(100))
+            .unwrap();
+        assert_eq!(cache.get_data().value, 4);
+    }
+
+    #[test]
+    fn test_footprint() {
+        let size = size_of::<Cache<u64, 8>>();
+
+        if cfg!(feature = "padding") {
+            assert!(size > 1024, "{size}");
+        } else {
+            assert!(size < 512, "{size}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cache is empty")]
+    fn test_get_data_after_clear() {
+        let cache: Cache<u64> = Cache::new(1);
+        cache.clear();
+        cache.get_data();
+    }
 }
@@ -0,0 +1,143 @@
+use std::{fmt, sync::Arc};
+
+use crate::registry::{self, CacheInfo, CacheStatus};
+
+use super::Cache;
+
+impl<T: Clone + Send + Sync + 'static, const LEN: usize> Cache<T, LEN> {
+    // Creates a cache that shows up as `name` in `registry::dump` for as long
+    // as it is alive
+    pub fn named(name: &str, data: T) -> Arc<Self> {
+        let cache = Arc::new(Self::new(data));
+        let weak = Arc::downgrade(&cache);
+        let owned = name.to_owned();
+
+        registry::register(name, move || {
+            let cache = weak.upgrade()?;
+
+            Some(info(&owned, &cache, None))
+        });
+
+        cache
+    }
+
+    // Like `named`, with the current value included in the dump
+    pub fn named_debug(name: &str, data: T) -> Arc<Self>
+    where
+        T: fmt::Debug,
+    {
+        let cache = Arc::new(Self::new(data));
+        let weak = Arc::downgrade(&cache);
+        let owned = name.to_owned();
+
+        registry::register(name, move || {
+            let cache = weak.upgrade()?;
+            let value = cache.try_get_data().map(|data| format!("{data:?}"));
+
+            Some(info(&owned, &cache, value))
+        });
+
+        cache
+    }
+}
+
+fn info<T: Clone, const LEN: usize>(
+    name: &str,
+    cache: &Cache<T, LEN>,
+    value: Option<String>,
+) -> CacheInfo {
+    CacheInfo {
+        name: name.to_owned(),
+        generation: cache.generation(),
+        slots: LEN,
+        status: status(cache),
+        last_updated: cache.last_updated(),
+        age: cache.age(),
+        #[cfg(feature = "stats")]
+        stats: cache.stats(),
+        value,
+    }
+}
+
+fn status<T: Clone, const LEN: usize>(cache: &Cache<T, LEN>) -> CacheStatus {
+    let draining = cache
+        .items
+        .iter()
+        .any(|item| item.is_retiring() && !item.is_drained());
+
+    if draining {
+        CacheStatus::Draining
+    } else if cache.pin().0.data().is_none() {
+        CacheStatus::Empty
+    } else {
+        CacheStatus::Ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named() {
+        let routes = Cache::<Vec<u32>>::named_debug("test_named_routes", vec![1]);
+        let secret = Cache::<String, 2>::named("test_named_secret", String::from("hunter2"));
+        routes.update(vec![1, 2]);
+
+        let info = registry::find("test_named_routes").pop().unwrap();
+        assert_eq!(info.generation, 1);
+        assert_eq!(info.slots, 4);
+        assert_eq!(info.value.as_deref(), Some("[1, 2]"));
+
+        let info = registry::find("test_named_secret").pop().unwrap();
+        assert_eq!(info.slots, 2);
+        assert_eq!(info.value, None);
+
+        drop(secret);
+        assert!(registry::find("test_named_secret").is_empty());
+        assert_eq!(registry::find("test_named_routes").len(), 1);
+    }
+
+    #[test]
+    fn test_named_status() {
+        let cache = Cache::<u32>::named("test_named_status", 1);
+        let status = || registry::find("test_named_status").pop().unwrap().status;
+        assert_eq!(status(), CacheStatus::Ok);
+
+        // Another slot retired under a reader
+        let reader = cache.items[1].pin().unwrap();
+        assert!(!cache.items[1].retire());
+        assert_eq!(status(), CacheStatus::Draining);
+        drop(reader);
+        cache.items[1].release();
+
+        cache.clear();
+        assert_eq!(status(), CacheStatus::Empty);
+    }
+
+    // Values are described without the registry lock, so their `Debug` may
+    // look at the registry too
+    #[test]
+    fn test_named_debug_reentrant() {
+        struct Peek;
+
+        impl fmt::Debug for Peek {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let others = registry::find("test_named_reentrant_other").len();
+                write!(f, "Peek({others})")
+            }
+        }
+
+        impl Clone for Peek {
+            fn clone(&self) -> Self {
+                Peek
+            }
+        }
+
+        let _other = Cache::<u32>::named("test_named_reentrant_other", 0);
+        let _cache = Cache::<Peek>::named_debug("test_named_reentrant", Peek);
+
+        let info = registry::find("test_named_reentrant").pop().unwrap();
+        assert_eq!(info.value.as_deref(), Some("Peek(1)"));
+    }
+}
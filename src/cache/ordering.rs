@@ -0,0 +1,63 @@
+use crate::sync::atomic::{self, Ordering};
+
+// Orderings used by every atomic access in the cache. With the
+// `strict-ordering` feature they are all SeqCst, which is slower but rules
+// out ordering bugs when chasing a suspected race.
+const STRICT: bool = cfg!(feature = "strict-ordering");
+
+pub(crate) const RELAXED: Ordering = if STRICT {
+    Ordering::SeqCst
+} else {
+    Ordering::Relaxed
+};
+
+pub(crate) const ACQUIRE: Ordering = if STRICT {
+    Ordering::SeqCst
+} else {
+    Ordering::Acquire
+};
+
+pub(crate) const RELEASE: Ordering = if STRICT {
+    Ordering::SeqCst
+} else {
+    Ordering::Release
+};
+
+pub(crate) const ACQ_REL: Ordering = if STRICT {
+    Ordering::SeqCst
+} else {
+    Ordering::AcqRel
+};
+
+// Used where a store must be ordered before a later load on another atomic.
+// Always SeqCst.
+pub(crate) const SEQ_CST: Ordering = Ordering::SeqCst;
+
+// The read path. With the `fences` feature a reader pins and unpins its slot
+// with relaxed RMWs, and pays for a fence only where one is needed: an
+// acquire fence once the pin succeeded, none when it bounces off a retiring
+// slot, and a release fence before unpinning. The index load is relaxed
+// too, as the generation check on the pinned slot is what makes the data
+// visible. Whether this beats the plain orderings depends on the target, so
+// it stays opt-in.
+const FENCES: bool = cfg!(feature = "fences") && !STRICT;
+
+pub(crate) const STAMP: Ordering = if FENCES { RELAXED } else { ACQUIRE };
+
+pub(crate) const PIN: Ordering = if FENCES { RELAXED } else { ACQUIRE };
+
+pub(crate) const UNPIN: Ordering = if FENCES { RELAXED } else { RELEASE };
+
+#[inline]
+pub(crate) fn pinned() {
+    if FENCES {
+        atomic::fence(ACQUIRE);
+    }
+}
+
+#[inline]
+pub(crate) fn unpinning() {
+    if FENCES {
+        atomic::fence(RELEASE);
+    }
+}
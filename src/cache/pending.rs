@@ -0,0 +1,54 @@
+use alloc::boxed::Box;
+use core::{marker::PhantomData, ptr};
+
+use crate::sync::atomic::AtomicPtr;
+
+use super::ordering::{ACQ_REL, ACQUIRE};
+
+// A single-value mailbox where the latest `put` wins. Used by coalescing
+// writers to hand their value to whoever holds the writer lock.
+pub(crate) struct Pending<T> {
+    ptr: AtomicPtr<T>,
+    _marker: PhantomData<Box<T>>,
+}
+
+impl<T> Pending<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn put(&self, data: T) {
+        let new = Box::into_raw(Box::new(data));
+        let old = self.ptr.swap(new, ACQ_REL);
+
+        if !old.is_null() {
+            // Safety: every non-null pointer stored came from `Box::into_raw`
+            // and the swap gave us exclusive ownership of it
+            drop(unsafe { Box::from_raw(old) });
+        }
+    }
+
+    pub(crate) fn take(&self) -> Option<T> {
+        let old = self.ptr.swap(ptr::null_mut(), ACQ_REL);
+
+        if old.is_null() {
+            None
+        } else {
+            // Safety: see `put`
+            Some(*unsafe { Box::from_raw(old) })
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ptr.load(ACQUIRE).is_null()
+    }
+}
+
+impl<T> Drop for Pending<T> {
+    fn drop(&mut self) {
+        drop(self.take());
+    }
+}
@@ -0,0 +1,12 @@
+use super::{BackoffPolicy, LockPolicy};
+
+// Tuning knobs for how writers behave under contention.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Policy {
+    pub backoff: BackoffPolicy,
+    pub lock: LockPolicy,
+    // When set, an `update` that finds the writer lock taken leaves its value
+    // for the lock holder to publish and returns immediately. Only the latest
+    // of the values left behind is published.
+    pub coalesce: bool,
+}
@@ -0,0 +1,50 @@
+use crate::pool::{Pool, Pooled};
+
+use super::Cache;
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    // Like `get_data`, but clones into a value recycled from `pool` with
+    // `clone_from`, so buffers keep their allocation across reads. The
+    // handle gives the value back to the pool when dropped.
+    pub fn get_data_pooled<'a>(&self, pool: &'a Pool<T>) -> Pooled<'a, T> {
+        let (guard, _) = self.pin();
+        self.counters.read();
+
+        let data = guard.data().as_ref().expect("cache is empty");
+
+        let value = match pool.pull() {
+            Some(mut value) => {
+                value.clone_from(data);
+                value
+            }
+            None => data.clone(),
+        };
+
+        pool.attach(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_data_pooled() {
+        let cache: Cache<String> = Cache::new(String::from("value"));
+        let pool = Pool::new(4);
+
+        let first = cache.get_data_pooled(&pool);
+        assert_eq!(*first, "value");
+        let ptr = first.as_ptr();
+        drop(first);
+
+        // The buffer is reused
+        cache.update(String::from("other"));
+        let second = cache.get_data_pooled(&pool);
+        assert_eq!(*second, "other");
+        assert_eq!(second.as_ptr(), ptr);
+
+        #[cfg(feature = "stats")]
+        assert_eq!(cache.stats().reads, 2);
+    }
+}
@@ -0,0 +1,151 @@
+use super::{Cache, Locked, error::Rejected};
+
+// Value staged in a free slot by `Cache::prepare`, not yet visible to
+// readers. Holds the writer lock until it is committed or aborted, so keep it
+// short-lived. Dropping it aborts.
+pub struct PreparedUpdate<'a, T, const LEN: usize>
+where
+    T: Clone,
+{
+    cache: &'a Cache<T, LEN>,
+    index: usize,
+    // Value the staged one displaced, restored on abort
+    old: Option<T>,
+    committed: bool,
+    guard: Option<Locked<'a, T, LEN>>,
+}
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    // Stages `data` in a free slot without publishing it. Readers keep
+    // seeing the current value until `commit`, which only has to flip the
+    // index. Other writers wait until the update is committed or aborted.
+    pub fn prepare(&self, data: T) -> Result<PreparedUpdate<'_, T, LEN>, Rejected<T>> {
+        let data = self.validator.admit(data)?;
+        let guard = self.lock();
+
+        while let Some(data) = self.pending.take() {
+            self.publish(data);
+        }
+
+        let Some(index) = self.next_free_slot(|| false) else {
+            unreachable!("the scan only gives up once `expired` returns true");
+        };
+
+        // Safety: the slot is retired and stays so until commit or abort
+        let old = unsafe { self.items[index].replace(Some(data)) };
+
+        Ok(PreparedUpdate {
+            cache: self,
+            index,
+            old,
+            committed: false,
+            guard: Some(guard),
+        })
+    }
+}
+
+impl<T: Clone, const LEN: usize> PreparedUpdate<'_, T, LEN> {
+    // The staged value
+    pub fn data(&self) -> &T {
+        // Safety: the slot is retired and we hold the writer lock
+        let data = unsafe { self.cache.items[self.index].peek() };
+
+        data.as_ref().expect("staged slot holds the value")
+    }
+
+    pub fn commit(mut self) {
+        self.cache.flip_to(self.index);
+        self.committed = true;
+    }
+
+    // Commits as part of group generation `group`, see `UpdateGroup`
+    pub(crate) fn commit_grouped(self, group: u64) {
+        self.cache.group.set(group, self.cache.next_generation());
+        self.commit();
+    }
+
+    // Generation the staged value is published under once committed
+    #[cfg(feature = "persist")]
+    pub(crate) fn generation(&self) -> u64 {
+        self.cache.next_generation() as u64
+    }
+
+    // Commits with `source` as the publisher, like `Cache::update_tagged`
+    #[cfg(feature = "persist")]
+    pub(crate) fn commit_tagged(self, source: String) {
+        self.cache.tag.set(source, self.cache.next_generation());
+        self.commit();
+    }
+
+    // Discards the staged value and hands it back. The cache is left as if
+    // `prepare` had never been called.
+    pub fn abort(mut self) -> T {
+        self.rollback().expect("staged slot holds the value")
+    }
+
+    fn rollback(&mut self) -> Option<T> {
+        let item = &self.cache.items[self.index];
+
+        // Safety: see `data`
+        let staged = unsafe { item.replace(self.old.take()) };
+        item.release();
+
+        staged
+    }
+}
+
+impl<T: Clone, const LEN: usize> Drop for PreparedUpdate<'_, T, LEN> {
+    fn drop(&mut self) {
+        let staged = if self.committed {
+            None
+        } else {
+            self.rollback()
+        };
+
+        drop(self.guard.take());
+
+        // The displaced value on commit, the staged one on a dropped
+        // handle. Dropped last in case the destructor panics.
+        drop(self.old.take());
+        drop(staged);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_commit() {
+        let cache: Cache<String> = Cache::new(String::from("a"));
+
+        let prepared = cache.prepare(String::from("b")).unwrap();
+        assert_eq!(prepared.data(), "b");
+        assert_eq!(cache.get_data(), "a");
+        assert_eq!(cache.generation(), 0);
+
+        prepared.commit();
+        assert_eq!(cache.get_data(), "b");
+        assert_eq!(cache.generation(), 1);
+    }
+
+    #[test]
+    fn test_prepare_abort() {
+        let cache: Cache<String> = Cache::new(String::from("a"));
+        cache.update(String::from("b"));
+        cache.update(String::from("c"));
+        cache.update(String::from("d"));
+
+        // Displaces "a", which must survive the abort
+        let prepared = cache.prepare(String::from("e")).unwrap();
+        assert_eq!(prepared.abort(), "e");
+
+        drop(cache.prepare(String::from("f")).unwrap());
+        assert_eq!(cache.get_data(), "d");
+        assert_eq!(cache.generation(), 3);
+
+        // The writer lock was released
+        cache.update(String::from("g"));
+        assert_eq!(cache.get_data(), "g");
+    }
+}
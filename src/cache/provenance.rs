@@ -0,0 +1,98 @@
+use std::time::SystemTime;
+
+use crate::sync::Mutex;
+
+use super::Cache;
+
+// Who published the current value, see `Cache::current_meta`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateMeta {
+    // Source given to `update_tagged`, `None` for the other updates
+    pub source: Option<String>,
+    pub generation: u64,
+    pub published: SystemTime,
+}
+
+// Source of the last tagged update, along with the generation it was
+// published under. Untagged updates leave it alone and are told apart by
+// their generation.
+pub(crate) struct Tag {
+    last: Mutex<Option<(String, usize)>>,
+}
+
+impl Tag {
+    pub(crate) fn new() -> Self {
+        Self {
+            last: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn set(&self, source: String, generation: usize) {
+        *self.last.lock().unwrap_or_else(|err| err.into_inner()) = Some((source, generation));
+    }
+}
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    // Like `update`, recording `source` as the publisher of `data` for
+    // `current_meta`. Never coalesces.
+    pub fn update_tagged(&self, data: T, source: impl Into<String>) {
+        let Ok(data) = self.validator.admit(data) else {
+            return;
+        };
+
+        let _guard = self.lock();
+
+        while let Some(data) = self.pending.take() {
+            self.publish(data);
+        }
+
+        // Tagged ahead of the publication so readers never see the value
+        // without its source
+        self.tag.set(source.into(), self.next_generation());
+
+        self.publish(data);
+    }
+
+    pub fn current_meta(&self) -> UpdateMeta {
+        loop {
+            let generation = self.generation();
+            let published = self.last_updated();
+
+            let source = match &*self.tag.last.lock().unwrap_or_else(|err| err.into_inner()) {
+                Some((source, tagged)) if *tagged as u64 == generation => Some(source.clone()),
+                _ => None,
+            };
+
+            // Retry if an update landed while the parts were being read
+            if self.generation() == generation {
+                return UpdateMeta {
+                    source,
+                    generation,
+                    published,
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_tagged() {
+        let cache: Cache<u64> = Cache::new(1);
+        assert_eq!(cache.current_meta().source, None);
+
+        cache.update_tagged(2, "admin api");
+        let meta = cache.current_meta();
+        assert_eq!(meta.source.as_deref(), Some("admin api"));
+        assert_eq!(meta.generation, 1);
+        assert_eq!(meta.published, cache.last_updated());
+
+        cache.update(3);
+        let meta = cache.current_meta();
+        assert_eq!(meta.source, None);
+        assert_eq!(meta.generation, 2);
+    }
+}
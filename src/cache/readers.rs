@@ -0,0 +1,172 @@
+use core::{cell::Cell, marker::PhantomData};
+
+use crate::{
+    records::{Record, Records},
+    sync::atomic::{self, AtomicUsize},
+    utils::Padded,
+};
+
+use super::{
+    Cache,
+    ordering::{ACQUIRE, RELAXED, RELEASE, SEQ_CST},
+};
+
+// Registered readers of a cache, whose records writers scan before reusing
+// a slot. A record announces the slot its reader is reading plus one, or
+// zero while idle.
+pub(crate) struct Readers {
+    records: Records<Padded<AtomicUsize>>,
+}
+
+impl Readers {
+    pub(crate) fn new() -> Self {
+        Self {
+            records: Records::new(),
+        }
+    }
+
+    fn acquire_record(&self) -> &Record<Padded<AtomicUsize>> {
+        self.records.acquire(|| Padded::new(AtomicUsize::new(0)))
+    }
+
+    // Whether a registered reader may be reading slot `index`. Must be
+    // called after retiring the slot: either the reader's announcement is
+    // seen here, or the reader sees the slot retiring and backs off.
+    pub(crate) fn announced(&self, index: usize) -> bool {
+        atomic::fence(SEQ_CST);
+
+        self.records
+            .iter()
+            .any(|record| record.hazard.load(SEQ_CST) == index + 1)
+    }
+}
+
+// Read handle of a long-lived reader thread, from `Cache::reader`. Reads
+// through it announce the slot they read in a record of its own instead of
+// pinning the slot through its shared reader count, so readers on
+// different cores never write to the same cache line. Writers pay for it
+// by scanning every registered record before reusing a slot.
+pub struct ReaderHandle<'a, T: Clone, const LEN: usize = 4> {
+    cache: &'a Cache<T, LEN>,
+    record: &'a Record<Padded<AtomicUsize>>,
+    // One announcement at a time, so not shared between threads
+    _marker: PhantomData<Cell<()>>,
+}
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    // Registers a reader. The record is given back for reuse when the handle
+    // is dropped.
+    pub fn reader(&self) -> ReaderHandle<'_, T, LEN> {
+        ReaderHandle {
+            cache: self,
+            record: self.readers.acquire_record(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Clone, const LEN: usize> ReaderHandle<'_, T, LEN> {
+    pub fn get_data(&self) -> T {
+        self.try_get_data().expect("cache is empty")
+    }
+
+    pub fn try_get_data(&self) -> Option<T> {
+        let cache = self.cache;
+
+        let index = loop {
+            let stamp = cache.index.load(ACQUIRE);
+            let index = stamp & (LEN - 1);
+
+            // Values from `update_urgent` live outside the ring, where only
+            // pinning protects them
+            if stamp & Cache::<T, LEN>::URGENT != 0 {
+                return cache.pin_current().data().clone();
+            }
+
+            // Announce the slot, then check it is still current and not
+            // being retired. All SeqCst, paired with the fence in
+            // `announced`.
+            self.record.hazard.store(index + 1, SEQ_CST);
+
+            if cache.index.load(SEQ_CST) == stamp && !cache.items[index].is_retiring() {
+                break index;
+            }
+
+            crate::sync::spin_loop();
+        };
+
+        cache.counters.read();
+
+        // Safety: writers leave the slot alone while it is announced
+        let data = unsafe { cache.items[index].peek() }.clone();
+
+        self.record.hazard.store(0, RELEASE);
+
+        data
+    }
+}
+
+impl<T: Clone, const LEN: usize> Drop for ReaderHandle<'_, T, LEN> {
+    fn drop(&mut self) {
+        self.record.hazard.store(0, RELAXED);
+        self.record.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_reader_handle() {
+        let cache: Cache<String, 2> = Cache::new(String::from("a"));
+
+        let reader = cache.reader();
+        assert_eq!(reader.get_data(), "a");
+
+        cache.update(String::from("b"));
+        assert_eq!(reader.get_data(), "b");
+
+        // An announced slot holds writers off like a pinned one
+        reader.record.hazard.store(1, SEQ_CST);
+        assert!(cache.readers.announced(0));
+        assert!(!cache.readers.announced(1));
+        reader.record.hazard.store(0, SEQ_CST);
+
+        // Records are reused once handles are dropped
+        drop(reader);
+        let reader = cache.reader();
+        assert_eq!(cache.readers.records.iter().count(), 1);
+
+        cache.clear();
+        assert_eq!(reader.try_get_data(), None);
+
+        cache.update_urgent(String::from("c"));
+        assert_eq!(reader.get_data(), "c");
+        drop(reader);
+
+        cache.update(String::from("0"));
+
+        // Every value is `n` followed by `n % 10` dots, which a torn or freed
+        // read would break
+        thread::scope(|s| {
+            for _ in 0..3 {
+                s.spawn(|| {
+                    let reader = cache.reader();
+
+                    for _ in 0..2_000 {
+                        let data = reader.get_data();
+                        let n: usize = data.trim_end_matches('.').parse().unwrap();
+                        assert_eq!(data.len(), n.to_string().len() + n % 10);
+                    }
+                });
+            }
+
+            for n in 1..2_000usize {
+                cache.update(format!("{n}{}", ".".repeat(n % 10)));
+            }
+        });
+    }
+}
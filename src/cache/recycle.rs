@@ -0,0 +1,179 @@
+use super::{Cache, item::INVALID_GENERATION};
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    // Publishes a copy of the current value after `f` has changed it, with
+    // the writer lock held so no other update lands in between. The copy is
+    // made with `clone_from` into the value the claimed slot held before,
+    // reusing its allocations. Panics if the cache is empty.
+    pub fn update_in_place(&self, f: impl FnOnce(&mut T)) {
+        self.recycle(|old, current| {
+            let current = current.expect("cache is empty");
+
+            let mut data = match old {
+                Some(mut old) => {
+                    old.clone_from(current);
+                    old
+                }
+                None => current.clone(),
+            };
+
+            f(&mut data);
+            data
+        });
+    }
+
+    // Publishes the value `f` builds out of the retired value held by the
+    // slot the writer claimed, so its allocations can be reused instead of
+    // dropped, e.g. with `String::clear` and `push_str`. `f` sees `None` for a
+    // slot that was never filled or was emptied. A value refused by the
+    // validator is dropped, as with `update`.
+    pub fn update_recycle(&self, f: impl FnOnce(Option<T>) -> T) {
+        self.recycle(|old, _| f(old));
+    }
+
+    // `f` gets the claimed slot's old value along with the current one
+    fn recycle(&self, f: impl FnOnce(Option<T>, Option<&T>) -> T) {
+        let _guard = self.lock();
+
+        while let Some(data) = self.pending.take() {
+            self.publish(data);
+        }
+
+        let Some(index) = self.next_free_slot(|| false) else {
+            unreachable!("the scan only gives up once `expired` returns true");
+        };
+
+        let item = &self.items[index];
+
+        // Readers holding a stale stamp for this slot must not mistake
+        // whatever ends up in it for the value they were after
+        item.set_generation(INVALID_GENERATION);
+
+        // Safety: the slot is retired and drained until published. If `f`
+        // panics it stays retired, which only sends readers back to the
+        // index, and the next scan claims it again.
+        let old = unsafe { item.take() };
+
+        let (current, _) = self.pin();
+        let data = f(old, current.data().as_ref());
+        drop(current);
+
+        #[cfg(feature = "std")]
+        let data = self.validator.admit(data).ok();
+        #[cfg(not(feature = "std"))]
+        let data = Some(data);
+
+        match data {
+            Some(data) => self.publish_to(index, data),
+            None => item.release(),
+        }
+    }
+
+    // Fills every inactive slot holding nothing with `f()`, for
+    // `update_recycle` to hand out later. Readers never see these values.
+    pub(crate) fn fill_spare_slots(&self, mut f: impl FnMut() -> T) {
+        let _guard = self.lock();
+
+        let current_index = self.index();
+
+        for (index, item) in self.items.iter().enumerate() {
+            if index == current_index {
+                continue;
+            }
+
+            // Safety: retired and drained
+            if self.retire(index) && unsafe { item.peek() }.is_none() {
+                item.set_generation(INVALID_GENERATION);
+
+                // Safety: as above
+                unsafe { item.replace(Some(f())) };
+            }
+
+            item.release();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_in_place() {
+        let cache: Cache<Vec<u32>, 2> = Cache::new(vec![1, 2, 3]);
+
+        cache.update_in_place(|data| data.push(4));
+        assert_eq!(cache.get_data(), [1, 2, 3, 4]);
+
+        // Two updates later the ring is back on the same slot, whose buffer
+        // is reused
+        let before = cache.pin_current().data().as_ref().unwrap().as_ptr();
+        cache.update_in_place(|data| data[0] = 0);
+        cache.update_in_place(|data| data[1] = 0);
+        assert_eq!(cache.get_data(), [0, 0, 3, 4]);
+        assert_eq!(
+            cache.pin_current().data().as_ref().unwrap().as_ptr(),
+            before
+        );
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..500 {
+                        cache.update_in_place(|data| data[3] += 1);
+                    }
+                });
+            }
+        });
+        assert_eq!(cache.get_data(), [0, 0, 3, 2_004]);
+    }
+
+    #[test]
+    fn test_update_recycle() {
+        let cache: Cache<String, 2> = Cache::new(String::from("a"));
+
+        cache.update_recycle(|old| {
+            assert_eq!(old, None);
+            String::with_capacity(16) + "b"
+        });
+
+        cache.update_recycle(|old| {
+            let mut old = old.unwrap();
+            assert_eq!(old, "a");
+
+            old.clear();
+            old.push('c');
+            old
+        });
+        assert_eq!(cache.get_data(), "c");
+
+        // Writers peeking at the current value don't count as reads
+        #[cfg(feature = "stats")]
+        assert_eq!(cache.stats().reads, 1);
+
+        let mut buffer = None;
+        cache.update_recycle(|old| {
+            let old = old.unwrap();
+            buffer = Some(old.as_ptr());
+
+            let mut data = old;
+            data.clear();
+            data.push_str("reused");
+            data
+        });
+
+        // Capacity 16 fits "reused" without reallocating
+        assert_eq!(cache.get_data(), "reused");
+        assert_eq!(
+            cache.pin_current().data().as_ref().unwrap().as_ptr(),
+            buffer.unwrap()
+        );
+
+        cache.clear();
+        cache.update_recycle(|old| {
+            assert_eq!(old, None);
+            String::from("d")
+        });
+        assert_eq!(cache.get_data(), "d");
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use super::Cache;
+
+// Handle to a background thread started by `Cache::spawn_refresher`,
+// `CacheMap::spawn_sweeper`, `ShardedCounter::spawn_publisher` or
+// `FileConfig::watch`. The thread stops when the handle is dropped or `stop`
+// is called.
+pub struct Refresher {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T: Clone + Send + Sync + 'static, const LEN: usize> Cache<T, LEN> {
+    // Publishes the result of `fetch` every `interval` on a background
+    // thread. The first fetch happens after one `interval`.
+    pub fn spawn_refresher(
+        self: Arc<Self>,
+        interval: Duration,
+        mut fetch: impl FnMut() -> T + Send + 'static,
+    ) -> Refresher {
+        Refresher::spawn(interval, move || self.update(fetch()))
+    }
+}
+
+impl Refresher {
+    // Runs `tick` every `interval` until the handle is stopped
+    pub(crate) fn spawn(interval: Duration, tick: impl FnMut() + Send + 'static) -> Self {
+        Self::spawn_with(move || interval, tick)
+    }
+
+    // Like `spawn`, waiting for whatever `delay` returns before each tick
+    pub(crate) fn spawn_with(
+        mut delay: impl FnMut() -> Duration + Send + 'static,
+        mut tick: impl FnMut() + Send + 'static,
+    ) -> Self {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let signal = stop.clone();
+
+        let thread = thread::spawn(move || {
+            let (stopped, wakeup) = &*signal;
+
+            loop {
+                let (stopped, _) = wakeup
+                    .wait_timeout_while(
+                        stopped.lock().unwrap_or_else(|err| err.into_inner()),
+                        delay(),
+                        |stopped| !*stopped,
+                    )
+                    .unwrap_or_else(|err| err.into_inner());
+
+                if *stopped {
+                    return;
+                }
+
+                drop(stopped);
+
+                tick();
+            }
+        });
+
+        Self {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    // Stops the thread and waits for it to exit. A fetch already in flight
+    // is still published.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        let (stopped, wakeup) = &*self.stop;
+
+        *stopped.lock().unwrap_or_else(|err| err.into_inner()) = true;
+        wakeup.notify_one();
+
+        if let Some(thread) = self.thread.take() {
+            // A panicking `fetch` already reported itself
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Refresher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
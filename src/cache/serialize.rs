@@ -0,0 +1,20 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer, ser::Error};
+
+use super::Cache;
+
+// Serializes the current value only. Fails on a cache emptied by `clear`.
+impl<T: Clone + Serialize, const LEN: usize> Serialize for Cache<T, LEN> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.try_get_data() {
+            Some(data) => data.serialize(serializer),
+            None => Err(S::Error::custom("cache is empty")),
+        }
+    }
+}
+
+// Deserializes a value into a fresh cache with the default policy
+impl<'de, T: Clone + Deserialize<'de>, const LEN: usize> Deserialize<'de> for Cache<T, LEN> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
@@ -0,0 +1,112 @@
+use core::{
+    convert::Infallible,
+    task::{Context, Poll},
+};
+
+use super::Cache;
+
+// Writer adapter with the methods of `futures::Sink<T>`, forwarding items to
+// `Cache::update`. Wrapping it in a `Sink` takes one forwarding call per
+// method.
+//
+// With `coalescing`, items are buffered and only the latest one is published
+// on `poll_flush`. `send_all` feeds items while the stream has them ready and
+// flushes when it runs dry, so a producer outpacing the consumer only
+// publishes the values it caught up to. Anything still buffered is published
+// when the sink is dropped.
+pub struct CacheSink<'a, T, const LEN: usize = 4>
+where
+    T: Clone,
+{
+    cache: &'a Cache<T, LEN>,
+    coalescing: bool,
+    buffered: Option<T>,
+}
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    pub fn sink(&self) -> CacheSink<'_, T, LEN> {
+        CacheSink {
+            cache: self,
+            coalescing: false,
+            buffered: None,
+        }
+    }
+}
+
+impl<T: Clone, const LEN: usize> CacheSink<'_, T, LEN> {
+    pub fn coalescing(mut self) -> Self {
+        self.coalescing = true;
+        self
+    }
+
+    // Publishing never waits on readers, so the sink is always ready
+    pub fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    pub fn start_send(&mut self, item: T) -> Result<(), Infallible> {
+        if self.coalescing {
+            self.buffered = Some(item);
+        } else {
+            self.cache.update(item);
+        }
+
+        Ok(())
+    }
+
+    pub fn poll_flush(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        self.flush();
+        Poll::Ready(Ok(()))
+    }
+
+    pub fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        self.poll_flush(cx)
+    }
+
+    // Publishes the buffered item, if any, without going through a
+    // `Context`
+    pub fn flush(&mut self) {
+        if let Some(item) = self.buffered.take() {
+            self.cache.update(item);
+        }
+    }
+}
+
+impl<T: Clone, const LEN: usize> Drop for CacheSink<'_, T, LEN> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::task::Waker;
+
+    use super::*;
+
+    #[test]
+    fn test_sink() {
+        let cache: Cache<u64> = Cache::new(0);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut sink = cache.sink();
+        assert!(sink.poll_ready(&mut cx).is_ready());
+        sink.start_send(1).unwrap();
+        assert_eq!(cache.get_data(), 1);
+        drop(sink);
+
+        let mut sink = cache.sink().coalescing();
+        for i in 2..10 {
+            sink.start_send(i).unwrap();
+        }
+        assert_eq!(cache.get_data(), 1);
+
+        assert!(sink.poll_flush(&mut cx).is_ready());
+        assert_eq!(cache.get_data(), 9);
+        assert_eq!(cache.generation(), 2);
+
+        sink.start_send(10).unwrap();
+        drop(sink);
+        assert_eq!(cache.get_data(), 10);
+    }
+}
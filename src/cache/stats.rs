@@ -0,0 +1,116 @@
+#[cfg(feature = "stats")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "stats")]
+use crate::{sync::atomic::AtomicU64, utils::Padded};
+
+#[cfg(feature = "stats")]
+use super::ordering::RELAXED;
+
+// Counters collected with the `stats` feature, see `Cache::stats`. Every
+// counter only ever grows.
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheStats {
+    // Values handed out by `get_data` and friends
+    pub reads: u64,
+    // Values published by `update` and friends
+    pub writes: u64,
+    // Rounds writers spent waiting on the writer lock. Growing much faster
+    // than `writes` means writers are fighting over the lock.
+    pub lock_spins: u64,
+    // Inactive slots the free-slot scan passed over because readers still
+    // had them pinned. A ring that is too small for its readers shows up
+    // here first.
+    pub scan_skips: u64,
+    // What each slot looks like right now, in ring order
+    pub slots: Vec<SlotStats>,
+}
+
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotStats {
+    // Readers currently pinning the slot
+    pub readers: usize,
+    // Whether the slot holds the current value
+    pub current: bool,
+}
+
+// Relaxed counters bumped by the cache. Without the `stats` feature this is
+// empty and every method is a no-op.
+pub(crate) struct Counters {
+    // Bumped by every reader, so kept away from the writer-side counters
+    #[cfg(feature = "stats")]
+    reads: Padded<AtomicU64>,
+    #[cfg(feature = "stats")]
+    writes: AtomicU64,
+    #[cfg(feature = "stats")]
+    lock_spins: AtomicU64,
+    #[cfg(feature = "stats")]
+    scan_skips: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn new() -> Self {
+        Self {
+            #[cfg(feature = "stats")]
+            reads: Padded::new(AtomicU64::new(0)),
+            #[cfg(feature = "stats")]
+            writes: AtomicU64::new(0),
+            #[cfg(feature = "stats")]
+            lock_spins: AtomicU64::new(0),
+            #[cfg(feature = "stats")]
+            scan_skips: AtomicU64::new(0),
+        }
+    }
+}
+
+#[cfg(feature = "stats")]
+impl Counters {
+    #[inline]
+    pub(crate) fn read(&self) {
+        self.reads.fetch_add(1, RELAXED);
+    }
+
+    #[inline]
+    pub(crate) fn write(&self) {
+        self.writes.fetch_add(1, RELAXED);
+    }
+
+    #[inline]
+    pub(crate) fn lock_spins(&self, spins: u64) {
+        if spins > 0 {
+            self.lock_spins.fetch_add(spins, RELAXED);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn scan_skip(&self) {
+        self.scan_skips.fetch_add(1, RELAXED);
+    }
+
+    pub(crate) fn snapshot(&self, slots: Vec<SlotStats>) -> CacheStats {
+        CacheStats {
+            reads: self.reads.load(RELAXED),
+            writes: self.writes.load(RELAXED),
+            lock_spins: self.lock_spins.load(RELAXED),
+            scan_skips: self.scan_skips.load(RELAXED),
+            slots,
+        }
+    }
+}
+
+#[cfg(not(feature = "stats"))]
+impl Counters {
+    #[inline]
+    pub(crate) fn read(&self) {}
+
+    #[inline]
+    pub(crate) fn write(&self) {}
+
+    #[inline]
+    pub(crate) fn lock_spins(&self, _spins: u64) {}
+
+    #[inline]
+    pub(crate) fn scan_skip(&self) {}
+}
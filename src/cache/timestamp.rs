@@ -0,0 +1,46 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::sync::atomic::AtomicU64;
+
+use super::ordering::{ACQUIRE, RELEASE};
+
+// When the current value was published, as an offset from the moment the
+// cache was created so it fits in one atomic
+pub(crate) struct Timestamp {
+    origin: Instant,
+    origin_wall: SystemTime,
+    // Nanoseconds from `origin`
+    offset: AtomicU64,
+}
+
+impl Timestamp {
+    pub(crate) fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            origin_wall: SystemTime::now(),
+            offset: AtomicU64::new(0),
+        }
+    }
+
+    // Must be called with the writer lock held
+    pub(crate) fn touch(&self) {
+        let offset = self.origin.elapsed().as_nanos();
+
+        self.offset
+            .store(u64::try_from(offset).unwrap_or(u64::MAX), RELEASE);
+    }
+
+    fn offset(&self) -> Duration {
+        Duration::from_nanos(self.offset.load(ACQUIRE))
+    }
+
+    pub(crate) fn instant(&self) -> Instant {
+        self.origin + self.offset()
+    }
+
+    // Wall-clock time, derived from the monotonic one so later changes to
+    // the system clock don't skew it
+    pub(crate) fn wall(&self) -> SystemTime {
+        self.origin_wall + self.offset()
+    }
+}
@@ -0,0 +1,147 @@
+// `tracing` events for slow writes, under the `sloth` target. Each carries
+// the address of the cache it comes from, so a tail-latency spike can be
+// pinned on one instance. Without the `tracing` feature every function is a
+// no-op.
+
+// Rounds on the writer lock past which the wait gets reported
+#[cfg(feature = "tracing")]
+const SLOW_LOCK_SPINS: u64 = 64;
+
+#[cfg(feature = "tracing")]
+#[inline]
+pub(crate) fn lock_spins(cache: *const (), spins: u64) {
+    if spins > SLOW_LOCK_SPINS {
+        tracing::warn!(target: "sloth", cache = ?cache, spins, "writer spun on the writer lock");
+    }
+}
+
+// The free-slot scan went around the ring and found every inactive slot
+// pinned, so the writer now waits for the readers of `slot` to leave
+#[cfg(feature = "tracing")]
+#[inline]
+pub(crate) fn scan_wrapped(cache: *const (), slot: usize) {
+    tracing::warn!(target: "sloth", cache = ?cache, slot, "every inactive slot is pinned, waiting for readers");
+}
+
+#[cfg(feature = "tracing")]
+#[inline]
+pub(crate) fn published(cache: *const (), generation: usize) {
+    tracing::trace!(target: "sloth", cache = ?cache, generation, "published");
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline]
+pub(crate) fn lock_spins(_cache: *const (), _spins: u64) {}
+
+#[cfg(not(feature = "tracing"))]
+#[inline]
+pub(crate) fn scan_wrapped(_cache: *const (), _slot: usize) {}
+
+#[cfg(not(feature = "tracing"))]
+#[inline]
+pub(crate) fn published(_cache: *const (), _generation: usize) {}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::{
+        fmt::{self, Write},
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use tracing::{
+        Event, Metadata, Subscriber,
+        field::{Field, Visit},
+        span,
+    };
+
+    use crate::cache::Cache;
+
+    // Keeps the fields of every event, rendered as `name=value` pairs
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Recorder {
+        fn find(&self, message: &str) -> Vec<String> {
+            let events = self.0.lock().unwrap();
+
+            events
+                .iter()
+                .filter(|event| event.contains(message))
+                .cloned()
+                .collect()
+        }
+    }
+
+    struct Fields(String);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            let _ = write!(self.0, "{}={value:?} ", field.name());
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(String::new());
+            event.record(&mut fields);
+            self.0.lock().unwrap().push(fields.0);
+        }
+
+        fn enter(&self, _span: &span::Id) {}
+
+        fn exit(&self, _span: &span::Id) {}
+    }
+
+    #[test]
+    fn test_trace_events() {
+        let recorder = Recorder::default();
+        let cache: Cache<u64, 2> = Cache::new(0);
+
+        tracing::subscriber::with_default(recorder.clone(), || {
+            cache.update(1);
+
+            // The only inactive slot is pinned
+            let reader = cache.items[0].pin().unwrap();
+            assert!(cache.update_timeout(2, Duration::from_millis(1)).is_err());
+            drop(reader);
+        });
+
+        let published = recorder.find("published");
+        assert_eq!(published.len(), 1);
+        assert!(published[0].contains("generation=1"), "{published:?}");
+
+        let wrapped = recorder.find("every inactive slot is pinned");
+        assert_eq!(wrapped.len(), 1);
+        assert!(wrapped[0].contains("slot=0"), "{wrapped:?}");
+
+        // A writer left spinning while another holds the lock
+        let recorder = Recorder::default();
+        let guard = cache.lock();
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                tracing::subscriber::with_default(recorder.clone(), || cache.update(3));
+            });
+
+            thread::sleep(Duration::from_millis(20));
+            drop(guard);
+        });
+
+        assert_eq!(cache.get_data(), 3);
+        assert_eq!(recorder.find("writer spun on the writer lock").len(), 1);
+    }
+}
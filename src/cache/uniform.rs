@@ -0,0 +1,115 @@
+use core::hint;
+
+use crate::sync;
+
+use super::{Cache, ordering::STAMP};
+
+// Access paths for `SecretCache::with_uniform_access`. They touch every slot
+// in the same order whichever one is current or pinned, so timing and
+// memory access patterns say less about the state of the ring. Not strictly
+// constant-time: cloning the value and contention still show.
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    // `try_get_data`, loading the generation of every slot in ring order
+    // before pinning the current one
+    pub(crate) fn try_get_uniform(&self) -> Option<T> {
+        let guard = loop {
+            let stamp = self.index.load(STAMP);
+
+            let touched = self
+                .items
+                .iter()
+                .fold(0, |touched, item| touched ^ item.generation());
+            hint::black_box(touched);
+
+            if let Some(guard) = self.pin_stamp(stamp) {
+                break guard;
+            }
+
+            sync::spin_loop();
+        };
+
+        self.counters.read();
+
+        guard.data().clone()
+    }
+
+    // `update`, with a free-slot scan that retires every inactive slot
+    // instead of stopping at the first free one
+    pub(crate) fn update_uniform(&self, data: T) {
+        #[cfg(feature = "std")]
+        let Ok(data) = self.validator.admit(data) else {
+            return;
+        };
+
+        let _guard = self.lock();
+
+        while let Some(data) = self.pending.take() {
+            self.publish(data);
+        }
+
+        let current_index = self.index();
+        let mut free = None;
+
+        for offset in 1..LEN {
+            let index = (current_index + offset) & Self::LEN_MASK;
+            let drained = self.retire(index);
+
+            // Keep the first drained slot retired for `publish_to`
+            if !drained || free.is_some() {
+                self.items[index].release();
+            }
+
+            if drained && free.is_none() {
+                free = Some(index);
+            }
+        }
+
+        match free {
+            Some(index) => self.publish_to(index, data),
+            // Every inactive slot is pinned, wait like `update` does
+            None => self.publish(data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_uniform_access() {
+        let cache: Cache<u64> = Cache::new(0);
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 1..=1000 {
+                    cache.update_uniform(i);
+                }
+            });
+
+            let mut last = 0;
+
+            while last < 1000 {
+                let value = cache.try_get_uniform().unwrap();
+                assert!(value >= last);
+                last = value;
+            }
+        });
+
+        assert_eq!(cache.generation(), 1000);
+
+        // With a reader pinning the slot after the current one, the scan
+        // moves past it
+        let stamp = cache.index.load(STAMP);
+        let pinned = (stamp + 1) & Cache::<u64>::LEN_MASK;
+        let guard = cache.items[pinned].pin();
+
+        cache.update_uniform(1001);
+        assert_ne!(cache.index(), pinned);
+        assert_eq!(cache.try_get_uniform(), Some(1001));
+
+        drop(guard);
+    }
+}
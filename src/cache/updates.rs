@@ -0,0 +1,297 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use std::{
+    sync::Arc,
+    task::Wake,
+    thread::{self, Thread},
+};
+
+use crate::sync::{
+    Mutex,
+    atomic::{self, AtomicBool},
+};
+
+use super::{Cache, ordering::SEQ_CST};
+
+// Tasks waiting in `Updates` for the next publication. Writers only take the
+// mutex when someone is waiting.
+pub(crate) struct Wakers {
+    waiting: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl Wakers {
+    pub(crate) fn new() -> Self {
+        Self {
+            waiting: AtomicBool::new(false),
+            wakers: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Called by writers after publishing
+    pub(crate) fn wake(&self) {
+        // Pairs with the fence in `register`: either we see the waiter, or it
+        // sees the new generation.
+        atomic::fence(SEQ_CST);
+
+        if !self.waiting.load(SEQ_CST) {
+            return;
+        }
+
+        let wakers = {
+            let mut wakers = self.wakers.lock().unwrap_or_else(|err| err.into_inner());
+            self.waiting.store(false, SEQ_CST);
+
+            core::mem::take(&mut *wakers)
+        };
+
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    // The caller must check for a new generation again afterwards
+    fn register(&self, waker: &Waker) {
+        let mut wakers = self.wakers.lock().unwrap_or_else(|err| err.into_inner());
+
+        if !wakers.iter().any(|registered| registered.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+
+        self.waiting.store(true, SEQ_CST);
+        drop(wakers);
+
+        atomic::fence(SEQ_CST);
+    }
+}
+
+// Values published to a cache after `Cache::updates` was called, each with
+// its generation. A consumer that falls behind only gets the latest value.
+//
+// `next` and `poll_next` follow `Stream`, so wrapping this in a
+// `futures::Stream` takes a single `poll_next` forwarding call. The stream
+// never ends.
+pub struct Updates<'a, T, const LEN: usize = 4>
+where
+    T: Clone,
+{
+    cache: &'a Cache<T, LEN>,
+    seen: u64,
+}
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    pub fn updates(&self) -> Updates<'_, T, LEN> {
+        Updates {
+            cache: self,
+            seen: self.generation(),
+        }
+    }
+}
+
+impl<'b, T: Clone, const LEN: usize> Updates<'b, T, LEN> {
+    // Async, like `StreamExt::next`, so not the `Iterator` method
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Next<'_, 'b, T, LEN> {
+        Next { updates: self }
+    }
+
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<(u64, T)>> {
+        if let Some(update) = self.try_next() {
+            return Poll::Ready(Some(update));
+        }
+
+        self.cache.wakers.register(cx.waker());
+
+        match self.try_next() {
+            Some(update) => Poll::Ready(Some(update)),
+            None => Poll::Pending,
+        }
+    }
+
+    // Skips generations left empty by `clear` or `take`
+    fn try_next(&mut self) -> Option<(u64, T)> {
+        if self.cache.generation() == self.seen {
+            return None;
+        }
+
+        let (data, generation) = self.cache.try_get_with_generation()?;
+        self.seen = generation;
+
+        Some((generation, data))
+    }
+}
+
+pub struct Next<'a, 'b, T, const LEN: usize>
+where
+    T: Clone,
+{
+    updates: &'a mut Updates<'b, T, LEN>,
+}
+
+impl<T: Clone, const LEN: usize> Future for Next<'_, '_, T, LEN> {
+    type Output = Option<(u64, T)>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.updates.poll_next(cx)
+    }
+}
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    // Resolves to the first value satisfying `pred`, starting with the
+    // current one. Values published and replaced while the task wasn't
+    // polled are never seen.
+    pub fn wait_for<F: Fn(&T) -> bool>(&self, pred: F) -> WaitFor<'_, T, F, LEN> {
+        WaitFor { cache: self, pred }
+    }
+
+    // Blocking version of `wait_for`, parking the calling thread in between
+    // updates
+    pub fn wait_for_blocking(&self, pred: impl Fn(&T) -> bool) -> T {
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+
+        loop {
+            if let Some(data) = self.try_satisfy(&pred) {
+                return data;
+            }
+
+            self.wakers.register(&waker);
+
+            if let Some(data) = self.try_satisfy(&pred) {
+                return data;
+            }
+
+            thread::park();
+        }
+    }
+
+    // Parks the calling thread until the generation moves past `seen` or
+    // `done` returns true. Whoever makes `done` true must unpark the thread.
+    #[cfg(feature = "persist")]
+    pub(crate) fn park_until_updated(&self, seen: u64, done: impl Fn() -> bool) {
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+
+        while self.generation() == seen && !done() {
+            self.wakers.register(&waker);
+
+            if self.generation() != seen || done() {
+                return;
+            }
+
+            thread::park();
+        }
+    }
+
+    fn try_satisfy(&self, pred: impl Fn(&T) -> bool) -> Option<T> {
+        self.try_get_data().filter(|data| pred(data))
+    }
+}
+
+pub struct WaitFor<'a, T, F, const LEN: usize>
+where
+    T: Clone,
+{
+    cache: &'a Cache<T, LEN>,
+    pred: F,
+}
+
+impl<T: Clone, F: Fn(&T) -> bool, const LEN: usize> Future for WaitFor<'_, T, F, LEN> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let this = &*self;
+
+        if let Some(data) = this.cache.try_satisfy(&this.pred) {
+            return Poll::Ready(data);
+        }
+
+        this.cache.wakers.register(cx.waker());
+
+        match this.cache.try_satisfy(&this.pred) {
+            Some(data) => Poll::Ready(data),
+            None => Poll::Pending,
+        }
+    }
+}
+
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+
+    use super::*;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn test_updates() {
+        let cache: Cache<u64> = Cache::new(0);
+        let mut updates = cache.updates();
+
+        // Latest wins for a lagging consumer
+        cache.update(1);
+        cache.update(2);
+        assert_eq!(block_on(updates.next()), Some((2, 2)));
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 3..=100 {
+                    cache.update(i);
+                }
+            });
+
+            let mut last = 2;
+
+            while last < 100 {
+                let (generation, value) = block_on(updates.next()).unwrap();
+                assert!(value > last);
+                assert_eq!(generation, value);
+                last = value;
+            }
+        });
+    }
+
+    #[test]
+    fn test_wait_for() {
+        let cache: Cache<Vec<u32>> = Cache::new(vec![]);
+        assert!(block_on(cache.wait_for(|shards| shards.is_empty())).is_empty());
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for shard in 0..20 {
+                    cache.update_with(|shards| {
+                        let mut shards = shards.unwrap().clone();
+                        shards.push(shard);
+                        shards
+                    });
+                }
+            });
+
+            let shards = block_on(cache.wait_for(|shards| shards.contains(&7)));
+            assert!(shards.contains(&7));
+
+            let shards = cache.wait_for_blocking(|shards| shards.len() == 20);
+            assert_eq!(shards, (0..20).collect::<Vec<_>>());
+        });
+    }
+}
@@ -0,0 +1,376 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::{marker::PhantomData, ptr};
+
+use crate::{sync::atomic::AtomicPtr, utils::Backoff};
+
+use super::{
+    Cache,
+    item::{INVALID_GENERATION, Item},
+    ordering::{ACQUIRE, RELEASE},
+    trace,
+};
+
+struct Node<T> {
+    item: Item<T>,
+    next: *mut Node<T>,
+}
+
+// Slots outside the ring for `update_urgent`. A new one is allocated
+// whenever every existing one is pinned, so publishing never waits for
+// readers. Nodes are only freed with the cache, their values are dropped
+// when the node is reused, on `drop_stale` or on `clear`.
+pub(crate) struct Overflow<T> {
+    nodes: AtomicPtr<Node<T>>,
+    // Node the current value lives in while the index has `URGENT` set
+    current: AtomicPtr<Item<T>>,
+    _marker: PhantomData<Item<T>>,
+}
+
+impl<T> Overflow<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            nodes: AtomicPtr::new(ptr::null_mut()),
+            current: AtomicPtr::new(ptr::null_mut()),
+            _marker: PhantomData,
+        }
+    }
+
+    fn items(&self) -> impl Iterator<Item = &Item<T>> {
+        let mut next = self.nodes.load(ACQUIRE);
+
+        core::iter::from_fn(move || {
+            // Safety: nodes are only freed with the cache
+            let node = unsafe { next.as_ref() }?;
+            next = node.next;
+
+            Some(&node.item)
+        })
+    }
+
+    // `None` before the first urgent update. A reader that loaded the
+    // index with relaxed ordering may also get an older node, which it tells
+    // apart by its generation.
+    pub(crate) fn current(&self) -> Option<&Item<T>> {
+        // Safety: nodes are only freed with the cache
+        unsafe { self.current.load(ACQUIRE).as_ref() }
+    }
+
+    // Must be called with the writer lock held. Returns a retired and
+    // drained node other than `current`.
+    fn claim(&self, current: &Item<T>) -> &Item<T> {
+        for item in self.items() {
+            if ptr::eq(item, current) {
+                continue;
+            }
+
+            if item.retire() {
+                return item;
+            }
+
+            item.release();
+        }
+
+        let node = Box::into_raw(Box::new(Node {
+            item: Item::new(),
+            next: self.nodes.load(ACQUIRE),
+        }));
+
+        self.nodes.store(node, RELEASE);
+
+        // Safety: just allocated, freed with the cache
+        let item = unsafe { &(*node).item };
+        item.retire();
+
+        item
+    }
+}
+
+impl<T> Drop for Overflow<T> {
+    fn drop(&mut self) {
+        let mut next = self.nodes.load(ACQUIRE);
+
+        while !next.is_null() {
+            // Safety: `&mut self` means no reader is left
+            let node = unsafe { Box::from_raw(next) };
+            next = node.next;
+        }
+    }
+}
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    // Publishes `data` without waiting for readers to leave a slot: the
+    // value goes into an overflow slot outside the ring that is allocated
+    // if every existing one is pinned, and the next regular update moves
+    // the cache back onto the ring. A writer holding the lock while it
+    // waits for readers of a ring slot publishes the value on our behalf.
+    // Meant for values such as kill switches that must land under any
+    // reader load, at the price of an allocation every time readers pin all
+    // the overflow slots. A value refused by the validator is dropped.
+    //
+    // Holders that wait on anything else are waited for: a `PreparedUpdate`
+    // until it is committed or aborted, which for `persist::ChangeLog`
+    // includes syncing the log, and `clear` or `take` while readers pin an
+    // overflow slot they empty.
+    pub fn update_urgent(&self, data: T) {
+        #[cfg(feature = "std")]
+        let Ok(data) = self.validator.admit(data) else {
+            return;
+        };
+
+        self.urgent.put(data);
+
+        // Returns once the value, or an even newer urgent one, was taken by
+        // whoever holds the lock
+        let backoff = Backoff::new();
+
+        while !self.urgent.is_empty() {
+            if let Some(guard) = self.writing.try_lock() {
+                let _guard = self.locked(guard);
+                self.publish_urgent();
+                break;
+            }
+
+            self.backoff.wait(&backoff);
+        }
+    }
+
+    // Publishes the value handed over by `update_urgent`, if any. Must be
+    // called with the writer lock held.
+    pub(crate) fn publish_urgent(&self) {
+        let Some(data) = self.urgent.take() else {
+            return;
+        };
+
+        let item = self.overflow.claim(self.current_item());
+
+        // Safety: retired and drained
+        let old = unsafe { item.replace(Some(data)) };
+        let generation = self.next_generation();
+
+        #[cfg(feature = "std")]
+        self.updated.touch();
+        item.set_generation(generation);
+        self.overflow
+            .current
+            .store(ptr::from_ref(item).cast_mut(), RELEASE);
+
+        // The ring slot bits keep pointing at the last regular value, so the
+        // free slot scan still leaves it alone
+        self.index.store(
+            Self::stamp(generation, self.index()) | Self::URGENT,
+            RELEASE,
+        );
+        item.release();
+        self.counters.write();
+        trace::published(self.id(), generation);
+
+        #[cfg(feature = "std")]
+        self.wakers.wake();
+
+        drop(old);
+    }
+
+    // Slot holding the current value, in the ring or not
+    pub(crate) fn current_item(&self) -> &Item<T> {
+        let stamp = self.index.load(ACQUIRE);
+
+        if stamp & Self::URGENT != 0 {
+            // Set before the index, which we loaded with acquire ordering
+            self.overflow.current().unwrap()
+        } else {
+            &self.items[stamp & Self::LEN_MASK]
+        }
+    }
+
+    // Drops the values of the overflow slots other than the current one
+    // that no reader is using. Must be called with the writer lock held.
+    pub(crate) fn drop_stale_overflow(&self) {
+        let current = ptr::from_ref(self.current_item());
+
+        for item in self.overflow.items() {
+            if ptr::eq(item, current) {
+                continue;
+            }
+
+            let mut stale = None;
+
+            if item.retire() {
+                // Safety: retired and drained
+                stale = unsafe { item.take() };
+                item.set_generation(INVALID_GENERATION);
+            }
+
+            item.release();
+
+            drop(stale);
+        }
+    }
+
+    // Retires every overflow slot and takes its value, for `clear`. Must be
+    // called with the writer lock held, and followed by
+    // `release_overflow` once the index moved on.
+    pub(crate) fn retire_overflow(&self) -> Vec<T> {
+        self.overflow
+            .items()
+            .filter_map(|item| {
+                let backoff = Backoff::new();
+
+                item.retire();
+
+                while !item.is_drained() {
+                    self.backoff.wait(&backoff);
+                }
+
+                item.set_generation(INVALID_GENERATION);
+
+                // Safety: retired and drained
+                unsafe { item.take() }
+            })
+            .collect()
+    }
+
+    pub(crate) fn release_overflow(&self) {
+        for item in self.overflow.items() {
+            item.release();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_update_urgent() {
+        let cache: Cache<u32, 2> = Cache::new(0);
+
+        cache.update_urgent(1);
+        assert_eq!(cache.get_data(), 1);
+        assert_eq!(cache.generation(), 1);
+
+        cache.update(2);
+        assert_eq!(cache.get_data(), 2);
+        assert_eq!(cache.try_get_with_generation(), Some((2, 2)));
+
+        // Readers pinning both overflow slots and the urgent value don't
+        // hold it up
+        cache.update_urgent(3);
+        let first = cache.pin_current();
+        cache.update_urgent(4);
+        let second = cache.pin_current();
+        cache.update_urgent(5);
+        assert_eq!((first.data(), second.data()), (&Some(3), &Some(4)));
+        assert_eq!(cache.overflow.items().count(), 3);
+        assert_eq!(cache.get_data(), 5);
+        drop((first, second));
+
+        cache.drop_stale();
+        assert_eq!(
+            cache
+                .overflow
+                .items()
+                .map(|item| unsafe { *item.peek() })
+                .collect::<Vec<_>>(),
+            [Some(5), None, None]
+        );
+
+        assert_eq!(cache.take(), Some(5));
+        assert_eq!(cache.try_get_data(), None);
+
+        cache.update_urgent(6);
+        cache.clear();
+        assert_eq!(cache.try_get_data(), None);
+        assert!(
+            cache
+                .overflow
+                .items()
+                .all(|item| unsafe { item.peek() }.is_none())
+        );
+    }
+
+    #[test]
+    fn test_update_urgent_preempts_stuck_writer() {
+        let cache: Cache<u32, 2> = Cache::new(0);
+        cache.update(1);
+
+        // Both ring slots pinned: a regular update waits for slot 0 to be
+        // released, and publishes the urgent value meanwhile
+        let pinned = cache.items[0].pin().unwrap();
+        let current = cache.pin_current();
+
+        thread::scope(|s| {
+            let writer = s.spawn(|| cache.update(2));
+
+            while !cache.items[0].state().1 {
+                thread::yield_now();
+            }
+
+            cache.update_urgent(3);
+            assert_eq!(cache.get_data(), 3);
+            assert!(!writer.is_finished());
+
+            drop(pinned);
+        });
+
+        drop(current);
+        assert_eq!(cache.get_data(), 2);
+        assert_eq!(cache.generation(), 3);
+    }
+
+    // `clear` and `take` waiting for readers of a ring slot publish urgent
+    // values meanwhile, like the free-slot scan
+    #[test]
+    fn test_update_urgent_preempts_clear_and_take() {
+        let cache: Cache<u32, 2> = Cache::new(0);
+        let reader = cache.items[0].pin().unwrap();
+
+        thread::scope(|s| {
+            s.spawn(|| cache.clear());
+
+            while cache.writing.try_lock().is_some() {
+                thread::yield_now();
+            }
+
+            // Returns while our own reader still holds `clear` up
+            cache.update_urgent(1);
+            drop(reader);
+        });
+        assert_eq!(cache.try_get_data(), None);
+
+        cache.update(2);
+        let reader = cache.pin_current();
+
+        thread::scope(|s| {
+            let taker = s.spawn(|| cache.take());
+
+            while cache.writing.try_lock().is_some() {
+                thread::yield_now();
+            }
+
+            cache.update_urgent(3);
+            drop(reader);
+
+            assert_eq!(taker.join().unwrap(), Some(2));
+        });
+        assert_eq!(cache.get_data(), 3);
+    }
+
+    #[test]
+    fn test_update_urgent_publishes_coalesced() {
+        let cache: Cache<u32> = Cache::with_policy(
+            0,
+            crate::cache::Policy {
+                coalesce: true,
+                ..Default::default()
+            },
+        );
+
+        // As left behind by an update that found the lock taken
+        cache.pending.put(2);
+        cache.update_urgent(1);
+        assert_eq!(cache.get_data(), 2);
+        assert!(cache.pending.is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+use std::{error::Error, sync::Arc};
+
+use crate::sync::{Mutex, atomic::AtomicBool};
+
+use super::{
+    Cache,
+    error::Rejected,
+    ordering::{ACQUIRE, RELEASE},
+};
+
+type Check<T> = Arc<dyn Fn(&T) -> Result<(), Box<dyn Error + Send + Sync>> + Send + Sync>;
+
+// Check every published value has to pass, see `Cache::set_validator`.
+// Updates of a cache that has none only load `installed`, and never take
+// the lock.
+pub(crate) struct Validator<T> {
+    installed: AtomicBool,
+    check: Mutex<Option<Check<T>>>,
+}
+
+impl<T> Validator<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            installed: AtomicBool::new(false),
+            check: Mutex::new(None),
+        }
+    }
+
+    fn get(&self) -> Option<Check<T>> {
+        if !self.installed.load(ACQUIRE) {
+            return None;
+        }
+
+        self.check
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    fn set(&self, check: Option<Check<T>>) {
+        let mut current = self.check.lock().unwrap_or_else(|err| err.into_inner());
+
+        // Under the lock, so racing calls leave the flag matching the check
+        self.installed.store(check.is_some(), RELEASE);
+        *current = check;
+    }
+
+    // Hands `data` back if it passes, or if there is no validator
+    pub(crate) fn admit(&self, data: T) -> Result<T, Rejected<T>> {
+        let Some(check) = self.get() else {
+            return Ok(data);
+        };
+
+        match check(&data) {
+            Ok(()) => Ok(data),
+            Err(error) => Err(Rejected { data, error }),
+        }
+    }
+}
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    // Makes every update run `validator` on the new value first. Values it
+    // refuses are never published: `try_publish` returns them along with the
+    // error, the other updates drop them and keep the current value.
+    //
+    // Replaces any previous validator. The current value is not checked.
+    pub fn set_validator<E>(&self, validator: impl Fn(&T) -> Result<(), E> + Send + Sync + 'static)
+    where
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        self.validator.set(Some(Arc::new(move |data: &T| {
+            validator(data).map_err(Into::into)
+        })));
+    }
+
+    pub fn clear_validator(&self) {
+        self.validator.set(None);
+    }
+
+    // Like `update`, but reports a value refused by the validator instead of
+    // dropping it
+    pub fn try_publish(&self, data: T) -> Result<(), Rejected<T>> {
+        let data = self.validator.admit(data)?;
+
+        self.update_admitted(data);
+        Ok(())
+    }
+
+    // Carries the validator over to a clone of the cache
+    pub(crate) fn copy_validator(&self, to: &Self) {
+        to.validator.set(self.validator.get());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validator() {
+        let cache: Cache<i64> = Cache::new(1);
+        assert!(!cache.validator.installed.load(ACQUIRE));
+
+        cache.set_validator(|data: &i64| {
+            if *data >= 0 {
+                Ok(())
+            } else {
+                Err(format!("{data} is negative"))
+            }
+        });
+
+        let rejected = cache.try_publish(-2).unwrap_err();
+        assert_eq!(rejected.error.to_string(), "-2 is negative");
+        assert_eq!(rejected.into_inner(), -2);
+        assert_eq!(cache.get_data(), 1);
+
+        cache.update(-3);
+        cache.update_with(|_| -4);
+        assert_eq!(cache.get_data(), 1);
+        assert_eq!(cache.generation(), 0);
+
+        cache.try_publish(5).unwrap();
+        assert_eq!(cache.get_data(), 5);
+
+        let clone = cache.clone();
+        clone.update(-6);
+        assert_eq!(clone.get_data(), 5);
+
+        cache.clear_validator();
+        assert!(!cache.validator.installed.load(ACQUIRE));
+        cache.update(-7);
+        assert_eq!(cache.get_data(), -7);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::cache::{Cache, Policy};
+
+// Cell that may hold a value, with `take` moving it out. Built on `Cache`,
+// so `peek_clone` never blocks, not even while another thread takes or sets
+// the value.
+pub struct OptionCell<T>
+where
+    T: Clone,
+{
+    cache: Cache<T>,
+}
+
+impl<T: Clone> OptionCell<T> {
+    pub fn new() -> Self {
+        Self {
+            cache: Cache::empty(Policy::default()),
+        }
+    }
+
+    pub fn with_value(data: T) -> Self {
+        Self {
+            cache: Cache::new(data),
+        }
+    }
+
+    pub fn set(&self, data: T) {
+        self.cache.update(data);
+    }
+
+    pub fn take(&self) -> Option<T> {
+        self.cache.take()
+    }
+
+    pub fn peek_clone(&self) -> Option<T> {
+        self.cache.try_get_data()
+    }
+
+    pub fn is_some(&self) -> bool {
+        self.peek_clone().is_some()
+    }
+}
+
+impl<T: Clone> Default for OptionCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_option_cell() {
+        let cell = OptionCell::new();
+        assert_eq!(cell.take(), None);
+
+        cell.set("stop");
+        assert!(cell.is_some());
+        assert_eq!(cell.peek_clone(), Some("stop"));
+        assert_eq!(cell.take(), Some("stop"));
+        assert_eq!(cell.peek_clone(), None);
+
+        let cell = OptionCell::with_value(1);
+        cell.set(2);
+        assert_eq!(cell.take(), Some(2));
+    }
+
+    #[test]
+    fn test_every_value_is_taken_once() {
+        let cell = OptionCell::new();
+
+        let mut taken = thread::scope(|s| {
+            s.spawn(|| {
+                for i in 1..=1_000u64 {
+                    cell.set(i);
+                }
+            });
+
+            let takers: Vec<_> = (0..2)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut taken = Vec::new();
+
+                        for _ in 0..1_000 {
+                            taken.extend(cell.take());
+                        }
+
+                        taken
+                    })
+                })
+                .collect();
+
+            takers
+                .into_iter()
+                .flat_map(|taker| taker.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        taken.extend(cell.take());
+
+        // Values overwritten before being taken are lost, but none is taken
+        // twice
+        let count = taken.len();
+        taken.sort_unstable();
+        taken.dedup();
+        assert_eq!(taken.len(), count, "{taken:?}");
+        assert!(taken.iter().all(|value| (1..=1_000).contains(value)));
+    }
+}
@@ -0,0 +1,99 @@
+use serde_json::{Map, Value};
+
+// Separates nested keys in variable names, `APP_DB__PORT` sets `db.port`
+const SEPARATOR: &str = "__";
+
+// Overrides fields of `value` with the variables starting with `prefix`
+// followed by `_`. The rest of the name is lowercased and split on `__` into
+// a path of object keys, missing objects are created along the way. Values
+// are parsed as JSON and taken as plain strings if that fails, so `8080` is
+// a number and `localhost` a string.
+pub(crate) fn overlay(
+    value: &mut Value,
+    prefix: &str,
+    vars: impl IntoIterator<Item = (String, String)>,
+) {
+    let prefix = format!("{prefix}_");
+
+    for (name, raw) in vars {
+        let Some(path) = name.strip_prefix(&prefix) else {
+            continue;
+        };
+
+        let keys: Vec<String> = path.split(SEPARATOR).map(str::to_lowercase).collect();
+
+        if keys.iter().any(String::is_empty) {
+            continue;
+        }
+
+        let parsed = serde_json::from_str(&raw).unwrap_or(Value::String(raw));
+
+        set(value, &keys, parsed);
+    }
+}
+
+fn set(value: &mut Value, keys: &[String], new: Value) {
+    let Some((last, parents)) = keys.split_last() else {
+        return;
+    };
+
+    let mut current = value;
+
+    for key in parents {
+        current = object(current)
+            .entry(key.as_str())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+
+    object(current).insert(last.clone(), new);
+}
+
+// Overriding a nested key of a non-object replaces it with an object
+fn object(value: &mut Value) -> &mut Map<String, Value> {
+    if !value.is_object() {
+        *value = Value::Object(Map::new());
+    }
+
+    match value {
+        Value::Object(map) => map,
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_overlay() {
+        let mut value = json!({
+            "name": "file",
+            "db": { "host": "db.local", "port": 5432 },
+            "debug": false,
+        });
+
+        let vars = [
+            ("APP_DB__PORT", "6543"),
+            ("APP_DEBUG", "true"),
+            ("APP_NAME", "env"),
+            ("APP_CACHE__TTL", "30"),
+            ("OTHER_NAME", "ignored"),
+            ("APP_DB____HOST", "ignored"),
+        ]
+        .map(|(name, value)| (name.to_owned(), value.to_owned()));
+
+        overlay(&mut value, "APP", vars);
+
+        assert_eq!(
+            value,
+            json!({
+                "name": "env",
+                "db": { "host": "db.local", "port": 6543 },
+                "debug": true,
+                "cache": { "ttl": 30 },
+            })
+        );
+    }
+}
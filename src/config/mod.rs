@@ -0,0 +1,199 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::cache::{Cache, Refresher};
+
+mod env;
+mod error;
+
+pub use error::ConfigError;
+
+// JSON config file loaded into a `Cache`. `reload` republishes the file if it
+// changed on disk, `watch` does so periodically on a background thread.
+//
+// With `load_with_env`, environment variables are layered on top of the file
+// every time it is loaded, see `env::overlay`.
+pub struct FileConfig<T>
+where
+    T: Clone,
+{
+    path: PathBuf,
+    env_prefix: Option<String>,
+    cache: Cache<T>,
+    // Modification time of the file the current value was loaded from
+    modified: Mutex<Option<SystemTime>>,
+}
+
+impl<T> FileConfig<T>
+where
+    T: Clone + DeserializeOwned,
+{
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, ConfigError> {
+        Self::with_env_prefix(path.into(), None)
+    }
+
+    // Like `load`, but variables named `{prefix}_{KEY}` override `key`
+    pub fn load_with_env(
+        path: impl Into<PathBuf>,
+        prefix: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::with_env_prefix(path.into(), Some(prefix.into()))
+    }
+
+    fn with_env_prefix(path: PathBuf, env_prefix: Option<String>) -> Result<Self, ConfigError> {
+        let modified = modified(&path);
+        let data = Self::read(&path, env_prefix.as_deref())?;
+
+        Ok(Self {
+            path,
+            env_prefix,
+            cache: Cache::new(data),
+            modified: Mutex::new(modified),
+        })
+    }
+
+    pub fn get(&self) -> T {
+        self.cache.get_data()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Re-reads the file if its modification time changed and returns whether
+    // a new value was published. On error the current value is kept.
+    pub fn reload(&self) -> Result<bool, ConfigError> {
+        let mut last = self.modified.lock().unwrap_or_else(|err| err.into_inner());
+
+        let modified = modified(&self.path);
+
+        if modified.is_some() && modified == *last {
+            return Ok(false);
+        }
+
+        self.cache
+            .update(Self::read(&self.path, self.env_prefix.as_deref())?);
+        *last = modified;
+
+        Ok(true)
+    }
+
+    fn read(path: &Path, env_prefix: Option<&str>) -> Result<T, ConfigError> {
+        let mut value: Value = serde_json::from_slice(&fs::read(path)?)?;
+
+        if let Some(prefix) = env_prefix {
+            env::overlay(&mut value, prefix, std::env::vars());
+        }
+
+        Ok(T::deserialize(value)?)
+    }
+}
+
+impl<T> FileConfig<T>
+where
+    T: Clone + DeserializeOwned + Send + Sync + 'static,
+{
+    // Polls the file every `interval` and reloads it when it changes. A file
+    // that fails to load keeps the last good value and is read again on
+    // every poll until it loads, so a write caught halfway is picked up once
+    // it completes even if the modification time doesn't move.
+    pub fn watch(self: Arc<Self>, interval: Duration) -> Refresher {
+        Refresher::spawn(interval, move || {
+            let _ = self.reload();
+        })
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    struct Settings {
+        name: String,
+        workers: u64,
+    }
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("sloth-{}-{name}.json", std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    // Bumps the modification time even on filesystems with coarse timestamps
+    fn rewrite(path: &Path, contents: &str) {
+        let before = modified(path);
+
+        while modified(path) == before {
+            thread::sleep(Duration::from_millis(10));
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_file_config() {
+        let path = temp_file("reload", r#"{"name": "a", "workers": 1}"#);
+        let config: FileConfig<Settings> = FileConfig::load(&path).unwrap();
+        assert_eq!(config.get().workers, 1);
+
+        assert!(!config.reload().unwrap());
+
+        rewrite(&path, r#"{"name": "b", "workers": 2}"#);
+        assert!(config.reload().unwrap());
+        assert_eq!(config.get().name, "b");
+
+        // Broken files keep the last good value and are read again until
+        // they load
+        rewrite(&path, "{");
+        assert!(matches!(config.reload(), Err(ConfigError::Parse(_))));
+        assert!(matches!(config.reload(), Err(ConfigError::Parse(_))));
+        assert_eq!(config.get().workers, 2);
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(config.reload(), Err(ConfigError::Io(_))));
+        assert!(FileConfig::<Settings>::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_env_overlay() {
+        let path = temp_file("env", r#"{"name": "a", "workers": 1}"#);
+
+        // Cargo sets `CARGO_PKG_NAME` for the test binary
+        let config: FileConfig<Settings> = FileConfig::load_with_env(&path, "CARGO_PKG").unwrap();
+        assert_eq!(config.get().name, "sloth");
+        assert_eq!(config.get().workers, 1);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_watch() {
+        let path = temp_file("watch", r#"{"name": "a", "workers": 1}"#);
+        let config: Arc<FileConfig<Settings>> = Arc::new(FileConfig::load(&path).unwrap());
+
+        let watcher = config.clone().watch(Duration::from_millis(1));
+        rewrite(&path, r#"{"name": "a", "workers": 5}"#);
+
+        while config.get().workers != 5 {
+            thread::yield_now();
+        }
+
+        watcher.stop();
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    cell::Cell,
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    cache::{Cache, Refresher},
+    utils::Padded,
+};
+
+// Threads are handed shards round-robin on their first add, so up to as
+// many threads as there are shards never share one
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static SHARD: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+fn shard_of_thread() -> usize {
+    SHARD.with(|shard| {
+        shard.get().unwrap_or_else(|| {
+            let next = NEXT_SHARD.fetch_add(1, Ordering::Relaxed);
+            shard.set(Some(next));
+            next
+        })
+    })
+}
+
+// Counter for hot paths: adds go to a shard of the calling thread with a
+// relaxed add, and readers get the total folded from all shards through a
+// `Cache<u64>`, either by calling `snapshot` or from a background thread
+// started with `spawn_publisher`. Published totals never go backwards and
+// include every add that happened before the fold started.
+pub struct ShardedCounter {
+    shards: Box<[Padded<AtomicU64>]>,
+    total: Cache<u64>,
+}
+
+impl ShardedCounter {
+    // One shard per available core
+    pub fn new() -> Self {
+        let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+
+        Self::with_shards(cores)
+    }
+
+    // Panics if `shards` is zero
+    pub fn with_shards(shards: usize) -> Self {
+        assert!(shards > 0, "a counter needs at least one shard");
+
+        Self {
+            shards: (0..shards)
+                .map(|_| Padded::new(AtomicU64::new(0)))
+                .collect(),
+            total: Cache::new(0),
+        }
+    }
+
+    #[inline]
+    pub fn add(&self, n: u64) {
+        let shard = &self.shards[shard_of_thread() % self.shards.len()];
+
+        shard.fetch_add(n, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn increment(&self) {
+        self.add(1);
+    }
+
+    // Folds the shards into a new total, publishes and returns it
+    pub fn snapshot(&self) -> u64 {
+        let folded = self
+            .shards
+            .iter()
+            .map(|shard| shard.load(Ordering::Relaxed))
+            .fold(0, u64::wrapping_add);
+
+        // Folds racing each other may finish out of order, the larger one
+        // holds every add the smaller one saw
+        self.total
+            .update_with(|current| current.map_or(folded, |current| folded.max(*current)));
+
+        self.total.get_data()
+    }
+
+    // Total as of the last `snapshot`, a single read of the cache
+    pub fn total(&self) -> u64 {
+        self.total.get_data()
+    }
+
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    // Calls `snapshot` every `interval` on a background thread, so readers
+    // only ever need `total`
+    pub fn spawn_publisher(self: Arc<Self>, interval: Duration) -> Refresher {
+        Refresher::spawn(interval, move || {
+            self.snapshot();
+        })
+    }
+}
+
+impl Default for ShardedCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ShardedCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedCounter")
+            .field("total", &self.total())
+            .field("shards", &self.shards())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sharded_counter() {
+        let counter = ShardedCounter::with_shards(4);
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..1_000 {
+                        counter.increment();
+                    }
+                });
+            }
+        });
+        counter.add(5);
+
+        // Only folded on demand
+        assert_eq!(counter.total(), 0);
+        assert_eq!(counter.snapshot(), 4_005);
+        assert_eq!(counter.total(), 4_005);
+
+        let counter = Arc::new(ShardedCounter::new());
+        let publisher = counter.clone().spawn_publisher(Duration::from_millis(1));
+        counter.add(3);
+
+        while counter.total() < 3 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        publisher.stop();
+        assert_eq!(counter.total(), 3);
+    }
+}
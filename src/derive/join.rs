@@ -0,0 +1,120 @@
+use crate::cache::{Cache, Policy};
+
+// Value combined from two caches by `join`, memoized along with both source
+// generations and recomputed once either source is updated. Like
+// `MappedCache`, racing readers may each run `f` on a fresh update.
+pub struct Joined<'a, A, B, U, F, const LA: usize = 4, const LB: usize = 4>
+where
+    A: Clone,
+    B: Clone,
+    U: Clone,
+{
+    left: &'a Cache<A, LA>,
+    right: &'a Cache<B, LB>,
+    f: F,
+    memo: Cache<(U, u64, u64)>,
+}
+
+pub fn join<'a, A, B, U, F, const LA: usize, const LB: usize>(
+    left: &'a Cache<A, LA>,
+    right: &'a Cache<B, LB>,
+    f: F,
+) -> Joined<'a, A, B, U, F, LA, LB>
+where
+    A: Clone,
+    B: Clone,
+    U: Clone,
+    F: Fn(&A, &B) -> U,
+{
+    Joined {
+        left,
+        right,
+        f,
+        memo: Cache::empty(Policy::default()),
+    }
+}
+
+impl<A, B, U, F, const LA: usize, const LB: usize> Joined<'_, A, B, U, F, LA, LB>
+where
+    A: Clone,
+    B: Clone,
+    U: Clone,
+    F: Fn(&A, &B) -> U,
+{
+    // Panics if either source cache is empty
+    pub fn get(&self) -> U {
+        let generations = (self.left.generation(), self.right.generation());
+
+        if let Some((data, left, right)) = self.memo.try_get_data()
+            && (left, right) == generations
+        {
+            return data;
+        }
+
+        let (a, left) = self.left.try_get_with_generation().expect("cache is empty");
+        let (b, right) = self
+            .right
+            .try_get_with_generation()
+            .expect("cache is empty");
+        let data = (self.f)(&a, &b);
+
+        // Generations only grow, so keep whichever result saw newer sources
+        self.memo.update_with(|current| match current {
+            Some((current, l, r)) if *l >= left && *r >= right && (*l, *r) != (left, right) => {
+                (current.clone(), *l, *r)
+            }
+            _ => (data.clone(), left, right),
+        });
+
+        data
+    }
+
+    // Whether the next `get` has to run `f`
+    pub fn is_stale(&self) -> bool {
+        let generations = (self.left.generation(), self.right.generation());
+
+        !matches!(self.memo.try_get_data(), Some((_, l, r)) if (l, r) == generations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn test_join() {
+        let file: Cache<Vec<(&str, u32)>> = Cache::new(vec![("timeout", 5), ("retries", 3)]);
+        let overrides: Cache<Vec<(&str, u32)>, 2> = Cache::new(vec![]);
+        let calls = Cell::new(0);
+
+        let effective = join(&file, &overrides, |file, overrides| {
+            calls.set(calls.get() + 1);
+
+            file.iter()
+                .map(|&(key, value)| {
+                    let value = overrides
+                        .iter()
+                        .find(|(name, _)| *name == key)
+                        .map_or(value, |&(_, value)| value);
+
+                    (key, value)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(effective.get(), [("timeout", 5), ("retries", 3)]);
+        assert_eq!(effective.get(), [("timeout", 5), ("retries", 3)]);
+        assert_eq!(calls.get(), 1);
+
+        overrides.update(vec![("retries", 1)]);
+        assert!(effective.is_stale());
+        assert_eq!(effective.get(), [("timeout", 5), ("retries", 1)]);
+
+        file.update(vec![("timeout", 9)]);
+        assert_eq!(effective.get(), [("timeout", 9)]);
+        assert_eq!(effective.get(), [("timeout", 9)]);
+        assert_eq!(calls.get(), 3);
+    }
+}
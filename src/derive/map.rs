@@ -0,0 +1,99 @@
+use crate::cache::{Cache, Policy};
+
+// Value computed from a source cache by `Cache::map_view`. The result is
+// memoized along with the source generation it was computed from, so reads
+// only run `f` again once the source has been updated. Readers racing on a
+// fresh update may each compute it; the result of the newest source
+// generation wins.
+pub struct MappedCache<'a, T, U, F, const LEN: usize = 4>
+where
+    T: Clone,
+    U: Clone,
+{
+    source: &'a Cache<T, LEN>,
+    f: F,
+    memo: Cache<(U, u64)>,
+}
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    pub fn map_view<U, F>(&self, f: F) -> MappedCache<'_, T, U, F, LEN>
+    where
+        U: Clone,
+        F: Fn(&T) -> U,
+    {
+        MappedCache {
+            source: self,
+            f,
+            memo: Cache::empty(Policy::default()),
+        }
+    }
+}
+
+impl<T, U, F, const LEN: usize> MappedCache<'_, T, U, F, LEN>
+where
+    T: Clone,
+    U: Clone,
+    F: Fn(&T) -> U,
+{
+    // Panics if the source cache is empty
+    pub fn get(&self) -> U {
+        let generation = self.source.generation();
+
+        if let Some((data, computed)) = self.memo.try_get_data()
+            && computed == generation
+        {
+            return data;
+        }
+
+        let (source, generation) = self
+            .source
+            .try_get_with_generation()
+            .expect("cache is empty");
+        let data = (self.f)(&source);
+
+        self.memo.update_with(|current| match current {
+            Some((current, computed)) if *computed > generation => (current.clone(), *computed),
+            _ => (data.clone(), generation),
+        });
+
+        data
+    }
+
+    // Whether the next `get` has to run `f`
+    pub fn is_stale(&self) -> bool {
+        !matches!(self.memo.try_get_data(), Some((_, computed)) if computed == self.source.generation())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn test_map_view() {
+        let raw: Cache<String> = Cache::new(String::from("1,2,3"));
+        let calls = Cell::new(0);
+
+        let parsed = raw.map_view(|raw| {
+            calls.set(calls.get() + 1);
+            raw.split(',')
+                .map(|n| n.parse().unwrap())
+                .collect::<Vec<u32>>()
+        });
+        assert!(parsed.is_stale());
+
+        for _ in 0..10 {
+            assert_eq!(parsed.get(), [1, 2, 3]);
+        }
+        assert_eq!(calls.get(), 1);
+        assert!(!parsed.is_stale());
+
+        raw.update(String::from("4,5"));
+        assert!(parsed.is_stale());
+        assert_eq!(parsed.get(), [4, 5]);
+        assert_eq!(parsed.get(), [4, 5]);
+        assert_eq!(calls.get(), 2);
+    }
+}
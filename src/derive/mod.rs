@@ -0,0 +1,6 @@
+// Values derived from caches and recomputed only when their sources change
+mod join;
+mod map;
+
+pub use join::{Joined, join};
+pub use map::MappedCache;
@@ -0,0 +1,187 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{cache::Cache, utils::Backoff};
+
+// What callers other than the refreshing one do with an expired value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnStale {
+    // Return the expired value right away
+    #[default]
+    Serve,
+    // Wait for the refresh to publish a new value
+    Block,
+}
+
+#[derive(Clone)]
+struct Entry<T> {
+    value: T,
+    fetched: Instant,
+}
+
+// Cache that refreshes its value through `refresh` once it is older than
+// `max_age`. Only one caller runs `refresh` at a time.
+pub struct ExpiringCache<T, F>
+where
+    T: Clone,
+{
+    cache: Cache<Entry<T>>,
+    max_age: Duration,
+    on_stale: OnStale,
+    refresh: F,
+    refreshing: AtomicBool,
+}
+
+impl<T, F> ExpiringCache<T, F>
+where
+    T: Clone,
+    F: Fn() -> T,
+{
+    // Runs `refresh` once to get the initial value
+    pub fn new(max_age: Duration, refresh: F) -> Self {
+        Self::with_on_stale(max_age, OnStale::default(), refresh)
+    }
+
+    pub fn with_on_stale(max_age: Duration, on_stale: OnStale, refresh: F) -> Self {
+        let entry = Entry {
+            value: refresh(),
+            fetched: Instant::now(),
+        };
+
+        Self {
+            cache: Cache::new(entry),
+            max_age,
+            on_stale,
+            refresh,
+            refreshing: AtomicBool::new(false),
+        }
+    }
+
+    pub fn get_data(&self) -> T {
+        let backoff = Backoff::new();
+
+        loop {
+            let entry = self.cache.get_data();
+
+            if entry.fetched.elapsed() < self.max_age {
+                return entry.value;
+            }
+
+            if self.try_refresh() {
+                return self.cache.get_data().value;
+            }
+
+            match self.on_stale {
+                OnStale::Serve => return entry.value,
+                OnStale::Block => backoff.snooze(),
+            }
+        }
+    }
+
+    // Refreshes the value now, unless another caller already is
+    pub fn refresh(&self) {
+        self.try_refresh();
+    }
+
+    // Age of the current value
+    pub fn age(&self) -> Duration {
+        self.cache.get_data().fetched.elapsed()
+    }
+
+    fn try_refresh(&self) -> bool {
+        if self
+            .refreshing
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+
+        // Let the next caller retry if `refresh` panics
+        let _guard = RefreshGuard(&self.refreshing);
+
+        let value = (self.refresh)();
+
+        self.cache.update(Entry {
+            value,
+            fetched: Instant::now(),
+        });
+
+        true
+    }
+}
+
+struct RefreshGuard<'a>(&'a AtomicBool);
+
+impl Drop for RefreshGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU64;
+
+    use super::*;
+
+    #[test]
+    fn test_expiring_cache() {
+        let calls = AtomicU64::new(0);
+        let cache = ExpiringCache::new(Duration::from_millis(50), || {
+            calls.fetch_add(1, Ordering::AcqRel) + 1
+        });
+
+        assert_eq!(cache.get_data(), 1);
+        assert_eq!(cache.get_data(), 1);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(cache.get_data(), 2);
+        assert_eq!(calls.load(Ordering::Acquire), 2);
+
+        cache.refresh();
+        assert_eq!(cache.get_data(), 3);
+    }
+
+    #[test]
+    fn test_single_refresher() {
+        for on_stale in [OnStale::Serve, OnStale::Block] {
+            let calls = AtomicU64::new(0);
+            let cache = ExpiringCache::with_on_stale(Duration::ZERO, on_stale, || {
+                std::thread::sleep(Duration::from_millis(1));
+                calls.fetch_add(1, Ordering::AcqRel)
+            });
+
+            std::thread::scope(|s| {
+                for _ in 0..4 {
+                    s.spawn(|| {
+                        for _ in 0..10 {
+                            cache.get_data();
+                        }
+                    });
+                }
+            });
+
+            // Never more refreshes than reads, however they interleave
+            assert!(calls.load(Ordering::Acquire) <= 41);
+        }
+    }
+
+    #[test]
+    fn test_panicking_refresh() {
+        let fail = AtomicBool::new(false);
+        let cache = ExpiringCache::new(Duration::ZERO, || {
+            assert!(!fail.load(Ordering::Acquire), "refresh failed");
+            1
+        });
+
+        fail.store(true, Ordering::Release);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| cache.get_data()));
+        assert!(result.is_err());
+
+        fail.store(false, Ordering::Release);
+        assert_eq!(cache.get_data(), 1);
+    }
+}
@@ -0,0 +1,177 @@
+// C API over `BytesCache`, for services written in other languages. The
+// functions are declared in `include/sloth.h`, generated by cbindgen from
+// the `///` comments below, which is why they are the only ones in the
+// crate. Build a linkable library with `make build-ffi`.
+//
+// Values are plain byte buffers: `sloth_cache_update` copies the caller's
+// buffer, `sloth_cache_get` hands out a reference-counted `SlothBytes`
+// that stays valid until freed, however often the cache is updated.
+
+use core::{ptr, slice};
+
+use crate::bytes::{BytesCache, Payload};
+
+pub struct SlothCache {
+    cache: BytesCache,
+}
+
+pub struct SlothBytes {
+    payload: Payload,
+}
+
+// Borrows `len` bytes at `data`, which may be null when `len` is zero
+unsafe fn borrow<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        return &[];
+    }
+
+    unsafe { slice::from_raw_parts(data, len) }
+}
+
+/// Copies `len` bytes at `data` as the initial value. Free the cache with
+/// sloth_cache_free.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, or may be NULL when `len` is 0.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sloth_cache_new(data: *const u8, len: usize) -> *mut SlothCache {
+    let data = unsafe { borrow(data, len) };
+
+    Box::into_raw(Box::new(SlothCache {
+        cache: BytesCache::new(data),
+    }))
+}
+
+/// The current value. Never blocks. Free the handle with sloth_bytes_free;
+/// it stays valid after the cache is updated or freed.
+///
+/// # Safety
+///
+/// `cache` must come from sloth_cache_new and not have been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sloth_cache_get(cache: *const SlothCache) -> *mut SlothBytes {
+    let cache = unsafe { &*cache };
+
+    Box::into_raw(Box::new(SlothBytes {
+        payload: cache.cache.get(),
+    }))
+}
+
+/// Copies `len` bytes at `data` and publishes them. Safe to call from any
+/// number of threads at once.
+///
+/// # Safety
+///
+/// `cache` must come from sloth_cache_new and not have been freed. `data`
+/// must point to `len` readable bytes, or may be NULL when `len` is 0.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sloth_cache_update(cache: *const SlothCache, data: *const u8, len: usize) {
+    let cache = unsafe { &*cache };
+    let data = unsafe { borrow(data, len) };
+
+    cache.cache.update(data);
+}
+
+/// Bumped by every update.
+///
+/// # Safety
+///
+/// `cache` must come from sloth_cache_new and not have been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sloth_cache_generation(cache: *const SlothCache) -> u64 {
+    let cache = unsafe { &*cache };
+
+    cache.cache.generation()
+}
+
+/// NULL is ignored, like with free.
+///
+/// # Safety
+///
+/// `cache` must be NULL or come from sloth_cache_new and not have been
+/// freed, and no other thread may still be using it.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sloth_cache_free(cache: *mut SlothCache) {
+    if !cache.is_null() {
+        drop(unsafe { Box::from_raw(cache) });
+    }
+}
+
+/// The bytes of the value, or NULL for an empty one.
+///
+/// # Safety
+///
+/// `bytes` must come from sloth_cache_get and not have been freed. The
+/// returned pointer is valid until `bytes` is freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sloth_bytes_data(bytes: *const SlothBytes) -> *const u8 {
+    let bytes = unsafe { &*bytes };
+
+    if bytes.payload.is_empty() {
+        return ptr::null();
+    }
+
+    bytes.payload.as_ptr()
+}
+
+/// The length of the value.
+///
+/// # Safety
+///
+/// `bytes` must come from sloth_cache_get and not have been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sloth_bytes_len(bytes: *const SlothBytes) -> usize {
+    let bytes = unsafe { &*bytes };
+
+    bytes.payload.len()
+}
+
+/// NULL is ignored, like with free.
+///
+/// # Safety
+///
+/// `bytes` must be NULL or come from sloth_cache_get and not have been
+/// freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sloth_bytes_free(bytes: *mut SlothBytes) {
+    if !bytes.is_null() {
+        drop(unsafe { Box::from_raw(bytes) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn read(bytes: *const SlothBytes) -> Vec<u8> {
+        unsafe { borrow(sloth_bytes_data(bytes), sloth_bytes_len(bytes)) }.to_vec()
+    }
+
+    #[test]
+    fn test_ffi() {
+        unsafe {
+            let cache = sloth_cache_new(b"v1".as_ptr(), 2);
+            let v1 = sloth_cache_get(cache);
+
+            sloth_cache_update(cache, b"v2!".as_ptr(), 3);
+            assert_eq!(sloth_cache_generation(cache), 1);
+
+            // Handles outlive updates
+            let v2 = sloth_cache_get(cache);
+            assert_eq!(read(v1), b"v1");
+            assert_eq!(read(v2), b"v2!");
+
+            sloth_cache_update(cache, ptr::null(), 0);
+            let empty = sloth_cache_get(cache);
+            assert!(sloth_bytes_data(empty).is_null());
+            assert_eq!(sloth_bytes_len(empty), 0);
+
+            sloth_bytes_free(v1);
+            sloth_bytes_free(v2);
+            sloth_bytes_free(empty);
+            sloth_cache_free(cache);
+            sloth_cache_free(ptr::null_mut());
+        }
+    }
+}
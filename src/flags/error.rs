@@ -0,0 +1,44 @@
+use std::{error::Error, fmt};
+
+use super::FlagKind;
+
+#[derive(Debug)]
+pub enum FlagError {
+    // No flag with this name was defined
+    Unknown(String),
+    // The value doesn't match the kind the flag was defined with
+    Kind { name: String, expected: FlagKind },
+    // Percentages must be between 0 and 100
+    OutOfRange(String),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for FlagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlagError::Unknown(name) => write!(f, "unknown flag `{name}`"),
+            FlagError::Kind { name, expected } => {
+                write!(f, "flag `{name}` expects a {expected} value")
+            }
+            FlagError::OutOfRange(name) => {
+                write!(f, "flag `{name}` must be between 0 and 100")
+            }
+            FlagError::Json(err) => write!(f, "invalid flag document: {err}"),
+        }
+    }
+}
+
+impl Error for FlagError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FlagError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for FlagError {
+    fn from(err: serde_json::Error) -> Self {
+        FlagError::Json(err)
+    }
+}
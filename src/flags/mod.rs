@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::map::CacheMap;
+
+mod error;
+mod rules;
+mod value;
+
+pub use error::FlagError;
+pub use rules::{Context, Rule};
+pub use value::{FlagKind, FlagValue};
+
+#[derive(Clone)]
+struct Flag {
+    default: FlagValue,
+    value: FlagValue,
+    rules: Vec<Rule>,
+}
+
+// Typed feature flags. Flags are defined once with a default, which also
+// fixes their kind, and can then be overridden one by one or in bulk from a
+// JSON document. Lookups read a shared snapshot and never lock.
+//
+// `enabled` additionally evaluates targeting rules against a `Context`.
+#[derive(Default)]
+pub struct FlagSet {
+    flags: CacheMap<String, Flag>,
+}
+
+impl FlagSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Defines `name`, or redefines it and drops its override and rules
+    pub fn define(&self, name: impl Into<String>, default: impl Into<FlagValue>) {
+        let default = default.into();
+
+        self.flags.insert(
+            name.into(),
+            Flag {
+                value: default.clone(),
+                default,
+                rules: Vec::new(),
+            },
+        );
+    }
+
+    pub fn get(&self, name: &str) -> Option<FlagValue> {
+        self.flags
+            .snapshot()
+            .get(name)
+            .map(|flag| flag.value.clone())
+    }
+
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        match self.flags.snapshot().get(name)?.value {
+            FlagValue::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn int(&self, name: &str) -> Option<i64> {
+        match self.flags.snapshot().get(name)?.value {
+            FlagValue::Int(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn string(&self, name: &str) -> Option<String> {
+        match &self.flags.snapshot().get(name)?.value {
+            FlagValue::String(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn percentage(&self, name: &str) -> Option<f64> {
+        match self.flags.snapshot().get(name)?.value {
+            FlagValue::Percentage(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    // Whether `name` is on for `context`: it is if any of its rules matches,
+    // and otherwise if its value is `true` or, for percentages, if the
+    // context's key falls within the rollout. Unknown flags are off.
+    pub fn enabled(&self, name: &str, context: &Context) -> bool {
+        let flags = self.flags.snapshot();
+
+        let Some(flag) = flags.get(name) else {
+            return false;
+        };
+
+        if flag.rules.iter().any(|rule| rule.matches(name, context)) {
+            return true;
+        }
+
+        match flag.value {
+            FlagValue::Bool(value) => value,
+            FlagValue::Percentage(percentage) => rules::in_rollout(name, context, percentage),
+            _ => false,
+        }
+    }
+
+    // Replaces the rules of `name` in one update
+    pub fn set_rules(&self, name: &str, rules: Vec<Rule>) -> Result<(), FlagError> {
+        if !rules.iter().all(Rule::is_valid) {
+            return Err(FlagError::OutOfRange(name.to_owned()));
+        }
+
+        let mut found = false;
+
+        self.flags.modify(|flags| {
+            if let Some(flag) = flags.get_mut(name) {
+                flag.rules = rules;
+                found = true;
+            }
+        });
+
+        found
+            .then_some(())
+            .ok_or_else(|| FlagError::Unknown(name.to_owned()))
+    }
+
+    pub fn set(&self, name: &str, value: impl Into<FlagValue>) -> Result<(), FlagError> {
+        self.set_all([(name.to_owned(), value.into())])
+    }
+
+    // Goes back to the default `name` was defined with
+    pub fn reset(&self, name: &str) -> Result<(), FlagError> {
+        let mut found = false;
+
+        self.flags.modify(|flags| {
+            if let Some(flag) = flags.get_mut(name) {
+                flag.value = flag.default.clone();
+                found = true;
+            }
+        });
+
+        found
+            .then_some(())
+            .ok_or_else(|| FlagError::Unknown(name.to_owned()))
+    }
+
+    // Overrides flags from a JSON object mapping names to values, as one
+    // update. Nothing is changed if any entry is invalid.
+    pub fn update_from_json(&self, json: &str) -> Result<(), FlagError> {
+        let document: HashMap<String, Value> = serde_json::from_str(json)?;
+        let flags = self.flags.snapshot();
+
+        let values = document
+            .into_iter()
+            .map(|(name, value)| {
+                let Some(flag) = flags.get(&name) else {
+                    return Err(FlagError::Unknown(name));
+                };
+
+                let expected = flag.default.kind();
+
+                match FlagValue::from_json(expected, &value) {
+                    Some(value) => Ok((name, value)),
+                    None => Err(FlagError::Kind { name, expected }),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.set_all(values)
+    }
+
+    fn set_all(
+        &self,
+        values: impl IntoIterator<Item = (String, FlagValue)>,
+    ) -> Result<(), FlagError> {
+        let mut result = Ok(());
+
+        self.flags.modify(|flags| {
+            let mut updated = flags.clone();
+
+            for (name, value) in values {
+                let Some(flag) = updated.get_mut(&name) else {
+                    result = Err(FlagError::Unknown(name));
+                    return;
+                };
+
+                let expected = flag.default.kind();
+
+                if value.kind() != expected {
+                    result = Err(FlagError::Kind { name, expected });
+                    return;
+                }
+
+                if !value.is_valid() {
+                    result = Err(FlagError::OutOfRange(name));
+                    return;
+                }
+
+                flag.value = value;
+            }
+
+            *flags = updated;
+        });
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag_set() -> FlagSet {
+        let flags = FlagSet::new();
+        flags.define("dark_mode", false);
+        flags.define("max_items", 10);
+        flags.define("banner", "hello");
+        flags.define("rollout", FlagValue::Percentage(0.0));
+        flags
+    }
+
+    #[test]
+    fn test_flag_set() {
+        let flags = flag_set();
+        assert_eq!(flags.bool("dark_mode"), Some(false));
+        assert_eq!(flags.int("max_items"), Some(10));
+        assert_eq!(flags.string("banner").as_deref(), Some("hello"));
+        assert_eq!(flags.bool("max_items"), None);
+        assert_eq!(flags.get("missing"), None);
+
+        flags.set("dark_mode", true).unwrap();
+        assert_eq!(flags.bool("dark_mode"), Some(true));
+
+        assert!(matches!(
+            flags.set("dark_mode", 1),
+            Err(FlagError::Kind {
+                expected: FlagKind::Bool,
+                ..
+            })
+        ));
+        assert!(matches!(
+            flags.set("rollout", FlagValue::Percentage(101.0)),
+            Err(FlagError::OutOfRange(_))
+        ));
+        assert!(matches!(
+            flags.set("missing", 1),
+            Err(FlagError::Unknown(_))
+        ));
+
+        flags.reset("dark_mode").unwrap();
+        assert_eq!(flags.bool("dark_mode"), Some(false));
+    }
+
+    #[test]
+    fn test_enabled() {
+        let flags = flag_set();
+        let alice = Context::with_key("alice").attribute("country", "NZ");
+        let bob = Context::with_key("bob").attribute("country", "US");
+
+        assert!(!flags.enabled("dark_mode", &alice));
+        assert!(!flags.enabled("missing", &alice));
+
+        flags
+            .set_rules(
+                "dark_mode",
+                vec![
+                    Rule::Allow(vec!["bob".to_owned()]),
+                    Rule::Attribute {
+                        name: "country".to_owned(),
+                        values: vec!["NZ".to_owned()],
+                    },
+                ],
+            )
+            .unwrap();
+        assert!(flags.enabled("dark_mode", &alice));
+        assert!(flags.enabled("dark_mode", &bob));
+        assert!(!flags.enabled("dark_mode", &Context::with_key("carol")));
+
+        // Percentage flags roll out by key
+        assert!(!flags.enabled("rollout", &alice));
+        flags.set("rollout", FlagValue::Percentage(100.0)).unwrap();
+        assert!(flags.enabled("rollout", &alice));
+        assert!(!flags.enabled("rollout", &Context::new()));
+
+        assert!(matches!(
+            flags.set_rules("dark_mode", vec![Rule::Percentage(-1.0)]),
+            Err(FlagError::OutOfRange(_))
+        ));
+        assert!(matches!(
+            flags.set_rules("missing", Vec::new()),
+            Err(FlagError::Unknown(_))
+        ));
+    }
+
+    #[test]
+    fn test_update_from_json() {
+        let flags = flag_set();
+
+        flags
+            .update_from_json(r#"{"dark_mode": true, "max_items": 20, "rollout": 12.5}"#)
+            .unwrap();
+        assert_eq!(flags.bool("dark_mode"), Some(true));
+        assert_eq!(flags.int("max_items"), Some(20));
+        assert_eq!(flags.percentage("rollout"), Some(12.5));
+
+        // A single bad entry rejects the whole document
+        let result = flags.update_from_json(r#"{"max_items": 30, "banner": 1}"#);
+        assert!(matches!(result, Err(FlagError::Kind { .. })));
+        assert_eq!(flags.int("max_items"), Some(20));
+
+        let result = flags.update_from_json(r#"{"max_items": 30, "rollout": 200}"#);
+        assert!(matches!(result, Err(FlagError::OutOfRange(_))));
+        assert_eq!(flags.int("max_items"), Some(20));
+
+        assert!(matches!(
+            flags.update_from_json("[]"),
+            Err(FlagError::Json(_))
+        ));
+    }
+}
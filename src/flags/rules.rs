@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+// Who a flag is evaluated for
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Context {
+    // Stable identifier, such as a user id, that percentage rollouts bucket on
+    pub key: Option<String>,
+    pub attributes: HashMap<String, String>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_key(key: impl Into<String>) -> Self {
+        Self {
+            key: Some(key.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+}
+
+// Targeting rule enabling a flag for the contexts it matches
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    // Contexts whose key is in the list
+    Allow(Vec<String>),
+    // Contexts whose attribute `name` is one of `values`
+    Attribute { name: String, values: Vec<String> },
+    // A stable share of keys, from 0 to 100. Contexts without a key never
+    // match.
+    Percentage(f64),
+}
+
+impl Rule {
+    pub(crate) fn matches(&self, flag: &str, context: &Context) -> bool {
+        match self {
+            Rule::Allow(keys) => context.key.as_ref().is_some_and(|key| keys.contains(key)),
+            Rule::Attribute { name, values } => context
+                .attributes
+                .get(name)
+                .is_some_and(|value| values.contains(value)),
+            Rule::Percentage(percentage) => in_rollout(flag, context, *percentage),
+        }
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        match self {
+            Rule::Percentage(percentage) => (0.0..=100.0).contains(percentage),
+            _ => true,
+        }
+    }
+}
+
+// Basis points are fine-grained enough for rollouts
+const BUCKETS: u64 = 10_000;
+
+// Whether the context's key falls in the first `percentage` of buckets for
+// `flag`. Hashing the flag name along with the key keeps rollouts of
+// different flags independent of each other.
+pub(crate) fn in_rollout(flag: &str, context: &Context, percentage: f64) -> bool {
+    let Some(key) = &context.key else {
+        return false;
+    };
+
+    bucket(flag, key) < (percentage * (BUCKETS / 100) as f64) as u64
+}
+
+// FNV-1a, which unlike `DefaultHasher` is stable across processes and Rust
+// versions, so a key keeps its bucket across restarts
+fn bucket(flag: &str, key: &str) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let bytes = flag.bytes().chain([0xff]).chain(key.bytes());
+    let hash = bytes.fold(OFFSET, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    });
+
+    hash % BUCKETS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollout_buckets() {
+        let enabled = (0..10_000)
+            .filter(|i| in_rollout("flag", &Context::with_key(i.to_string()), 25.0))
+            .count();
+        assert!((2_000..3_000).contains(&enabled), "{enabled}");
+
+        // Stable for a key, independent across flags
+        let context = Context::with_key("user-1");
+        assert_eq!(bucket("flag", "user-1"), bucket("flag", "user-1"));
+        assert_ne!(bucket("flag", "user-1"), bucket("other", "user-1"));
+        assert!(in_rollout("flag", &context, 100.0));
+        assert!(!in_rollout("flag", &context, 0.0));
+        assert!(!in_rollout("flag", &Context::new(), 100.0));
+    }
+}
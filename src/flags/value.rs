@@ -0,0 +1,85 @@
+use core::fmt;
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagKind {
+    Bool,
+    Int,
+    String,
+    // A share of traffic, from 0 to 100
+    Percentage,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlagValue {
+    Bool(bool),
+    Int(i64),
+    String(String),
+    Percentage(f64),
+}
+
+impl FlagValue {
+    pub fn kind(&self) -> FlagKind {
+        match self {
+            FlagValue::Bool(_) => FlagKind::Bool,
+            FlagValue::Int(_) => FlagKind::Int,
+            FlagValue::String(_) => FlagKind::String,
+            FlagValue::Percentage(_) => FlagKind::Percentage,
+        }
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        match self {
+            FlagValue::Percentage(percentage) => (0.0..=100.0).contains(percentage),
+            _ => true,
+        }
+    }
+
+    // Reads a JSON value as a flag of the given kind
+    pub(crate) fn from_json(kind: FlagKind, value: &Value) -> Option<Self> {
+        let value = match kind {
+            FlagKind::Bool => FlagValue::Bool(value.as_bool()?),
+            FlagKind::Int => FlagValue::Int(value.as_i64()?),
+            FlagKind::String => FlagValue::String(value.as_str()?.to_owned()),
+            FlagKind::Percentage => FlagValue::Percentage(value.as_f64()?),
+        };
+
+        Some(value)
+    }
+}
+
+impl fmt::Display for FlagKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FlagKind::Bool => "bool",
+            FlagKind::Int => "int",
+            FlagKind::String => "string",
+            FlagKind::Percentage => "percentage",
+        })
+    }
+}
+
+impl From<bool> for FlagValue {
+    fn from(value: bool) -> Self {
+        FlagValue::Bool(value)
+    }
+}
+
+impl From<i64> for FlagValue {
+    fn from(value: i64) -> Self {
+        FlagValue::Int(value)
+    }
+}
+
+impl From<&str> for FlagValue {
+    fn from(value: &str) -> Self {
+        FlagValue::String(value.to_owned())
+    }
+}
+
+impl From<String> for FlagValue {
+    fn from(value: String) -> Self {
+        FlagValue::String(value)
+    }
+}
@@ -0,0 +1,107 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::cache::PreparedUpdate;
+
+mod snapshot;
+
+pub use snapshot::snapshot;
+#[doc(hidden)]
+pub use snapshot::{consistent, read};
+
+// Shared by every group so values from different commits never share a
+// group generation
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+// Prepared updates to several caches, possibly holding different types,
+// committed together under one group generation. Readers compare
+// `Cache::group_generation` to tell whether their values come from the same
+// commit.
+//
+// Each prepared update holds its cache's writer lock until the group is
+// committed or dropped. Prepare the caches of a group in the same order
+// everywhere, or two groups can deadlock each other.
+#[derive(Default)]
+pub struct UpdateGroup<'a> {
+    updates: Vec<Box<dyn Staged + 'a>>,
+}
+
+// A `PreparedUpdate` with its value type erased
+trait Staged {
+    fn commit_grouped(self: Box<Self>, group: u64);
+}
+
+impl<T: Clone, const LEN: usize> Staged for PreparedUpdate<'_, T, LEN> {
+    fn commit_grouped(self: Box<Self>, group: u64) {
+        PreparedUpdate::commit_grouped(*self, group);
+    }
+}
+
+impl<'a> UpdateGroup<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add<T: Clone + 'a, const LEN: usize>(&mut self, update: PreparedUpdate<'a, T, LEN>) {
+        self.updates.push(Box::new(update));
+    }
+
+    pub fn len(&self) -> usize {
+        self.updates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+
+    // Publishes every staged value and returns their group generation.
+    // The caches are flipped one after the other, so a reader may briefly
+    // see some of them updated and not the others, but never with matching
+    // group generations.
+    pub fn commit(self) -> u64 {
+        let group = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+
+        for update in self.updates {
+            update.commit_grouped(group);
+        }
+
+        group
+    }
+
+    // Aborts every staged update, as does dropping the group
+    pub fn abort(self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::Cache;
+
+    use super::*;
+
+    #[test]
+    fn test_update_group() {
+        let routes: Cache<Vec<&str>> = Cache::new(vec!["a"]);
+        let checksum: Cache<u64, 2> = Cache::new(1);
+        assert_eq!(routes.group_generation(), None);
+
+        let mut group = UpdateGroup::new();
+        group.add(routes.prepare(vec!["a", "b"]).unwrap());
+        group.add(checksum.prepare(2).unwrap());
+        assert_eq!(group.len(), 2);
+
+        let generation = group.commit();
+        assert_eq!(
+            routes.get_with_group(),
+            Some((vec!["a", "b"], Some(generation)))
+        );
+        assert_eq!(checksum.group_generation(), Some(generation));
+
+        let mut group = UpdateGroup::new();
+        group.add(routes.prepare(vec!["c"]).unwrap());
+        group.abort();
+        assert_eq!(routes.get_data(), ["a", "b"]);
+
+        checksum.update(3);
+        assert_eq!(checksum.group_generation(), None);
+        assert_eq!(routes.group_generation(), Some(generation));
+    }
+}
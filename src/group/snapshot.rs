@@ -0,0 +1,96 @@
+use crate::cache::Cache;
+
+// Reads one value from each cache, retrying until all of them report the
+// same group generation, and evaluates to a tuple of the values:
+//
+//     let (routes, checksum) = sloth::group::snapshot![routes, checksum];
+//
+// Values published by plain updates have no group generation and only match
+// each other. If one of the caches is also updated outside of groups, the
+// snapshot spins until the others catch up with it. The cache expressions
+// are evaluated on every attempt.
+//
+// Panics if one of the caches is empty.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __group_snapshot {
+    ($($cache:expr),+ $(,)?) => {{
+        let mut generations = ::std::vec::Vec::new();
+
+        loop {
+            generations.clear();
+
+            let values = ($($crate::group::read(&$cache, &mut generations),)+);
+
+            if $crate::group::consistent(&generations) {
+                break values;
+            }
+
+            ::std::thread::yield_now();
+        }
+    }};
+}
+
+pub use crate::__group_snapshot as snapshot;
+
+// Reads the current value for `snapshot!`, recording its group generation
+#[doc(hidden)]
+pub fn read<T: Clone, const LEN: usize>(
+    cache: &Cache<T, LEN>,
+    generations: &mut Vec<Option<u64>>,
+) -> T {
+    let (data, group) = cache.get_with_group().expect("cache is empty");
+    generations.push(group);
+
+    data
+}
+
+#[doc(hidden)]
+pub fn consistent(generations: &[Option<u64>]) -> bool {
+    generations.windows(2).all(|pair| pair[0] == pair[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+    };
+
+    use crate::group::UpdateGroup;
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let left: Cache<u64> = Cache::new(0);
+        let right: Cache<String, 2> = Cache::new(String::from("0"));
+        let done = AtomicBool::new(false);
+
+        assert_eq!(snapshot![left, right], (0, String::from("0")));
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 1..=200u64 {
+                    let mut group = UpdateGroup::new();
+                    group.add(left.prepare(i).unwrap());
+                    group.add(right.prepare(i.to_string()).unwrap());
+                    group.commit();
+                }
+
+                done.store(true, Ordering::Release);
+            });
+
+            loop {
+                let finished = done.load(Ordering::Acquire);
+                let (number, text) = snapshot![left, right];
+                assert_eq!(number.to_string(), text);
+
+                if finished {
+                    assert_eq!(number, 200);
+                    break;
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,233 @@
+use core::{
+    marker::PhantomData,
+    ops::Deref,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+use std::sync::Mutex;
+
+use crate::records::{Record, Records};
+
+// Retired values are only scanned for reclamation once this many piled up
+const SCAN_THRESHOLD: usize = 16;
+
+// Same API as `RcuCell`, but reclaimed through hazard pointers: readers
+// announce the value they borrow, and writers free retired values that no
+// reader announced. Reclamation happens in the writer that crosses
+// `SCAN_THRESHOLD` instead of whenever the epoch advances, so there are no
+// collector pauses and at most a bounded number of stale values.
+pub struct HpCell<T> {
+    data: AtomicPtr<T>,
+    // Hazard slots of the readers, each announcing the value it borrows
+    records: Records<AtomicPtr<T>>,
+    retired: Mutex<Vec<*mut T>>,
+}
+
+// Safety:
+// - `data` and `retired` own boxed values, shared as `&T` with readers
+// - A retired value is only freed once no record announces it, see `scan`
+unsafe impl<T: Send + Sync> Send for HpCell<T> {}
+unsafe impl<T: Send + Sync> Sync for HpCell<T> {}
+
+impl<T: Send + Sync> HpCell<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            data: AtomicPtr::new(Box::into_raw(Box::new(data))),
+            records: Records::new(),
+            retired: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn read(&self) -> HpGuard<'_, T> {
+        let record = self.records.acquire(|| AtomicPtr::new(ptr::null_mut()));
+        let mut data = self.data.load(Ordering::Acquire);
+
+        // Announce the value, then check it is still current. Both SeqCst,
+        // paired with the swap and the hazard loads in `scan`: either the
+        // writer sees the announcement, or we see its new value and retry.
+        loop {
+            record.hazard.store(data, Ordering::SeqCst);
+
+            let current = self.data.load(Ordering::SeqCst);
+
+            if current == data {
+                break;
+            }
+
+            data = current;
+        }
+
+        HpGuard {
+            record,
+            data,
+            cell: PhantomData,
+        }
+    }
+
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        T::clone(&self.read())
+    }
+
+    pub fn update(&self, data: T) {
+        let old = self
+            .data
+            .swap(Box::into_raw(Box::new(data)), Ordering::SeqCst);
+
+        self.retire(old);
+    }
+
+    // Publishes `f` applied to the current value, retrying if another
+    // update lands first. `f` may run several times.
+    pub fn update_with(&self, mut f: impl FnMut(&T) -> T) {
+        loop {
+            let current = self.read();
+            let new = Box::into_raw(Box::new(f(&current)));
+
+            let result =
+                self.data
+                    .compare_exchange(current.data, new, Ordering::SeqCst, Ordering::Relaxed);
+
+            // Only unannounced after the exchange: until then the value can't
+            // be freed and its address reused by a newer one, which the
+            // exchange would mistake for the value `f` saw
+            drop(current);
+
+            match result {
+                Ok(old) => return self.retire(old),
+                // Safety: `new` was never shared
+                Err(_) => drop(unsafe { Box::from_raw(new) }),
+            }
+        }
+    }
+
+    fn retire(&self, old: *mut T) {
+        let mut retired = self.retired.lock().unwrap_or_else(|err| err.into_inner());
+        retired.push(old);
+
+        if retired.len() < SCAN_THRESHOLD {
+            return;
+        }
+
+        let reclaimed = self.scan(&mut retired);
+        drop(retired);
+
+        // Dropped outside the lock in case a destructor panics
+        for data in reclaimed {
+            drop(unsafe { Box::from_raw(data) });
+        }
+    }
+
+    // Removes and returns the retired values no reader announced
+    fn scan(&self, retired: &mut Vec<*mut T>) -> Vec<*mut T> {
+        let hazards: Vec<_> = self
+            .records
+            .iter()
+            .map(|record| record.hazard.load(Ordering::SeqCst))
+            .collect();
+
+        let (protected, reclaimed) = retired.drain(..).partition(|data| hazards.contains(data));
+
+        *retired = protected;
+
+        reclaimed
+    }
+}
+
+impl<T> Drop for HpCell<T> {
+    fn drop(&mut self) {
+        // Safety: `&mut self` means there are no guards left
+        unsafe {
+            drop(Box::from_raw(*self.data.get_mut()));
+
+            let retired = self
+                .retired
+                .get_mut()
+                .unwrap_or_else(|err| err.into_inner());
+
+            for data in retired.drain(..) {
+                drop(Box::from_raw(data));
+            }
+        }
+    }
+}
+
+pub struct HpGuard<'a, T> {
+    record: &'a Record<AtomicPtr<T>>,
+    data: *mut T,
+    cell: PhantomData<&'a HpCell<T>>,
+}
+
+impl<T> Deref for HpGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Safety: announced in `record`, so no writer frees it
+        unsafe { &*self.data }
+    }
+}
+
+impl<T> Drop for HpGuard<'_, T> {
+    fn drop(&mut self) {
+        self.record.hazard.store(ptr::null_mut(), Ordering::Release);
+        self.record.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, atomic::Ordering},
+        thread,
+    };
+
+    use super::*;
+    use crate::testing::{Counted, Counts};
+
+    #[test]
+    fn test_hp_cell() {
+        let first_drops = Arc::new(Counts::default());
+        let drops = Arc::new(Counts::default());
+        let cell = HpCell::new(Counted(0, first_drops.clone()));
+
+        let first = cell.read();
+
+        for i in 1..=SCAN_THRESHOLD as u64 * 2 {
+            cell.update(Counted(i, drops.clone()));
+        }
+
+        // Scans reclaimed retired values but the borrowed one
+        assert_eq!(first.0, 0);
+        assert_eq!(first_drops.drops.load(Ordering::Acquire), 0);
+        assert!(drops.drops.load(Ordering::Acquire) >= SCAN_THRESHOLD);
+        drop(first);
+
+        for i in 0..SCAN_THRESHOLD as u64 {
+            cell.update_with(|current| Counted(current.0 + i, drops.clone()));
+        }
+        assert_eq!(first_drops.drops.load(Ordering::Acquire), 1);
+
+        drop(cell);
+        assert_eq!(drops.drops.load(Ordering::Acquire), SCAN_THRESHOLD * 3);
+    }
+
+    #[test]
+    fn test_concurrent_updates() {
+        let cell = HpCell::new(0_u64);
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..500 {
+                        cell.update_with(|current| current + 1);
+                        assert!(*cell.read() <= 2_000);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(cell.get(), 2_000);
+    }
+}
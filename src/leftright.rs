@@ -0,0 +1,219 @@
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::ops::Deref;
+
+use crate::{
+    sync::{
+        self, UnsafeCell,
+        atomic::{AtomicUsize, Ordering},
+    },
+    utils::{Backoff, Padded},
+};
+
+// Left-right pair: readers see one copy of the value while the single writer
+// mutates the other in place. `publish` flips readers over to the mutated
+// copy, waits for the ones still on the old copy to leave and replays the
+// same mutations on it, so the value is only ever cloned once, in `new`.
+pub fn new<T: Clone>(data: T) -> (WriteHandle<T>, ReadHandle<T>) {
+    let shared = Arc::new(Shared {
+        live: Padded::new(AtomicUsize::new(0)),
+        readers: [
+            Padded::new(AtomicUsize::new(0)),
+            Padded::new(AtomicUsize::new(0)),
+        ],
+        data: [UnsafeCell::new(data.clone()), UnsafeCell::new(data)],
+    });
+
+    let reader = ReadHandle {
+        shared: shared.clone(),
+    };
+
+    (
+        WriteHandle {
+            shared,
+            ops: Vec::new(),
+        },
+        reader,
+    )
+}
+
+struct Shared<T> {
+    // Copy readers are sent to
+    live: Padded<AtomicUsize>,
+    // Readers currently inside each copy
+    readers: [Padded<AtomicUsize>; 2],
+    data: [UnsafeCell<T>; 2],
+}
+
+// Safety:
+// - Readers only hold `&T` into the live copy and are counted in `readers`
+// - The writer only mutates the other copy, and only once every reader
+//   counted on it left after the flip, see `publish`
+unsafe impl<T: Send + Sync> Sync for Shared<T> {}
+unsafe impl<T: Send + Sync> Send for Shared<T> {}
+
+type Op<T> = Box<dyn FnMut(&mut T) + Send>;
+
+pub struct WriteHandle<T> {
+    shared: Arc<Shared<T>>,
+    // Mutations applied to the shadow copy since the last publish, to be
+    // replayed on the other copy once it becomes the shadow
+    ops: Vec<Op<T>>,
+}
+
+impl<T> WriteHandle<T> {
+    // Mutates the copy readers don't see. `op` runs a second time on the
+    // other copy after the next `publish`, so it has to be deterministic.
+    pub fn apply(&mut self, mut op: impl FnMut(&mut T) + Send + 'static) {
+        let shadow = 1 - self.shared.live.load(Ordering::Relaxed);
+
+        // Safety: readers are never sent to the shadow copy
+        self.shared.data[shadow].with_mut(|data| op(unsafe { &mut *data }));
+
+        self.ops.push(Box::new(op));
+    }
+
+    // Makes the mutations applied so far visible to readers. Blocks until
+    // readers still holding guards into the old copy drop them.
+    pub fn publish(&mut self) {
+        let old = self.shared.live.load(Ordering::Relaxed);
+
+        // Pairs with the SeqCst accesses in `ReadHandle::read`
+        self.shared.live.store(1 - old, Ordering::SeqCst);
+
+        let backoff = Backoff::new();
+
+        while self.shared.readers[old].load(Ordering::SeqCst) != 0 {
+            if cfg!(any(loom, shuttle)) {
+                sync::spin_loop();
+            } else {
+                backoff.snooze();
+            }
+        }
+
+        // Safety: the old copy is the shadow now and every reader left it
+        self.shared.data[old].with_mut(|data| {
+            let data = unsafe { &mut *data };
+
+            for mut op in self.ops.drain(..) {
+                op(data);
+            }
+        });
+    }
+
+    pub fn reader(&self) -> ReadHandle<T> {
+        ReadHandle {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+pub struct ReadHandle<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> ReadHandle<T> {
+    // Borrows the live copy. Hold the guard briefly: `publish` waits for it.
+    pub fn read(&self) -> ReadGuard<'_, T> {
+        loop {
+            let side = self.shared.live.load(Ordering::SeqCst);
+
+            self.shared.readers[side].fetch_add(1, Ordering::SeqCst);
+
+            let guard = ReadGuard {
+                shared: &self.shared,
+                side,
+            };
+
+            // The writer may have flipped and started waiting before we
+            // were counted, in which case it could mutate this copy
+            if self.shared.live.load(Ordering::SeqCst) == side {
+                return guard;
+            }
+
+            drop(guard);
+        }
+    }
+}
+
+impl<T> Clone for ReadHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+pub struct ReadGuard<'a, T> {
+    shared: &'a Shared<T>,
+    side: usize,
+}
+
+impl<T> Deref for ReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Safety: counted in `readers`, so the writer leaves this copy alone
+        self.shared.data[self.side].with(|data| unsafe { &*data })
+    }
+}
+
+impl<T> Drop for ReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.shared.readers[self.side].fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_left_right() {
+        let (mut writer, reader) = new(vec![1, 2]);
+
+        writer.apply(|data| data.push(3));
+        assert_eq!(*reader.read(), [1, 2]);
+
+        writer.publish();
+        assert_eq!(*reader.read(), [1, 2, 3]);
+
+        // Both copies got the mutation
+        writer.apply(|data| data.retain(|i| *i != 1));
+        writer.publish();
+        assert_eq!(*reader.read(), [2, 3]);
+        writer.publish();
+        assert_eq!(*reader.read(), [2, 3]);
+    }
+
+    #[test]
+    fn test_concurrent_readers() {
+        let (mut writer, reader) = new((0_u64, 0_u64));
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                let reader = reader.clone();
+
+                s.spawn(move || {
+                    let mut last = 0;
+
+                    for _ in 0..10_000 {
+                        let data = reader.read();
+                        assert_eq!(data.0, data.1);
+                        assert!(data.0 >= last);
+                        last = data.0;
+                    }
+                });
+            }
+
+            for _ in 0..500 {
+                writer.apply(|data| data.0 += 1);
+                writer.apply(|data| data.1 += 1);
+                writer.publish();
+            }
+        });
+
+        assert_eq!(*reader.read(), (500, 500));
+    }
+}
@@ -1 +1,78 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+// Lets code generated by `sloth-derive` name the crate as `::sloth` here too
+#[cfg(feature = "derive")]
+extern crate self as sloth;
+
+#[cfg(feature = "derive")]
+pub use sloth_derive::SlothSnapshot;
+
+#[cfg(feature = "std")]
+pub mod broadcast;
+#[cfg(target_has_atomic = "ptr")]
+pub mod bytes;
 pub mod cache;
+pub mod cell;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod counters;
+pub mod derive;
+#[cfg(feature = "std")]
+pub mod expiring;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "flags")]
+pub mod flags;
+#[cfg(feature = "std")]
+pub mod group;
+#[cfg(feature = "std")]
+pub mod hazard;
+#[cfg(target_has_atomic = "ptr")]
+pub mod leftright;
+#[cfg(feature = "std")]
+pub mod lock;
+pub mod mailbox;
+#[cfg(feature = "std")]
+pub mod map;
+#[cfg(feature = "std")]
+pub mod memo;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "numa")]
+pub mod numa;
+#[cfg(feature = "std")]
+pub mod once;
+#[cfg(feature = "persist")]
+pub mod persist;
+#[cfg(feature = "std")]
+pub mod pool;
+#[cfg(feature = "rcu")]
+pub mod rcu;
+#[cfg(feature = "std")]
+pub mod registry;
+pub mod replicated;
+pub mod secret;
+pub mod seqlock;
+#[cfg(all(feature = "shm", unix))]
+pub mod shm;
+#[cfg(feature = "std")]
+pub mod singleflight;
+#[cfg(target_has_atomic = "ptr")]
+pub mod snapvec;
+#[cfg(feature = "std")]
+pub mod source;
+pub mod traits;
+#[cfg(target_has_atomic = "ptr")]
+pub mod trie;
+#[cfg(target_has_atomic = "ptr")]
+pub mod triple;
+#[cfg(feature = "std")]
+pub mod watch;
+
+mod records;
+mod sync;
+#[cfg(test)]
+mod testing;
+mod utils;
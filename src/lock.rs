@@ -0,0 +1,76 @@
+use std::sync::RwLock;
+
+use crate::traits::{SnapshotRead, SnapshotWrite};
+
+// `RwLock` behind the same API as `Cache`, for platforms where spinning on
+// atomics is a bad fit or when plain blocking semantics are wanted. Readers
+// block while an update is in progress, and there is no slot count to tune.
+#[derive(Debug, Default)]
+pub struct LockCache<T> {
+    data: RwLock<T>,
+}
+
+impl<T: Clone> LockCache<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            data: RwLock::new(data),
+        }
+    }
+
+    pub fn get_data(&self) -> T {
+        self.data.load()
+    }
+
+    pub fn update(&self, data: T) {
+        self.data.store(data);
+    }
+}
+
+impl<T: Clone> SnapshotRead<T> for LockCache<T> {
+    fn load(&self) -> T {
+        self.get_data()
+    }
+}
+
+impl<T: Clone> SnapshotWrite<T> for LockCache<T> {
+    fn store(&self, data: T) {
+        self.update(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        thread,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_lock_cache() {
+        let cache = LockCache::new(1);
+        assert_eq!(cache.get_data(), 1);
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for i in 0..100 {
+                        cache.update(i);
+                        assert!(cache.get_data() < 100);
+                    }
+                });
+            }
+        });
+
+        // A panic while holding the lock doesn't make the cache unusable
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = cache.data.write().unwrap();
+            panic!("writer panicked");
+        }));
+        assert!(result.is_err());
+
+        cache.update(7);
+        assert_eq!(cache.get_data(), 7);
+    }
+}
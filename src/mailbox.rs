@@ -0,0 +1,128 @@
+use crate::cache::{Cache, Policy};
+
+// Latest-value mailbox for pushing state to many consumers. Producers
+// overwrite the value, and each `Consumer` keeps the generation it last
+// received, so it gets every value at most once and skips the ones
+// overwritten before it looked.
+pub struct Mailbox<T>
+where
+    T: Clone,
+{
+    cache: Cache<T>,
+}
+
+impl<T: Clone> Mailbox<T> {
+    pub fn new() -> Self {
+        Self::with_policy(Policy::default())
+    }
+
+    pub fn with_policy(policy: Policy) -> Self {
+        Self {
+            cache: Cache::empty(policy),
+        }
+    }
+
+    pub fn publish(&self, data: T) {
+        self.cache.update(data);
+    }
+
+    // New consumer. Its first `try_recv` returns the current value, if any.
+    pub fn consumer(&self) -> Consumer<'_, T> {
+        Consumer {
+            mailbox: self,
+            seen: None,
+        }
+    }
+}
+
+impl<T: Clone> Default for Mailbox<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Consumer<'a, T>
+where
+    T: Clone,
+{
+    mailbox: &'a Mailbox<T>,
+    // Generation of the last value received
+    seen: Option<u64>,
+}
+
+impl<T: Clone> Consumer<'_, T> {
+    // The latest value if this consumer hasn't received it yet
+    pub fn try_recv(&mut self) -> Option<T> {
+        let (data, generation) = self.mailbox.cache.try_get_with_generation()?;
+
+        if self.seen == Some(generation) {
+            return None;
+        }
+
+        self.seen = Some(generation);
+
+        Some(data)
+    }
+
+    pub fn has_new(&self) -> bool {
+        let generation = self.mailbox.cache.generation();
+
+        // Generation 0 is the empty mailbox
+        generation != 0 && self.seen != Some(generation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_mailbox() {
+        let mailbox = Mailbox::new();
+        let mut early = mailbox.consumer();
+        assert!(!early.has_new());
+        assert_eq!(early.try_recv(), None);
+
+        mailbox.publish("v1");
+        let mut late = mailbox.consumer();
+
+        assert!(early.has_new());
+        assert_eq!(early.try_recv(), Some("v1"));
+        assert_eq!(early.try_recv(), None);
+        assert_eq!(late.try_recv(), Some("v1"));
+
+        // Overwritten values are skipped
+        mailbox.publish("v2");
+        mailbox.publish("v3");
+        assert_eq!(early.try_recv(), Some("v3"));
+        assert!(!early.has_new());
+        assert_eq!(late.try_recv(), Some("v3"));
+    }
+
+    #[test]
+    fn test_values_are_received_in_order_at_most_once() {
+        let mailbox = Mailbox::new();
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let mut consumer = mailbox.consumer();
+                    let mut last = 0;
+
+                    while last != 1_000 {
+                        if let Some(value) = consumer.try_recv() {
+                            assert!(value > last);
+                            last = value;
+                        }
+                    }
+                });
+            }
+
+            for i in 1..=1_000 {
+                mailbox.publish(i);
+            }
+        });
+    }
+}
@@ -0,0 +1,124 @@
+use std::{borrow::Borrow, collections::BTreeMap, ops::RangeBounds, sync::Arc};
+
+use crate::cache::Cache;
+
+// Ordered `SnapshotMap`, for readers that scan key ranges of one consistent
+// version, such as longest-prefix matches over routing tables. Changes are
+// published copy-on-write like in `SnapshotMap`.
+pub struct SnapshotBTreeMap<K, V> {
+    snapshot: Cache<Arc<BTreeMap<K, V>>>,
+}
+
+impl<K, V> SnapshotBTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self::from_map(BTreeMap::new())
+    }
+
+    pub fn from_map(map: BTreeMap<K, V>) -> Self {
+        Self {
+            snapshot: Cache::new(Arc::new(map)),
+        }
+    }
+
+    // The map as of now, to scan with `BTreeMap::range` without copying
+    // entries out. Later changes publish a new version and leave it
+    // untouched.
+    pub fn snapshot(&self) -> Arc<BTreeMap<K, V>> {
+        self.snapshot.get_data()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.snapshot.with_pinned(|map| map.get(key).cloned())
+    }
+
+    // Entries within `range` of one version, in key order
+    pub fn range<Q, R>(&self, range: R) -> Vec<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        self.snapshot.with_pinned(|map| {
+            map.range(range)
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        })
+    }
+
+    // Applies `f` to a copy of the current map and publishes the result as
+    // one version, like `SnapshotMap::modify`
+    pub fn modify<R>(&self, f: impl FnOnce(&mut BTreeMap<K, V>) -> R) -> R {
+        let mut result = None;
+
+        self.snapshot.update_with(|current| {
+            let mut map = BTreeMap::clone(current.expect("snapshot maps are never cleared"));
+            result = Some(f(&mut map));
+            Arc::new(map)
+        });
+
+        result.expect("`update_with` always runs its closure")
+    }
+
+    pub fn replace(&self, map: BTreeMap<K, V>) {
+        self.snapshot.update(Arc::new(map));
+    }
+}
+
+impl<K, V> Default for SnapshotBTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_btree_map() {
+        let routes: SnapshotBTreeMap<&str, &str> = SnapshotBTreeMap::new();
+
+        routes.modify(|routes| {
+            routes.insert("/api", "backend-a");
+            routes.insert("/api/v2", "backend-b");
+            routes.insert("/static", "cdn");
+        });
+
+        assert_eq!(
+            routes.range("/api".."/b"),
+            [("/api", "backend-a"), ("/api/v2", "backend-b")]
+        );
+        assert_eq!(routes.get("/static"), Some("cdn"));
+
+        // Longest prefix: the last key at or before the path that is a
+        // prefix of it
+        let longest_prefix = |path: &str| {
+            routes
+                .snapshot()
+                .range(..=path)
+                .rev()
+                .find(|(prefix, _)| path.starts_with(**prefix))
+                .map(|(_, backend)| *backend)
+        };
+        assert_eq!(longest_prefix("/api/v2/users"), Some("backend-b"));
+        assert_eq!(longest_prefix("/api/v1/users"), Some("backend-a"));
+        assert_eq!(longest_prefix("/admin"), None);
+
+        let before = routes.snapshot();
+        routes.replace(BTreeMap::new());
+        assert_eq!(before.len(), 3);
+        assert!(routes.range::<&str, _>(..).is_empty());
+    }
+}
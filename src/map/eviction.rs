@@ -0,0 +1,308 @@
+use std::{
+    cmp,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    hash::{BuildHasher, Hash, RandomState},
+    sync::{
+        Arc,
+        atomic::{AtomicU8, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    },
+};
+
+use super::expiry::Timer;
+
+// How a bounded `CacheMap` picks the entries to drop once it holds more
+// than its capacity. Recency and frequency are tracked by readers with
+// plain relaxed loads and stores, so they are approximate: recency only has
+// the resolution of one write, and concurrent hits on the same key may be
+// counted once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eviction {
+    // Least recently read first
+    Lru,
+    // Least often read first, least recently read among equals
+    Lfu,
+    // Like `Lru`, but a new key only gets in if it was asked for more often
+    // than the entry it would replace, according to a sketch of the recent
+    // reads of every key, hits and misses alike. One-off scans then can't
+    // flush the keys that are read all the time.
+    TinyLfu,
+}
+
+// Why a `CacheMap` let go of an entry, for `CacheMap::on_evict`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovalCause {
+    // Evicted or not admitted by a bounded map
+    Capacity,
+    // Dropped after its `Expiry`
+    Expired,
+    // Taken out by `CacheMap::remove`
+    Removed,
+}
+
+// Read statistics and expiry of an entry, shared by every snapshot holding
+// it
+#[derive(Debug)]
+pub(crate) struct Meta {
+    used: AtomicU64,
+    hits: AtomicU32,
+    pub(crate) timer: Timer,
+}
+
+impl Meta {
+    pub(crate) fn new(used: u64, timer: Timer) -> Self {
+        Self {
+            used: AtomicU64::new(used),
+            hits: AtomicU32::new(0),
+            timer,
+        }
+    }
+
+    // For a write replacing the value of an entry, keeping its hits
+    pub(crate) fn renew(&self, used: u64, timer: Timer) -> Self {
+        Self {
+            used: AtomicU64::new(used),
+            hits: AtomicU32::new(self.hits.load(Ordering::Relaxed)),
+            timer,
+        }
+    }
+}
+
+pub(crate) struct Bound {
+    // In entries, or in weight for `CacheMap::bounded_by_weight`
+    capacity: usize,
+    eviction: Eviction,
+    // Bumped by every write, read into `Meta::used` by readers
+    clock: AtomicU64,
+    sketch: Option<Sketch>,
+}
+
+impl Bound {
+    pub(crate) fn new(capacity: usize, eviction: Eviction) -> Self {
+        assert!(capacity > 0, "capacity must not be zero");
+
+        Self {
+            capacity,
+            eviction,
+            clock: AtomicU64::new(0),
+            sketch: (eviction == Eviction::TinyLfu).then(|| Sketch::new(capacity)),
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // Records a read of `key`, `meta` being its entry if it hit
+    pub(crate) fn touch<Q: Hash + ?Sized>(&self, key: &Q, meta: Option<&Meta>) {
+        if let Some(sketch) = &self.sketch {
+            sketch.record(key);
+        }
+
+        let Some(meta) = meta else {
+            return;
+        };
+
+        // Skip the store when it would change nothing, so hot keys don't
+        // bounce their cache line between readers
+        let now = self.clock.load(Ordering::Relaxed);
+
+        if meta.used.load(Ordering::Relaxed) != now {
+            meta.used.store(now, Ordering::Relaxed);
+        }
+
+        if self.eviction == Eviction::Lfu {
+            let hits = meta.hits.load(Ordering::Relaxed);
+            meta.hits.store(hits.saturating_add(1), Ordering::Relaxed);
+        }
+    }
+
+    // Called by every write, returns the recency entries it writes start
+    // with
+    pub(crate) fn tick(&self) -> u64 {
+        if let Some(sketch) = &self.sketch {
+            sketch.tick();
+        }
+
+        self.clock.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    // Evicts entries into `evicted` until their total weight fits. `meta`
+    // must hold every key of `map`, `candidates` the keys the write added.
+    // Returns the weight left.
+    pub(crate) fn enforce<K, V>(
+        &self,
+        map: &mut HashMap<K, V>,
+        meta: &mut HashMap<K, Arc<Meta>>,
+        mut candidates: Vec<K>,
+        weigh: impl Fn(&K, &V) -> usize,
+        evicted: &mut Vec<(K, V)>,
+    ) -> usize
+    where
+        K: Hash + Eq + Clone,
+    {
+        // A new entry heavier than the whole budget would only flush the
+        // others before being evicted itself
+        candidates.retain(|key| {
+            let fits = weigh(key, &map[key]) <= self.capacity;
+
+            if !fits {
+                evicted.extend(map.remove_entry(key));
+                meta.remove(key);
+            }
+
+            fits
+        });
+
+        let mut weight: usize = map.iter().map(|(key, value)| weigh(key, value)).sum();
+
+        if weight <= self.capacity {
+            return weight;
+        }
+
+        // Entries that were already in the map are ranked once, so a write
+        // evicting many of them doesn't scan `meta` for each
+        let added: HashSet<&K> = candidates.iter().collect();
+        let mut victims: BinaryHeap<Victim<K>> = meta
+            .iter()
+            .filter(|(key, _)| !added.contains(key))
+            .map(|(key, meta)| Victim {
+                score: self.score(meta),
+                key: key.clone(),
+            })
+            .collect();
+        let mut candidates = VecDeque::from(candidates);
+
+        while weight > self.capacity {
+            let victim = match (victims.pop(), &self.sketch) {
+                // Admission: the newest candidate has to beat the victim
+                (Some(victim), Some(sketch)) => match candidates.pop_back() {
+                    Some(candidate)
+                        if sketch.estimate(&candidate) <= sketch.estimate(&victim.key) =>
+                    {
+                        victims.push(victim);
+                        candidate
+                    }
+                    // Admitted, and from now on as evictable as the rest
+                    Some(candidate) => {
+                        victims.push(Victim {
+                            score: self.score(&meta[&candidate]),
+                            key: candidate,
+                        });
+                        victim.key
+                    }
+                    None => victim.key,
+                },
+                (Some(victim), None) => victim.key,
+                // Only new keys left, drop the oldest of them
+                (None, _) => candidates
+                    .pop_front()
+                    .expect("an overweight map has entries left"),
+            };
+
+            if let Some((key, value)) = map.remove_entry(&victim) {
+                weight -= weigh(&key, &value);
+                evicted.push((key, value));
+            }
+            meta.remove(&victim);
+        }
+
+        weight
+    }
+
+    fn score(&self, meta: &Meta) -> (u64, u64) {
+        let used = meta.used.load(Ordering::Relaxed);
+
+        match self.eviction {
+            Eviction::Lfu => (u64::from(meta.hits.load(Ordering::Relaxed)), used),
+            Eviction::Lru | Eviction::TinyLfu => (used, 0),
+        }
+    }
+}
+
+// Entry that can be evicted, ordered so the lowest score comes out of a
+// `BinaryHeap` first
+struct Victim<K> {
+    score: (u64, u64),
+    key: K,
+}
+
+impl<K> PartialEq for Victim<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score
+    }
+}
+
+impl<K> Eq for Victim<K> {}
+
+impl<K> PartialOrd for Victim<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K> Ord for Victim<K> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        other.score.cmp(&self.score)
+    }
+}
+
+// Count-min sketch of how often keys were read, with four 4-bit counters
+// per key kept in bytes. Counters are halved every `capacity` writes, so
+// the estimates follow what is popular now rather than since startup.
+struct Sketch {
+    hasher: RandomState,
+    counters: Box<[AtomicU8]>,
+    writes: AtomicUsize,
+    period: usize,
+}
+
+const SKETCH_MAX: u8 = 15;
+
+impl Sketch {
+    fn new(capacity: usize) -> Self {
+        let len = (capacity * 4).next_power_of_two().max(64);
+
+        Self {
+            hasher: RandomState::new(),
+            counters: (0..len).map(|_| AtomicU8::new(0)).collect(),
+            writes: AtomicUsize::new(0),
+            period: capacity,
+        }
+    }
+
+    fn indices<Q: Hash + ?Sized>(&self, key: &Q) -> impl Iterator<Item = usize> {
+        let hash = self.hasher.hash_one(key);
+        let mask = self.counters.len() - 1;
+
+        (0..4).map(move |row| (hash.rotate_left(row * 16) as usize) & mask)
+    }
+
+    fn record<Q: Hash + ?Sized>(&self, key: &Q) {
+        for index in self.indices(key) {
+            let counter = &self.counters[index];
+            let count = counter.load(Ordering::Relaxed);
+
+            if count < SKETCH_MAX {
+                counter.store(count + 1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn estimate<Q: Hash + ?Sized>(&self, key: &Q) -> u8 {
+        self.indices(key)
+            .map(|index| self.counters[index].load(Ordering::Relaxed))
+            .min()
+            .unwrap_or(0)
+    }
+
+    // Called by every write
+    fn tick(&self) {
+        if !(self.writes.fetch_add(1, Ordering::Relaxed) + 1).is_multiple_of(self.period) {
+            return;
+        }
+
+        for counter in &self.counters {
+            counter.store(counter.load(Ordering::Relaxed) / 2, Ordering::Relaxed);
+        }
+    }
+}
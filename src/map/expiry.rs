@@ -0,0 +1,93 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+// When entries of a `CacheMap` expire, set for the whole map with
+// `CacheMap::with_expiry` or per entry with `CacheMap::insert_with_expiry`.
+// The default never expires. Expired entries read as absent right away and
+// are dropped by the next write or sweep.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Expiry {
+    // Time to live: expires this long after it was inserted
+    pub ttl: Option<Duration>,
+    // Time to idle: expires this long after it was last read or inserted
+    pub tti: Option<Duration>,
+}
+
+impl Expiry {
+    pub fn ttl(ttl: Duration) -> Self {
+        Self {
+            ttl: Some(ttl),
+            tti: None,
+        }
+    }
+
+    pub fn tti(tti: Duration) -> Self {
+        Self {
+            ttl: None,
+            tti: Some(tti),
+        }
+    }
+
+    pub fn is_never(&self) -> bool {
+        self.ttl.is_none() && self.tti.is_none()
+    }
+}
+
+const NEVER: u64 = u64::MAX;
+
+// Nanoseconds since `epoch`, the time base of every `Timer` of a map
+pub(crate) fn since(epoch: Instant) -> u64 {
+    u64::try_from(epoch.elapsed().as_nanos()).unwrap_or(NEVER - 1)
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(NEVER)
+}
+
+// Expiry of one entry, in nanoseconds since the map's epoch
+#[derive(Debug)]
+pub(crate) struct Timer {
+    deadline: u64,
+    idle: u64,
+    // Last read, stored by readers with relaxed ordering
+    accessed: AtomicU64,
+}
+
+impl Timer {
+    pub(crate) fn new(expiry: Expiry, now: u64) -> Self {
+        Self {
+            deadline: expiry
+                .ttl
+                .map_or(NEVER, |ttl| now.saturating_add(nanos(ttl))),
+            idle: expiry.tti.map_or(NEVER, nanos),
+            accessed: AtomicU64::new(now),
+        }
+    }
+
+    pub(crate) fn is_set(&self) -> bool {
+        self.deadline != NEVER || self.idle != NEVER
+    }
+
+    pub(crate) fn is_expired(&self, now: u64) -> bool {
+        now >= self.deadline
+            || (self.idle != NEVER
+                && now.saturating_sub(self.accessed.load(Ordering::Relaxed)) >= self.idle)
+    }
+
+    // Records a read at `now`. Only stores once the last read is a fair
+    // share of the idle time back, so hot keys don't bounce their cache
+    // line between readers.
+    pub(crate) fn touch(&self, now: u64) {
+        if self.idle == NEVER {
+            return;
+        }
+
+        let accessed = self.accessed.load(Ordering::Relaxed);
+
+        if now.saturating_sub(accessed) > self.idle / 64 {
+            self.accessed.store(now, Ordering::Relaxed);
+        }
+    }
+}
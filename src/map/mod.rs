@@ -0,0 +1,784 @@
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    cache::{Cache, Policy, Refresher},
+    singleflight::Group,
+};
+
+mod btree;
+mod eviction;
+mod expiry;
+mod sharded;
+mod snapshot;
+
+pub use btree::SnapshotBTreeMap;
+pub use eviction::{Eviction, RemovalCause};
+pub use expiry::Expiry;
+pub use sharded::ShardedCacheMap;
+pub use snapshot::SnapshotMap;
+
+use eviction::{Bound, Meta};
+use expiry::Timer;
+
+// Read-mostly map for many keys. Readers share an immutable snapshot of the
+// whole map, writers copy it, apply their change and publish the copy
+// through a `Cache`, so a write costs O(len) and reads never block.
+//
+// A map made `bounded` evicts entries by the chosen `Eviction` whenever a
+// write takes it over capacity, and entries given an `Expiry` are dropped
+// once they expire.
+pub struct CacheMap<K, V, const LEN: usize = 4> {
+    snapshot: Cache<Arc<Entries<K, V>>, LEN>,
+    loads: Group<K, V>,
+    on_loading: OnLoading,
+    bound: Option<Bound>,
+    weigher: Option<fn(&K, &V) -> usize>,
+    expiry: Expiry,
+    epoch: Instant,
+    on_evict: Option<Listener<K, V>>,
+}
+
+type Listener<K, V> = Box<dyn Fn(&K, &V, RemovalCause) + Send + Sync>;
+
+// What readers share: the map, and the metadata of the entries that need
+// any, which is all of them if the map is bounded and otherwise only those
+// that expire
+#[derive(Clone)]
+struct Entries<K, V> {
+    map: Arc<HashMap<K, V>>,
+    meta: HashMap<K, Arc<Meta>>,
+    // Total weight as of the last eviction pass, for weighed maps
+    weight: usize,
+}
+
+// Entries a write starts the expiry of afresh, besides those it adds
+#[derive(Clone, Copy)]
+enum Renew<'a, K> {
+    Nothing,
+    Key(&'a K, Expiry),
+    Keys(&'a HashSet<K>),
+    All,
+}
+
+// What `CacheMap::get_or_load` does on a miss for a key another caller is
+// already loading
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnLoading {
+    // Wait for that load and return its value
+    #[default]
+    Wait,
+    // Return `None` right away, for callers that would rather serve a
+    // fallback than queue up behind a slow lookup
+    Skip,
+}
+
+impl<K, V, const LEN: usize> CacheMap<K, V, LEN>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self::from_map(HashMap::new())
+    }
+
+    pub fn from_map(map: HashMap<K, V>) -> Self {
+        Self::with_policy(map, Policy::default())
+    }
+
+    pub fn with_policy(map: HashMap<K, V>, policy: Policy) -> Self {
+        let entries = Entries {
+            map: Arc::new(map),
+            meta: HashMap::new(),
+            weight: 0,
+        };
+
+        Self {
+            snapshot: Cache::with_policy(Arc::new(entries), policy),
+            loads: Group::new(),
+            on_loading: OnLoading::default(),
+            bound: None,
+            weigher: None,
+            expiry: Expiry::default(),
+            epoch: Instant::now(),
+            on_evict: None,
+        }
+    }
+
+    // Calls `listener` with every entry the map evicts, expires or has
+    // removed, for values that hold resources beyond their memory. It runs
+    // on the writer's thread after the new snapshot is published, so it
+    // may use the map. Entries that `modify` or `replace_all` drop and
+    // values that `insert` replaces are not reported.
+    pub fn on_evict(
+        mut self,
+        listener: impl Fn(&K, &V, RemovalCause) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_evict = Some(Box::new(listener));
+        self
+    }
+
+    // Expiry of the entries inserted from now on, unless they come with
+    // their own
+    pub fn with_expiry(mut self, expiry: Expiry) -> Self {
+        self.expiry = expiry;
+        self
+    }
+
+    // Caps the map at `capacity` entries, evicting by `eviction` right away
+    // if it holds more. A bounded map may drop a value right after `insert`
+    // added it, or with `TinyLfu` not admit it at all. Panics if `capacity`
+    // is zero.
+    pub fn bounded(mut self, capacity: usize, eviction: Eviction) -> Self {
+        self.bound = Some(Bound::new(capacity, eviction));
+        self.modify(|_| {});
+        self
+    }
+
+    // Like `bounded`, but caps the total `weigher` gives the entries, such
+    // as their size in bytes, rather than their count. An entry weighing
+    // more than `budget` on its own is never kept.
+    pub fn bounded_by_weight(
+        mut self,
+        budget: usize,
+        eviction: Eviction,
+        weigher: fn(&K, &V) -> usize,
+    ) -> Self {
+        self.weigher = Some(weigher);
+        self.bounded(budget, eviction)
+    }
+
+    // Entry count or weight budget of a bounded map
+    pub fn capacity(&self) -> Option<usize> {
+        self.bound.as_ref().map(Bound::capacity)
+    }
+
+    // Total weight of the entries, which is their count unless the map is
+    // `bounded_by_weight`
+    pub fn weight(&self) -> usize {
+        self.snapshot.with_pinned(|entries| match self.weigher {
+            Some(_) => entries.weight,
+            None => entries.map.len(),
+        })
+    }
+
+    pub fn with_on_loading(mut self, on_loading: OnLoading) -> Self {
+        self.on_loading = on_loading;
+        self
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.snapshot.with_pinned(|entries| {
+            let meta = entries.meta.get(key);
+            let live = self.is_live(meta, true);
+
+            if let Some(bound) = &self.bound {
+                bound.touch(key, meta.filter(|_| live).map(Arc::as_ref));
+            }
+
+            entries.map.get(key).filter(|_| live).cloned()
+        })
+    }
+
+    // Whether the entry with `meta` has not expired, recording a read of it
+    // if `read` is set. Only looks at the clock for entries that expire.
+    fn is_live(&self, meta: Option<&Arc<Meta>>, read: bool) -> bool {
+        let Some(timer) = meta.map(|meta| &meta.timer).filter(|timer| timer.is_set()) else {
+            return true;
+        };
+
+        let now = expiry::since(self.epoch);
+
+        if timer.is_expired(now) {
+            return false;
+        }
+
+        if read {
+            timer.touch(now);
+        }
+
+        true
+    }
+
+    // Read-through lookup: on a miss, runs `loader` and inserts its value.
+    // Concurrent misses for the same key run a single load, the others wait
+    // for it or get `None` depending on `OnLoading`. If `loader` panics, the
+    // panic propagates and waiting callers run their own load.
+    pub fn get_or_load(&self, key: K, loader: impl FnOnce(&K) -> V) -> Option<V> {
+        if let Some(value) = self.get(&key) {
+            return Some(value);
+        }
+
+        let load = || {
+            // A load that just finished may have inserted it
+            if let Some(value) = self.get(&key) {
+                return value;
+            }
+
+            let value = loader(&key);
+            self.insert(key.clone(), value.clone());
+            value
+        };
+
+        match self.on_loading {
+            OnLoading::Wait => Some(self.loads.do_call(key.clone(), load)),
+            OnLoading::Skip => self.loads.try_call(key.clone(), load),
+        }
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.snapshot.with_pinned(|entries| {
+            entries.map.contains_key(key) && self.is_live(entries.meta.get(key), false)
+        })
+    }
+
+    // Counts expired entries until the next write or sweep drops them, like
+    // `snapshot` still holds them
+    pub fn len(&self) -> usize {
+        self.snapshot.with_pinned(|entries| entries.map.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshot.with_pinned(|entries| entries.map.is_empty())
+    }
+
+    // The whole map as of now. Later writes publish a new snapshot and leave
+    // this one untouched.
+    pub fn snapshot(&self) -> Arc<HashMap<K, V>> {
+        self.snapshot.with_pinned(|entries| entries.map.clone())
+    }
+
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.insert_with_expiry(key, value, self.expiry)
+    }
+
+    // Inserts with `expiry` instead of the map's own. Replacing a value
+    // starts its expiry afresh either way.
+    pub fn insert_with_expiry(&self, key: K, value: V, expiry: Expiry) -> Option<V> {
+        let renewed = key.clone();
+        let mut old = None;
+
+        self.publish_with(Renew::Key(&renewed, expiry), |current| {
+            let mut map = HashMap::clone(current);
+            old = map.insert(key, value);
+            map
+        });
+
+        old
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        // Don't publish a copy if there is nothing to remove
+        if !self.contains_key(key) {
+            return None;
+        }
+
+        let mut old = None;
+
+        self.modify(|map| old = map.remove_entry(key));
+
+        let (key, value) = old?;
+        self.notify(&key, &value, RemovalCause::Removed);
+
+        Some(value)
+    }
+
+    // Swaps in a whole new map in one update, without copying the old one
+    pub fn replace_all(&self, entries: impl IntoIterator<Item = (K, V)>) {
+        let map = entries.into_iter().collect();
+
+        self.publish_with(Renew::All, |_| map);
+    }
+
+    // Live entries of one snapshot, for dumping the map to be `import`ed
+    // later
+    pub fn export(&self) -> Vec<(K, V)> {
+        self.snapshot.with_pinned(|entries| {
+            entries
+                .map
+                .iter()
+                .filter(|(key, _)| self.is_live(entries.meta.get(*key), false))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        })
+    }
+
+    // Inserts every entry in a single write, so readers see all of them or
+    // none. Imported entries replace the values of keys already present and
+    // get the map's expiry.
+    pub fn import(&self, entries: impl IntoIterator<Item = (K, V)>) {
+        let entries: Vec<_> = entries.into_iter().collect();
+        let keys: HashSet<_> = entries.iter().map(|(key, _)| key.clone()).collect();
+
+        self.publish_with(Renew::Keys(&keys), |current| {
+            let mut map = HashMap::clone(current);
+            map.extend(entries);
+            map
+        });
+    }
+
+    // Applies `f` to a copy of the current map and publishes it. Writers are
+    // serialized, so concurrent `modify` calls never lose each other's
+    // changes. Entries `f` adds get the map's expiry, those it replaces the
+    // value of keep theirs.
+    pub fn modify(&self, f: impl FnOnce(&mut HashMap<K, V>)) {
+        self.publish_with(Renew::Nothing, |current| {
+            let mut map = HashMap::clone(current);
+            f(&mut map);
+            map
+        });
+    }
+
+    // Drops expired entries now rather than on the next write. Only
+    // publishes a new snapshot if any expired.
+    pub fn sweep(&self) {
+        let now = expiry::since(self.epoch);
+        let expired = self
+            .snapshot
+            .with_pinned(|entries| entries.meta.values().any(|meta| meta.timer.is_expired(now)));
+
+        if expired {
+            self.modify(|_| {});
+        }
+    }
+
+    // Publishes the map `f` builds from the current one, dropping expired
+    // entries from it and evicting more if the map is bounded
+    fn publish_with(&self, renew: Renew<'_, K>, f: impl FnOnce(&HashMap<K, V>) -> HashMap<K, V>) {
+        let mut expired = Vec::new();
+        let mut evicted = Vec::new();
+
+        self.snapshot.update_with(|current| {
+            let current = current.expect("map snapshots are never cleared");
+            let mut map = f(&current.map);
+
+            let now = expiry::since(self.epoch);
+            let used = self.bound.as_ref().map_or(0, Bound::tick);
+            let mut meta = HashMap::new();
+            let mut candidates = Vec::new();
+
+            let removed = map.extract_if(|key, _| {
+                let expiry = match renew {
+                    Renew::Key(renewed, expiry) if renewed == key => Some(expiry),
+                    Renew::Keys(renewed) if renewed.contains(key) => Some(self.expiry),
+                    Renew::All => Some(self.expiry),
+                    Renew::Key(..) | Renew::Keys(_) | Renew::Nothing => None,
+                };
+
+                let entry = match (current.meta.get(key), expiry) {
+                    (Some(previous), None) if previous.timer.is_expired(now) => return true,
+                    (Some(previous), None) => previous.clone(),
+                    (Some(previous), Some(expiry)) => {
+                        Arc::new(previous.renew(used, Timer::new(expiry, now)))
+                    }
+                    (None, expiry) => {
+                        // Entries that had no metadata so far don't expire
+                        let added = !current.map.contains_key(key);
+                        let expiry = match expiry {
+                            Some(expiry) => expiry,
+                            None if added => self.expiry,
+                            None => Expiry::default(),
+                        };
+                        let timer = Timer::new(expiry, now);
+
+                        // Plain entries of an unbounded map need no metadata
+                        if self.bound.is_none() && !timer.is_set() {
+                            return false;
+                        }
+
+                        if self.bound.is_some() && added {
+                            candidates.push(key.clone());
+                        }
+
+                        Arc::new(Meta::new(used, timer))
+                    }
+                };
+
+                meta.insert(key.clone(), entry);
+                false
+            });
+            expired.extend(removed);
+
+            let weight = match (&self.bound, self.weigher) {
+                (Some(bound), Some(weigher)) => {
+                    bound.enforce(&mut map, &mut meta, candidates, weigher, &mut evicted)
+                }
+                (Some(bound), None) => {
+                    bound.enforce(&mut map, &mut meta, candidates, |_, _| 1, &mut evicted)
+                }
+                (None, _) => 0,
+            };
+
+            Arc::new(Entries {
+                map: Arc::new(map),
+                meta,
+                weight,
+            })
+        });
+
+        for (key, value) in &expired {
+            self.notify(key, value, RemovalCause::Expired);
+        }
+
+        for (key, value) in &evicted {
+            self.notify(key, value, RemovalCause::Capacity);
+        }
+    }
+
+    // Hands an entry the map let go of to the listener, if any. Called
+    // outside the writer lock.
+    fn notify(&self, key: &K, value: &V, cause: RemovalCause) {
+        if let Some(listener) = &self.on_evict {
+            listener(key, value, cause);
+        }
+    }
+}
+
+impl<K, V, const LEN: usize> CacheMap<K, V, LEN>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    // Calls `sweep` every `interval` on a background thread, for maps whose
+    // expired entries would otherwise linger between rare writes
+    pub fn spawn_sweeper(self: Arc<Self>, interval: Duration) -> Refresher {
+        Refresher::spawn(interval, move || self.sweep())
+    }
+}
+
+impl<K, V, const LEN: usize> Default for CacheMap<K, V, LEN>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Barrier, Mutex,
+            atomic::{AtomicUsize, Ordering},
+        },
+        thread,
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_cache_map() {
+        let map: CacheMap<String, u64> = CacheMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.get("a"), None);
+
+        assert_eq!(map.insert("a".to_owned(), 1), None);
+        assert_eq!(map.insert("a".to_owned(), 2), Some(1));
+        assert_eq!(map.insert("b".to_owned(), 3), None);
+
+        // Snapshots are immutable
+        let before = map.snapshot();
+
+        assert_eq!(map.remove("a"), Some(2));
+        assert_eq!(map.remove("a"), None);
+        assert_eq!(map.get("b"), Some(3));
+        assert_eq!(map.len(), 1);
+        assert_eq!(before.get("a"), Some(&2));
+
+        map.replace_all([("c".to_owned(), 4), ("d".to_owned(), 5)]);
+        assert!(!map.contains_key("b"));
+        assert_eq!(map.get("c"), Some(4));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_get_or_load() {
+        let loads = AtomicUsize::new(0);
+        let load = |key: &u64| {
+            loads.fetch_add(1, Ordering::AcqRel);
+            thread::sleep(Duration::from_millis(20));
+            key * 10
+        };
+
+        let map: CacheMap<u64, u64> = CacheMap::new();
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| assert_eq!(map.get_or_load(1, load), Some(10)));
+            }
+        });
+
+        assert_eq!(loads.load(Ordering::Acquire), 1);
+        assert_eq!(map.get(&1), Some(10));
+        assert_eq!(map.get_or_load(1, |_| unreachable!()), Some(10));
+
+        // Skipping callers don't wait for the running load
+        let map: CacheMap<u64, u64> = CacheMap::new().with_on_loading(OnLoading::Skip);
+        let (started, finish) = (Barrier::new(2), Barrier::new(2));
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                map.get_or_load(2, |key| {
+                    started.wait();
+                    finish.wait();
+                    key * 10
+                })
+            });
+
+            started.wait();
+            assert_eq!(map.get_or_load(2, |_| unreachable!()), None);
+            finish.wait();
+        });
+
+        assert_eq!(map.get_or_load(2, |_| unreachable!()), Some(20));
+    }
+
+    #[test]
+    fn test_bounded() {
+        let map: CacheMap<u64, u64> =
+            CacheMap::from_map((0..4).map(|key| (key, key)).collect()).bounded(3, Eviction::Lru);
+        assert_eq!((map.len(), map.capacity()), (3, Some(3)));
+
+        // The entry read least recently goes first
+        let map: CacheMap<u64, u64> = CacheMap::new().bounded(3, Eviction::Lru);
+        for key in 1..=3 {
+            map.insert(key, key);
+        }
+        map.get(&1);
+        map.insert(4, 4);
+        assert!(!map.contains_key(&2));
+
+        map.get(&1);
+        map.get(&4);
+        map.insert(5, 5);
+        assert_eq!(map.len(), 3);
+        assert!([1, 4, 5].iter().all(|key| map.contains_key(key)));
+
+        // With LFU the key read most survives a run of new ones
+        let map: CacheMap<u64, u64> = CacheMap::new().bounded(2, Eviction::Lfu);
+        map.insert(1, 1);
+        for _ in 0..10 {
+            map.get(&1);
+        }
+        for key in 2..10 {
+            map.insert(key, key);
+            map.get(&key);
+        }
+        assert!(map.contains_key(&1));
+        assert!(map.contains_key(&9));
+
+        // TinyLFU doesn't even let a key in that was never asked for
+        let map: CacheMap<u64, u64> = CacheMap::new().bounded(2, Eviction::TinyLfu);
+        map.insert(1, 1);
+        map.insert(2, 2);
+        for _ in 0..5 {
+            map.get(&1);
+            map.get(&2);
+        }
+        map.insert(3, 3);
+        assert!(!map.contains_key(&3));
+
+        for _ in 0..10 {
+            map.get(&4);
+        }
+        map.insert(4, 4);
+        assert!(map.contains_key(&4));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_bounded_by_weight() {
+        let map: CacheMap<u32, Vec<u8>> =
+            CacheMap::new().bounded_by_weight(100, Eviction::Lru, |_, value| value.len());
+        assert_eq!(map.capacity(), Some(100));
+
+        map.insert(1, vec![0; 40]);
+        map.insert(2, vec![0; 40]);
+        assert_eq!(map.weight(), 80);
+
+        // Makes room by weight, evicting as many entries as it takes
+        map.get(&2);
+        map.insert(3, vec![0; 30]);
+        assert!(!map.contains_key(&1));
+        assert_eq!(map.weight(), 70);
+
+        map.insert(4, vec![0; 90]);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.weight(), 90);
+
+        // Too heavy to ever fit, so it leaves the rest alone
+        map.insert(5, vec![0; 101]);
+        assert!(!map.contains_key(&5));
+        assert_eq!(map.weight(), 90);
+
+        // Replacing a value reweighs it
+        map.insert(4, vec![0; 10]);
+        map.insert(6, vec![0; 90]);
+        assert_eq!(map.weight(), 100);
+    }
+
+    #[test]
+    fn test_expiry() {
+        let map: CacheMap<u32, u32> =
+            CacheMap::new().with_expiry(Expiry::ttl(Duration::from_millis(100)));
+
+        map.insert(1, 1);
+        map.insert_with_expiry(2, 2, Expiry::default());
+        map.insert_with_expiry(3, 3, Expiry::tti(Duration::from_millis(200)));
+
+        // Reads keep the idle entry alive past the others' time to live
+        for _ in 0..4 {
+            thread::sleep(Duration::from_millis(60));
+            assert_eq!(map.get(&3), Some(3));
+        }
+
+        assert_eq!(map.get(&1), None);
+        assert!(!map.contains_key(&1));
+        assert_eq!(map.len(), 3);
+
+        map.sweep();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&2), Some(2));
+
+        thread::sleep(Duration::from_millis(250));
+        assert!(!map.contains_key(&3));
+
+        // Inserting again starts over
+        map.insert(3, 30);
+        assert_eq!(map.get(&3), Some(30));
+
+        let map = Arc::new(CacheMap::<u32, u32>::new());
+        let sweeper = map.clone().spawn_sweeper(Duration::from_millis(5));
+        map.insert_with_expiry(1, 1, Expiry::ttl(Duration::from_millis(10)));
+
+        while !map.is_empty() {
+            thread::sleep(Duration::from_millis(5));
+        }
+        sweeper.stop();
+    }
+
+    #[test]
+    fn test_on_evict() {
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let listener = removed.clone();
+
+        let map: CacheMap<u32, u32> =
+            CacheMap::new()
+                .bounded(3, Eviction::Lru)
+                .on_evict(move |key, value, cause| {
+                    listener.lock().unwrap().push((*key, *value, cause));
+                });
+
+        for key in 1..=3 {
+            map.insert(key, key * 10);
+        }
+        map.get(&1);
+        map.insert(4, 40);
+        assert_eq!(map.remove(&1), Some(10));
+        assert_eq!(map.remove(&1), None);
+
+        map.insert_with_expiry(5, 50, Expiry::ttl(Duration::ZERO));
+        map.sweep();
+
+        assert_eq!(
+            *removed.lock().unwrap(),
+            [
+                (2, 20, RemovalCause::Capacity),
+                (1, 10, RemovalCause::Removed),
+                (5, 50, RemovalCause::Expired),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_export_import() {
+        let map: CacheMap<u32, String> = CacheMap::new();
+        map.insert(1, String::from("a"));
+        map.insert(2, String::from("b"));
+        map.insert_with_expiry(3, String::from("c"), Expiry::ttl(Duration::ZERO));
+
+        let mut exported = map.export();
+        exported.sort();
+        assert_eq!(exported, [(1, String::from("a")), (2, String::from("b"))]);
+
+        let warmed: CacheMap<u32, String> = CacheMap::new();
+        warmed.insert(2, String::from("old"));
+        warmed.insert(4, String::from("d"));
+
+        let generation = warmed.snapshot.generation();
+        warmed.import(exported);
+        assert_eq!(warmed.snapshot.generation(), generation + 1);
+        assert_eq!(warmed.get(&2).as_deref(), Some("b"));
+        assert_eq!(warmed.len(), 3);
+    }
+
+    #[test]
+    fn test_import_over_capacity() {
+        for eviction in [Eviction::Lru, Eviction::Lfu, Eviction::TinyLfu] {
+            let evicted = Arc::new(AtomicUsize::new(0));
+            let listener = evicted.clone();
+
+            let map: CacheMap<u32, u32> =
+                CacheMap::new()
+                    .bounded(100, eviction)
+                    .on_evict(move |_, _, cause| {
+                        assert_eq!(cause, RemovalCause::Capacity);
+                        listener.fetch_add(1, Ordering::Relaxed);
+                    });
+
+            for key in 0..100 {
+                map.insert(key, key);
+            }
+            for _ in 0..10 {
+                map.get(&7);
+            }
+
+            // One write evicting hundreds of times what the map holds
+            map.import((1_000..51_000).map(|key| (key, key)));
+            assert_eq!(map.len(), 100);
+            assert_eq!(evicted.load(Ordering::Relaxed), 50_000);
+
+            // Only TinyLFU holds on to a key read more than the new ones
+            assert_eq!(map.contains_key(&7), eviction == Eviction::TinyLfu);
+        }
+    }
+
+    #[test]
+    fn test_concurrent_inserts() {
+        let map: CacheMap<u64, u64, 2> = CacheMap::new();
+
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                let map = &map;
+
+                s.spawn(move || {
+                    for i in 0..200 {
+                        map.insert(t * 1_000 + i, i);
+                        assert_eq!(map.get(&(t * 1_000 + i)), Some(i));
+                    }
+                });
+            }
+        });
+
+        assert_eq!(map.len(), 800);
+    }
+}
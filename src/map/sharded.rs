@@ -0,0 +1,155 @@
+use core::array;
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    hash::{BuildHasher, Hash, RandomState},
+};
+
+use super::CacheMap;
+
+// `CacheMap` split into `SHARDS` independent maps by key hash. A write only
+// copies and serializes on its own shard, at the price of having no
+// snapshot of the whole map.
+pub struct ShardedCacheMap<K, V, const SHARDS: usize = 16, S = RandomState> {
+    hasher: S,
+    shards: [CacheMap<K, V>; SHARDS],
+}
+
+impl<K, V, const SHARDS: usize> ShardedCacheMap<K, V, SHARDS>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K, V, const SHARDS: usize, S> ShardedCacheMap<K, V, SHARDS, S>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher,
+{
+    const CHECK_SHARDS_IS_NOT_ZERO: () = assert!(SHARDS > 0);
+
+    pub fn with_hasher(hasher: S) -> Self {
+        let () = Self::CHECK_SHARDS_IS_NOT_ZERO;
+
+        Self {
+            hasher,
+            shards: array::from_fn(|_| CacheMap::new()),
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shard(key).get(key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shard(key).contains_key(key)
+    }
+
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.shard(&key).insert(key, value)
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shard(key).remove(key)
+    }
+
+    // Not atomic: readers may see some shards replaced and others not yet
+    pub fn replace_all(&self, entries: impl IntoIterator<Item = (K, V)>) {
+        let mut maps: [HashMap<K, V>; SHARDS] = array::from_fn(|_| HashMap::new());
+
+        for (key, value) in entries {
+            maps[self.shard_index(&key)].insert(key, value);
+        }
+
+        for (shard, map) in self.shards.iter().zip(maps) {
+            shard.replace_all(map);
+        }
+    }
+
+    // Sums the shards one by one, so concurrent writes may or may not be
+    // counted
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(CacheMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(CacheMap::is_empty)
+    }
+
+    fn shard<Q>(&self, key: &Q) -> &CacheMap<K, V>
+    where
+        Q: Hash + ?Sized,
+    {
+        &self.shards[self.shard_index(key)]
+    }
+
+    fn shard_index<Q>(&self, key: &Q) -> usize
+    where
+        Q: Hash + ?Sized,
+    {
+        (self.hasher.hash_one(key) % SHARDS as u64) as usize
+    }
+}
+
+impl<K, V, const SHARDS: usize> Default for ShardedCacheMap<K, V, SHARDS>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sharded_cache_map() {
+        let map: ShardedCacheMap<u64, u64, 4> = ShardedCacheMap::new();
+        assert!(map.is_empty());
+
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                let map = &map;
+
+                s.spawn(move || {
+                    for i in 0..200 {
+                        map.insert(t * 1_000 + i, i);
+                    }
+                });
+            }
+        });
+        assert_eq!(map.len(), 800);
+        assert_eq!(map.get(&3_005), Some(5));
+
+        // Keys are spread over every shard
+        assert!(map.shards.iter().all(|shard| !shard.is_empty()));
+
+        assert_eq!(map.remove(&3_005), Some(5));
+        assert!(!map.contains_key(&3_005));
+
+        map.replace_all((0..10).map(|i| (i, i * 2)));
+        assert_eq!(map.len(), 10);
+        assert_eq!(map.get(&9), Some(18));
+        assert_eq!(map.get(&1_000), None);
+    }
+}
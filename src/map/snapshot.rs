@@ -0,0 +1,131 @@
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hash, RandomState},
+    sync::Arc,
+};
+
+use crate::cache::Cache;
+
+// Copy-on-write map for readers that need a consistent view of many keys at
+// once, such as routing tables. Unlike `CacheMap`, which is built around
+// point lookups, everything goes through `snapshot`, and batches of changes
+// made in `modify` are published as one new version.
+pub struct SnapshotMap<K, V, S = RandomState> {
+    snapshot: Cache<Arc<HashMap<K, V, S>>>,
+}
+
+impl<K, V> SnapshotMap<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self::from_map(HashMap::new())
+    }
+}
+
+impl<K, V, S> SnapshotMap<K, V, S>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher + Clone,
+{
+    pub fn from_map(map: HashMap<K, V, S>) -> Self {
+        Self {
+            snapshot: Cache::new(Arc::new(map)),
+        }
+    }
+
+    // The map as of now. It can be held and iterated for as long as needed,
+    // later changes publish a new version and leave it untouched.
+    pub fn snapshot(&self) -> Arc<HashMap<K, V, S>> {
+        self.snapshot.get_data()
+    }
+
+    // Applies `f` to a copy of the current map and publishes the result as
+    // one version. Writers are serialized, so concurrent batches apply one
+    // after the other.
+    pub fn modify<R>(&self, f: impl FnOnce(&mut HashMap<K, V, S>) -> R) -> R {
+        let mut result = None;
+
+        self.snapshot.update_with(|current| {
+            let mut map = HashMap::clone(current.expect("snapshot maps are never cleared"));
+            result = Some(f(&mut map));
+            Arc::new(map)
+        });
+
+        result.expect("`update_with` always runs its closure")
+    }
+
+    pub fn replace(&self, map: HashMap<K, V, S>) {
+        self.snapshot.update(Arc::new(map));
+    }
+}
+
+impl<K, V> Default for SnapshotMap<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot_map() {
+        let routes: SnapshotMap<&str, &str> = SnapshotMap::new();
+
+        let replaced = routes.modify(|routes| {
+            routes.insert("/api", "backend-a");
+            routes.insert("/static", "cdn");
+            routes.insert("/api", "backend-b")
+        });
+        assert_eq!(replaced, Some("backend-a"));
+
+        let before = routes.snapshot();
+        routes.modify(|routes| {
+            routes.remove("/static");
+            routes.insert("/admin", "backend-c");
+        });
+
+        // Old snapshots keep their consistent view
+        assert_eq!(before.len(), 2);
+        assert_eq!(before.get("/static"), Some(&"cdn"));
+        assert!(!before.contains_key("/admin"));
+
+        let after = routes.snapshot();
+        assert_eq!(after.len(), 2);
+        assert!(!after.contains_key("/static"));
+
+        routes.replace(HashMap::new());
+        assert!(routes.snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_batches_are_atomic() {
+        let map: SnapshotMap<u64, u64> = SnapshotMap::new();
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 0..500 {
+                    map.modify(|map| {
+                        map.insert(0, i);
+                        map.insert(1, i);
+                    });
+                }
+            });
+
+            for _ in 0..2_000 {
+                let snapshot = map.snapshot();
+                assert_eq!(snapshot.get(&0), snapshot.get(&1));
+            }
+        });
+    }
+}
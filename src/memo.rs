@@ -0,0 +1,132 @@
+use std::{borrow::Borrow, hash::Hash};
+
+use crate::{map::CacheMap, singleflight::Group};
+
+// Results of an expensive function, computed once per key. Cached results
+// are read from a `CacheMap` snapshot without locking. Concurrent callers
+// missing the same key wait for a single computation instead of all running
+// it.
+pub struct MemoCache<K, V> {
+    values: CacheMap<K, V>,
+    flights: Group<K, V>,
+}
+
+impl<K, V> MemoCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            values: CacheMap::new(),
+            flights: Group::new(),
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.values.get(key)
+    }
+
+    // Cached result for `key`, computing it with `f` if there is none yet.
+    // If `f` panics, the panic propagates to this caller and one of the
+    // waiting callers runs its own `f` instead.
+    pub fn get_or_compute(&self, key: K, f: impl FnOnce(&K) -> V) -> V {
+        if let Some(value) = self.values.get(&key) {
+            return value;
+        }
+
+        self.flights.do_call(key.clone(), || {
+            // A call for `key` may have finished since the lookup above
+            if let Some(value) = self.values.get(&key) {
+                return value;
+            }
+
+            let value = f(&key);
+            self.values.insert(key, value.clone());
+            value
+        })
+    }
+
+    // Forgets the result for `key`, the next call computes it again
+    pub fn invalidate<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.values.remove(key)
+    }
+
+    pub fn clear(&self) {
+        self.values.replace_all([]);
+    }
+}
+
+impl<K, V> Default for MemoCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_memo_cache() {
+        let memo: MemoCache<u64, u64> = MemoCache::new();
+        let calls = AtomicUsize::new(0);
+
+        thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for key in 0..4 {
+                        let value = memo.get_or_compute(key, |key| {
+                            calls.fetch_add(1, Ordering::AcqRel);
+                            thread::sleep(Duration::from_millis(2));
+                            key * 10
+                        });
+                        assert_eq!(value, key * 10);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(calls.load(Ordering::Acquire), 4);
+        assert_eq!(memo.get(&3), Some(30));
+        assert_eq!(memo.flights.in_flight(), 0);
+
+        assert_eq!(memo.invalidate(&3), Some(30));
+        assert_eq!(memo.get_or_compute(3, |_| 31), 31);
+
+        memo.clear();
+        assert_eq!(memo.get(&3), None);
+    }
+
+    #[test]
+    fn test_failed_computation_is_retried() {
+        let memo: MemoCache<&str, u64> = MemoCache::new();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            memo.get_or_compute("key", |_| panic!("compute panicked"))
+        }));
+        assert!(result.is_err());
+        assert_eq!(memo.flights.in_flight(), 0);
+
+        assert_eq!(memo.get_or_compute("key", |_| 1), 1);
+    }
+}
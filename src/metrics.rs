@@ -0,0 +1,202 @@
+use std::{
+    fmt::Write,
+    sync::{Arc, Mutex},
+};
+
+use crate::cache::CacheStats;
+
+// Prometheus metrics for caches registered with `Cache::describe_metrics`,
+// rendered in the text exposition format by `render`. Serve the output from
+// a `/metrics` endpoint for the scraper to pick up.
+//
+// Caches are held weakly and disappear from the output once dropped.
+#[derive(Clone, Default)]
+pub struct Registry {
+    sources: Arc<Mutex<Vec<Source>>>,
+}
+
+struct Source {
+    name: String,
+    sample: Box<dyn Fn() -> Option<Sample> + Send + Sync>,
+}
+
+// What a cache looked like when scraped
+pub(crate) struct Sample {
+    pub(crate) stats: CacheStats,
+    pub(crate) generation: u64,
+    pub(crate) active_slot: usize,
+}
+
+struct Family {
+    name: &'static str,
+    kind: &'static str,
+    help: &'static str,
+    value: fn(&Sample) -> u64,
+}
+
+const FAMILIES: [Family; 6] = [
+    Family {
+        name: "sloth_cache_reads_total",
+        kind: "counter",
+        help: "Values read from the cache.",
+        value: |sample| sample.stats.reads,
+    },
+    Family {
+        name: "sloth_cache_writes_total",
+        kind: "counter",
+        help: "Values published to the cache.",
+        value: |sample| sample.stats.writes,
+    },
+    Family {
+        name: "sloth_cache_lock_spins_total",
+        kind: "counter",
+        help: "Rounds writers spent waiting on the writer lock.",
+        value: |sample| sample.stats.lock_spins,
+    },
+    Family {
+        name: "sloth_cache_scan_skips_total",
+        kind: "counter",
+        help: "Slots skipped by writers because readers had them pinned.",
+        value: |sample| sample.stats.scan_skips,
+    },
+    Family {
+        name: "sloth_cache_generation",
+        kind: "gauge",
+        help: "Generation of the current value.",
+        value: |sample| sample.generation,
+    },
+    Family {
+        name: "sloth_cache_active_slot",
+        kind: "gauge",
+        help: "Slot holding the current value.",
+        value: |sample| sample.active_slot as u64,
+    },
+];
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Registering a second cache under the same name replaces the first
+    pub(crate) fn register(
+        &self,
+        name: &str,
+        sample: impl Fn() -> Option<Sample> + Send + Sync + 'static,
+    ) {
+        let mut sources = self.sources.lock().unwrap_or_else(|err| err.into_inner());
+
+        sources.retain(|source| source.name != name);
+        sources.push(Source {
+            name: name.to_owned(),
+            sample: Box::new(sample),
+        });
+    }
+
+    // Names of the caches still alive, in registration order
+    pub fn names(&self) -> Vec<String> {
+        self.samples().into_iter().map(|(name, _)| name).collect()
+    }
+
+    pub fn render(&self) -> String {
+        let samples = self.samples();
+        let mut out = String::new();
+
+        for family in &FAMILIES {
+            let _ = writeln!(out, "# HELP {} {}", family.name, family.help);
+            let _ = writeln!(out, "# TYPE {} {}", family.name, family.kind);
+
+            for (name, sample) in &samples {
+                let _ = writeln!(
+                    out,
+                    "{}{{cache=\"{}\"}} {}",
+                    family.name,
+                    escape(name),
+                    (family.value)(sample)
+                );
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP sloth_cache_slot_readers Readers pinning the slot."
+        );
+        let _ = writeln!(out, "# TYPE sloth_cache_slot_readers gauge");
+
+        for (name, sample) in &samples {
+            for (slot, stats) in sample.stats.slots.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "sloth_cache_slot_readers{{cache=\"{}\",slot=\"{slot}\"}} {}",
+                    escape(name),
+                    stats.readers
+                );
+            }
+        }
+
+        out
+    }
+
+    // Samples every live cache, forgetting the dropped ones
+    fn samples(&self) -> Vec<(String, Sample)> {
+        let mut sources = self.sources.lock().unwrap_or_else(|err| err.into_inner());
+        let mut samples = Vec::with_capacity(sources.len());
+
+        sources.retain(|source| match (source.sample)() {
+            Some(sample) => {
+                samples.push((source.name.clone(), sample));
+                true
+            }
+            None => false,
+        });
+
+        samples
+    }
+}
+
+// Label values are quoted, so backslashes, quotes and newlines are escaped
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::Cache;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let registry = Registry::new();
+        let cache = Arc::new(Cache::<u64>::new(1));
+
+        cache.describe_metrics(&registry, "routes \"v2\"");
+        cache.update(2);
+        cache.get_data();
+
+        let text = registry.render();
+        assert!(text.contains("# TYPE sloth_cache_reads_total counter\n"));
+        assert!(text.contains("sloth_cache_reads_total{cache=\"routes \\\"v2\\\"\"} 1\n"));
+        assert!(text.contains("sloth_cache_writes_total{cache=\"routes \\\"v2\\\"\"} 1\n"));
+        assert!(text.contains("sloth_cache_generation{cache=\"routes \\\"v2\\\"\"} 1\n"));
+        assert!(text.contains("sloth_cache_active_slot{cache=\"routes \\\"v2\\\"\"} 1\n"));
+        assert!(
+            text.contains("sloth_cache_slot_readers{cache=\"routes \\\"v2\\\"\",slot=\"3\"} 0\n")
+        );
+
+        drop(cache);
+        assert!(registry.names().is_empty());
+        assert!(!registry.render().contains("cache=\""));
+    }
+}
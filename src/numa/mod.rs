@@ -0,0 +1,132 @@
+use std::sync::Mutex;
+
+use crate::{
+    cache::{Cache, Policy},
+    utils::Padded,
+};
+
+mod topology;
+
+use topology::Topology;
+
+// `Cache` replicated once per NUMA node. Readers go to the replica of the
+// node they are running on, so the slots they pin stay in node-local memory
+// and caches, and writers publish to every replica in turn.
+//
+// Between two replicas being updated, readers on different nodes may see
+// different values, but each replica on its own moves forward in the same
+// order.
+pub struct NumaCache<T, const LEN: usize = 4>
+where
+    T: Clone,
+{
+    topology: Topology,
+    // Serializes the fan-out so replicas are updated in the same order
+    writing: Mutex<()>,
+    replicas: Box<[Padded<Cache<T, LEN>>]>,
+}
+
+impl<T: Clone, const LEN: usize> NumaCache<T, LEN> {
+    pub fn new(data: T) -> Self {
+        Self::with_policy(data, Policy::default())
+    }
+
+    pub fn with_policy(data: T, policy: Policy) -> Self {
+        Self::with_topology(Topology::detect(), data, policy)
+    }
+
+    fn with_topology(topology: Topology, data: T, policy: Policy) -> Self {
+        // The kernel places pages on first touch, so the replicas start out
+        // on the creating thread's node. Binding them to their own node
+        // would take libnuma, but the hot cache lines still end up in the
+        // caches of the node reading them.
+        let replicas = (0..topology.nodes())
+            .map(|_| Padded::new(Cache::with_policy(data.clone(), policy)))
+            .collect();
+
+        Self {
+            topology,
+            writing: Mutex::new(()),
+            replicas,
+        }
+    }
+
+    pub fn nodes(&self) -> usize {
+        self.replicas.len()
+    }
+
+    pub fn get_data(&self) -> T {
+        self.local().get_data()
+    }
+
+    pub fn update(&self, data: T) {
+        let _guard = self.writing.lock().unwrap_or_else(|err| err.into_inner());
+
+        let (last, rest) = self.replicas.split_last().expect("at least one node");
+
+        for replica in rest {
+            replica.update(data.clone());
+        }
+
+        last.update(data);
+    }
+
+    fn local(&self) -> &Cache<T, LEN> {
+        &self.replicas[self.topology.node_of(current_cpu())]
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn current_cpu() -> usize {
+    // Safety: no preconditions, returns -1 on failure
+    let cpu = unsafe { libc::sched_getcpu() };
+
+    usize::try_from(cpu).unwrap_or(0)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_cpu() -> usize {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_numa_cache() {
+        let cache: NumaCache<u64> = NumaCache::new(1);
+        assert!(cache.nodes() >= 1);
+        assert_eq!(cache.get_data(), 1);
+
+        cache.update(2);
+        assert_eq!(cache.get_data(), 2);
+    }
+
+    #[test]
+    fn test_updates_reach_every_replica() {
+        let cache: NumaCache<u64, 2> =
+            NumaCache::with_topology(Topology::with_nodes(3), 0, Policy::default());
+        assert_eq!(cache.nodes(), 3);
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for i in 1..=250 {
+                        cache.update(i);
+                    }
+                });
+            }
+        });
+
+        cache.update(u64::MAX);
+        assert!(
+            cache
+                .replicas
+                .iter()
+                .all(|replica| replica.get_data() == u64::MAX)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use std::{fs, path::Path};
+
+const NODES: &str = "/sys/devices/system/node";
+
+// Which NUMA node each CPU belongs to, read from sysfs. Machines without
+// NUMA, or where sysfs can't be read, are treated as a single node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Topology {
+    nodes: usize,
+    // Node of each CPU, by CPU number
+    cpu_nodes: Vec<usize>,
+}
+
+impl Topology {
+    pub(crate) fn detect() -> Self {
+        Self::read(Path::new(NODES)).unwrap_or_else(Self::single_node)
+    }
+
+    pub(crate) fn single_node() -> Self {
+        Self {
+            nodes: 1,
+            cpu_nodes: Vec::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_nodes(nodes: usize) -> Self {
+        Self {
+            nodes,
+            cpu_nodes: Vec::new(),
+        }
+    }
+
+    pub(crate) fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    pub(crate) fn node_of(&self, cpu: usize) -> usize {
+        self.cpu_nodes.get(cpu).copied().unwrap_or(0)
+    }
+
+    fn read(root: &Path) -> Option<Self> {
+        let online = parse_list(&fs::read_to_string(root.join("online")).ok()?)?;
+
+        // Node numbers may have holes, replicas are indexed densely
+        let mut cpu_nodes = Vec::new();
+
+        for (index, node) in online.iter().enumerate() {
+            let cpus = fs::read_to_string(root.join(format!("node{node}/cpulist"))).ok()?;
+
+            for cpu in parse_list(&cpus)? {
+                if cpu_nodes.len() <= cpu {
+                    cpu_nodes.resize(cpu + 1, 0);
+                }
+
+                cpu_nodes[cpu] = index;
+            }
+        }
+
+        Some(Self {
+            nodes: online.len().max(1),
+            cpu_nodes,
+        })
+    }
+}
+
+// Parses the kernel's list format, such as `0-3,8,10-11`
+fn parse_list(list: &str) -> Option<Vec<usize>> {
+    let list = list.trim();
+
+    if list.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let mut values = Vec::new();
+
+    for range in list.split(',') {
+        match range.split_once('-') {
+            Some((start, end)) => values.extend(start.parse::<usize>().ok()?..=end.parse().ok()?),
+            None => values.push(range.parse().ok()?),
+        }
+    }
+
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(
+            parse_list("0-3,8,10-11\n"),
+            Some(vec![0, 1, 2, 3, 8, 10, 11])
+        );
+        assert_eq!(parse_list(""), Some(Vec::new()));
+        assert_eq!(parse_list("0-x"), None);
+    }
+
+    #[test]
+    fn test_read_topology() {
+        let root = std::env::temp_dir().join(format!("sloth-numa-{}", std::process::id()));
+
+        for (node, cpus) in [(0, "0-1"), (2, "2-3")] {
+            fs::create_dir_all(root.join(format!("node{node}"))).unwrap();
+            fs::write(root.join(format!("node{node}/cpulist")), cpus).unwrap();
+        }
+        fs::write(root.join("online"), "0,2").unwrap();
+
+        let topology = Topology::read(&root).unwrap();
+        assert_eq!(topology.nodes(), 2);
+        assert_eq!(topology.node_of(1), 0);
+        assert_eq!(topology.node_of(3), 1);
+        assert_eq!(topology.node_of(64), 0);
+
+        fs::remove_dir_all(&root).unwrap();
+        assert!(Topology::read(&root).is_none());
+    }
+}
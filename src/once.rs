@@ -0,0 +1,111 @@
+use std::sync::Mutex;
+
+use crate::cache::{Cache, Policy};
+
+// Value initialized once, on first use. Exactly one caller runs the
+// initializer while the others wait for it, and reads after that are plain
+// `Cache` reads.
+pub struct OnceCache<T>
+where
+    T: Clone,
+{
+    cache: Cache<T>,
+    // Held while an initializer runs
+    init: Mutex<()>,
+}
+
+impl<T: Clone> OnceCache<T> {
+    pub fn new() -> Self {
+        Self {
+            cache: Cache::empty(Policy::default()),
+            init: Mutex::new(()),
+        }
+    }
+
+    pub fn get(&self) -> Option<T> {
+        self.cache.try_get_data()
+    }
+
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> T {
+        match self.get_or_try_init(|| Ok::<_, core::convert::Infallible>(f())) {
+            Ok(data) => data,
+        }
+    }
+
+    // Like `get_or_init`, but leaves the cache empty if `f` fails so the
+    // next caller tries again
+    pub fn get_or_try_init<E>(&self, f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+        if let Some(data) = self.get() {
+            return Ok(data);
+        }
+
+        // A panicking initializer leaves nothing behind, so a poisoned lock
+        // is as good as a free one
+        let _guard = self.init.lock().unwrap_or_else(|err| err.into_inner());
+
+        if let Some(data) = self.get() {
+            return Ok(data);
+        }
+
+        let data = f()?;
+        self.cache.update(data.clone());
+
+        Ok(data)
+    }
+}
+
+impl<T: Clone> Default for OnceCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_once_cache() {
+        let cache = OnceCache::new();
+        let calls = AtomicUsize::new(0);
+        assert_eq!(cache.get(), None);
+
+        thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    let value = cache.get_or_init(|| {
+                        calls.fetch_add(1, Ordering::AcqRel);
+                        thread::sleep(std::time::Duration::from_millis(5));
+                        String::from("ready")
+                    });
+                    assert_eq!(value, "ready");
+                });
+            }
+        });
+
+        assert_eq!(calls.load(Ordering::Acquire), 1);
+        assert_eq!(cache.get().as_deref(), Some("ready"));
+    }
+
+    #[test]
+    fn test_get_or_try_init() {
+        let cache: OnceCache<u64> = OnceCache::new();
+
+        assert_eq!(cache.get_or_try_init(|| Err("down")), Err("down"));
+        assert_eq!(cache.get(), None);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            cache.get_or_init(|| panic!("init panicked"))
+        }));
+        assert!(result.is_err());
+
+        assert_eq!(cache.get_or_try_init(|| Ok::<_, &str>(3)), Ok(3));
+        assert_eq!(cache.get_or_try_init(|| Err("unused")), Ok(3));
+    }
+}
@@ -0,0 +1,41 @@
+use std::{error::Error, fmt, io};
+
+pub(crate) type BoxError = Box<dyn Error + Send + Sync>;
+
+// Why a value could not be written to or read back from disk
+#[derive(Debug)]
+pub enum PersistError {
+    Io(io::Error),
+    Encode(BoxError),
+    Decode(BoxError),
+    // Refused by the cache's validator, see `Cache::set_validator`
+    Rejected(BoxError),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistError::Io(err) => write!(f, "failed to access persisted value: {err}"),
+            PersistError::Encode(err) => write!(f, "failed to encode value: {err}"),
+            PersistError::Decode(err) => write!(f, "failed to decode persisted value: {err}"),
+            PersistError::Rejected(err) => write!(f, "value rejected by the validator: {err}"),
+        }
+    }
+}
+
+impl Error for PersistError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PersistError::Io(err) => Some(err),
+            PersistError::Encode(err) | PersistError::Decode(err) | PersistError::Rejected(err) => {
+                Some(&**err)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for PersistError {
+    fn from(err: io::Error) -> Self {
+        PersistError::Io(err)
+    }
+}
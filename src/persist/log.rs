@@ -0,0 +1,192 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+
+use crate::cache::Cache;
+
+use super::PersistError;
+
+// Append-only audit trail of a cache: every value published through
+// `publish` is appended to a JSON Lines file with its generation, time and
+// source before readers can see it. `replay` reads the history back.
+//
+// Only `publish` is logged, updates made on the cache directly bypass the
+// log. Generations restart with the process, so order entries by position
+// rather than generation.
+pub struct ChangeLog<T, const LEN: usize = 4>
+where
+    T: Clone,
+{
+    cache: Arc<Cache<T, LEN>>,
+    file: Mutex<File>,
+}
+
+// One published value, as recorded by `ChangeLog::publish`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry<T> {
+    pub generation: u64,
+    pub published: SystemTime,
+    pub source: String,
+    pub value: T,
+}
+
+impl<T: Clone + Serialize, const LEN: usize> ChangeLog<T, LEN> {
+    // Appends to the file at `path`, creating it if needed
+    pub fn open(cache: Arc<Cache<T, LEN>>, path: impl AsRef<Path>) -> Result<Self, PersistError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            cache,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn cache(&self) -> &Arc<Cache<T, LEN>> {
+        &self.cache
+    }
+
+    // Logs `data` and publishes it, returning its generation. The value is
+    // staged through `Cache::prepare` and only committed once the entry is
+    // on disk, so a failed write leaves the cache untouched.
+    pub fn publish(&self, data: T, source: impl Into<String>) -> Result<u64, PersistError> {
+        let source = source.into();
+        let mut file = self.file.lock().unwrap_or_else(|err| err.into_inner());
+
+        let prepared = self
+            .cache
+            .prepare(data)
+            .map_err(|rejected| PersistError::Rejected(rejected.error))?;
+
+        let generation = prepared.generation();
+        let published = SystemTime::now();
+        let millis = published
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let value = serde_json::to_value(prepared.data())
+            .map_err(|err| PersistError::Encode(err.into()))?;
+
+        let entry = json!({
+            "generation": generation,
+            "published_ms": millis,
+            "source": source,
+            "value": value,
+        });
+
+        // One write per line, so a crash can only tear the last one
+        let mut line = entry.to_string();
+        line.push('\n');
+
+        let start = file.metadata()?.len();
+
+        if let Err(err) = file
+            .write_all(line.as_bytes())
+            .and_then(|()| file.sync_data())
+        {
+            // Don't leave half a line for the next entry to be appended to
+            let _ = file.set_len(start);
+            return Err(err.into());
+        }
+
+        prepared.commit_tagged(source);
+
+        Ok(generation)
+    }
+}
+
+// Reads back every entry written to `path` by a `ChangeLog`, oldest first.
+// A final line cut short by a crash is skipped.
+pub fn replay<T: DeserializeOwned>(
+    path: impl AsRef<Path>,
+) -> Result<Vec<LogEntry<T>>, PersistError> {
+    let text = fs::read_to_string(path)?;
+    let complete = match text.rfind('\n') {
+        Some(end) => &text[..end],
+        None => "",
+    };
+
+    complete
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_entry)
+        .collect()
+}
+
+fn parse_entry<T: DeserializeOwned>(line: &str) -> Result<LogEntry<T>, PersistError> {
+    let decode = |err: serde_json::Error| PersistError::Decode(err.into());
+    let missing = |field: &str| PersistError::Decode(format!("entry has no `{field}`").into());
+
+    let mut entry: Value = serde_json::from_str(line).map_err(decode)?;
+
+    let generation = entry["generation"]
+        .as_u64()
+        .ok_or_else(|| missing("generation"))?;
+    let millis = entry["published_ms"]
+        .as_u64()
+        .ok_or_else(|| missing("published_ms"))?;
+    let source = entry["source"]
+        .as_str()
+        .ok_or_else(|| missing("source"))?
+        .to_owned();
+    let value = serde_json::from_value(entry["value"].take()).map_err(decode)?;
+
+    Ok(LogEntry {
+        generation,
+        published: UNIX_EPOCH + Duration::from_millis(millis),
+        source,
+        value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_log() {
+        let path = std::env::temp_dir().join(format!("sloth-log-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let cache = Arc::new(Cache::<Vec<u32>>::new(vec![]));
+        cache.set_validator(|data: &Vec<u32>| {
+            if data.len() > 2 {
+                return Err("too long");
+            }
+
+            Ok(())
+        });
+
+        let log = ChangeLog::open(cache.clone(), &path).unwrap();
+        assert_eq!(log.publish(vec![1], "alice").unwrap(), 1);
+        assert_eq!(log.publish(vec![1, 2], "bob").unwrap(), 2);
+        assert!(matches!(
+            log.publish(vec![1, 2, 3], "eve"),
+            Err(PersistError::Rejected(_))
+        ));
+
+        assert_eq!(cache.get_data(), vec![1, 2]);
+        assert_eq!(cache.current_meta().source.as_deref(), Some("bob"));
+
+        // A torn final line is ignored
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"generation\": 3, \"pub").unwrap();
+
+        let entries: Vec<LogEntry<Vec<u32>>> = replay(&path).unwrap();
+        let history: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.generation, entry.source.as_str(), entry.value.clone()))
+            .collect();
+
+        assert_eq!(history, vec![(1, "alice", vec![1]), (2, "bob", vec![1, 2])]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
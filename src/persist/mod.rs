@@ -0,0 +1,265 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::cache::Cache;
+
+mod error;
+mod log;
+
+pub use error::PersistError;
+pub use log::{ChangeLog, LogEntry, replay};
+
+use error::BoxError;
+
+// How values are turned into bytes on disk and back
+pub trait Codec<T>: Send + 'static {
+    fn encode(&self, data: &T) -> Result<Vec<u8>, BoxError>;
+    fn decode(&self, bytes: &[u8]) -> Result<T, BoxError>;
+}
+
+// Any serde type, as JSON
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+impl<T: Serialize + DeserializeOwned> Codec<T> for Json {
+    fn encode(&self, data: &T) -> Result<Vec<u8>, BoxError> {
+        Ok(serde_json::to_vec(data)?)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<T, BoxError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+// Handle to the thread started by `Cache::persist_to`. Dropping it or calling
+// `stop` ends the thread after writing out whatever was published last.
+pub struct Persister {
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T: Clone + Send + Sync + 'static, const LEN: usize> Cache<T, LEN> {
+    // Writes the current value to `path` on a background thread, then again
+    // after every publish. Publishes made while a write is in flight are
+    // coalesced into one write of the latest value.
+    //
+    // Each write goes to a temporary file next to `path` that is then
+    // renamed over it, so a crash never leaves a torn file behind for
+    // `restore_from` to trip over.
+    pub fn persist_to(
+        self: Arc<Self>,
+        path: impl Into<PathBuf>,
+        codec: impl Codec<T>,
+        mut on_error: impl FnMut(PersistError) + Send + 'static,
+    ) -> Persister {
+        let path = path.into();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let signal = stopped.clone();
+
+        let thread = thread::spawn(move || {
+            let mut written = None;
+
+            loop {
+                let seen = self.generation();
+
+                if Some(seen) != written {
+                    // Nothing to write while the cache is empty
+                    if let Some((data, generation)) = self.try_get_with_generation() {
+                        let result = codec
+                            .encode(&data)
+                            .map_err(PersistError::Encode)
+                            .and_then(|bytes| Ok(write_atomic(&path, &bytes)?));
+
+                        match result {
+                            Ok(()) => written = Some(generation),
+                            Err(err) => on_error(err),
+                        }
+                    }
+                }
+
+                // Publishes that landed during the write still go out before
+                // stopping
+                if signal.load(Ordering::Acquire) {
+                    if self.generation() == seen {
+                        return;
+                    }
+
+                    continue;
+                }
+
+                self.park_until_updated(seen, || signal.load(Ordering::Acquire));
+            }
+        });
+
+        Persister {
+            stopped,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl<T: Clone, const LEN: usize> Cache<T, LEN> {
+    // Builds a cache holding the value last written by `persist_to`
+    pub fn restore_from(
+        path: impl AsRef<Path>,
+        codec: impl Codec<T>,
+    ) -> Result<Self, PersistError> {
+        let bytes = fs::read(path)?;
+        let data = codec.decode(&bytes).map_err(PersistError::Decode)?;
+
+        Ok(Cache::new(data))
+    }
+}
+
+impl Persister {
+    // Waits for the latest value to be written and the thread to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Persister {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".tmp");
+
+    let temp = path.with_file_name(name);
+
+    let mut file = File::create(&temp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&temp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::mpsc,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+
+        std::env::temp_dir().join(format!("sloth-{name}-{}-{nanos}.json", std::process::id()))
+    }
+
+    #[test]
+    fn test_persist_and_restore() {
+        let path = temp_path("persist");
+        let cache = Arc::new(Cache::<Vec<u32>>::new(vec![1]));
+
+        let persister = cache.clone().persist_to(&path, Json, |err| panic!("{err}"));
+
+        cache.update(vec![1, 2]);
+        cache.update(vec![1, 2, 3]);
+        persister.stop();
+
+        let restored: Cache<Vec<u32>> = Cache::restore_from(&path, Json).unwrap();
+        assert_eq!(restored.get_data(), vec![1, 2, 3]);
+
+        fs::write(&path, b"[1, 2").unwrap();
+        assert!(matches!(
+            Cache::<Vec<u32>>::restore_from(&path, Json),
+            Err(PersistError::Decode(_))
+        ));
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            Cache::<Vec<u32>>::restore_from(&path, Json),
+            Err(PersistError::Io(_))
+        ));
+    }
+
+    // Stands in for a slow disk: the first write waits for `go`
+    struct Slow {
+        started: mpsc::Sender<()>,
+        go: mpsc::Receiver<()>,
+        first: AtomicBool,
+    }
+
+    impl Codec<u32> for Slow {
+        fn encode(&self, data: &u32) -> Result<Vec<u8>, BoxError> {
+            if !self.first.swap(true, Ordering::AcqRel) {
+                self.started.send(())?;
+                self.go.recv()?;
+            }
+
+            Json.encode(data)
+        }
+
+        fn decode(&self, bytes: &[u8]) -> Result<u32, BoxError> {
+            Json.decode(bytes)
+        }
+    }
+
+    #[test]
+    fn test_stop_during_write() {
+        let path = temp_path("stop-during-write");
+        let cache = Arc::new(Cache::<u32>::new(1));
+        let (started, on_started) = mpsc::channel();
+        let (go, on_go) = mpsc::channel();
+        let codec = Slow {
+            started,
+            go: on_go,
+            first: AtomicBool::new(false),
+        };
+
+        let persister = cache
+            .clone()
+            .persist_to(&path, codec, |err| panic!("{err}"));
+        let stopped = persister.stopped.clone();
+        on_started.recv().unwrap();
+
+        // Published while the first value is being written
+        cache.update(2);
+        cache.update(3);
+
+        thread::scope(|s| {
+            s.spawn(|| persister.stop());
+
+            while !stopped.load(Ordering::Acquire) {
+                thread::yield_now();
+            }
+
+            go.send(()).unwrap();
+        });
+
+        let restored: Cache<u32> = Cache::restore_from(&path, Json).unwrap();
+        assert_eq!(restored.get_data(), 3);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,109 @@
+use core::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+// Free list of values to reuse instead of reallocating. Meant for buffers
+// such as `String` and `Vec` whose `clone_from` reuses the existing
+// allocation, see `Cache::get_data_pooled`.
+pub struct Pool<T> {
+    free: Mutex<Vec<T>>,
+    // Values beyond this are dropped instead of kept for reuse
+    capacity: usize,
+}
+
+impl<T> Pool<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            free: Mutex::new(Vec::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    // A value given back earlier, if any
+    pub fn pull(&self) -> Option<T> {
+        self.free
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .pop()
+    }
+
+    pub fn put(&self, value: T) {
+        let mut free = self.free.lock().unwrap_or_else(|err| err.into_inner());
+
+        if free.len() < self.capacity {
+            free.push(value);
+        }
+    }
+
+    // Wraps `value` so it goes back to the pool when dropped
+    pub fn attach(&self, value: T) -> Pooled<'_, T> {
+        Pooled {
+            pool: self,
+            value: Some(value),
+        }
+    }
+
+    pub fn idle(&self) -> usize {
+        self.free
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .len()
+    }
+}
+
+pub struct Pooled<'a, T> {
+    pool: &'a Pool<T>,
+    value: Option<T>,
+}
+
+impl<T> Pooled<'_, T> {
+    // Keeps the value instead of returning it to the pool
+    pub fn into_inner(mut self) -> T {
+        self.value.take().expect("only taken on drop")
+    }
+}
+
+impl<T> Deref for Pooled<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().expect("only taken on drop")
+    }
+}
+
+impl<T> DerefMut for Pooled<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().expect("only taken on drop")
+    }
+}
+
+impl<T> Drop for Pooled<'_, T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            self.pool.put(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool() {
+        let pool = Pool::new(1);
+        assert_eq!(pool.pull(), None::<String>);
+
+        let value = pool.attach(String::with_capacity(64));
+        let other = pool.attach(String::new());
+        drop(value);
+        drop(other);
+
+        // Only one fits
+        assert_eq!(pool.idle(), 1);
+        assert_eq!(pool.pull().unwrap().capacity(), 64);
+
+        let kept = pool.attach(String::from("kept")).into_inner();
+        assert_eq!(kept, "kept");
+        assert_eq!(pool.idle(), 0);
+    }
+}
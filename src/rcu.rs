@@ -0,0 +1,155 @@
+use core::{marker::PhantomData, ops::Deref, sync::atomic::Ordering};
+
+use crossbeam::epoch::{self, Atomic, Guard, Owned};
+
+// Read-copy-update cell. Readers pin the current epoch and borrow the value
+// in place, writers swap in a new boxed value and leave the old one to the
+// epoch collector, which drops it once every reader that could see it has
+// unpinned. Unlike `Cache` there is no fixed number of slots to run out of,
+// at the price of an allocation per update and deferred drops.
+pub struct RcuCell<T> {
+    data: Atomic<T>,
+}
+
+impl<T: Send + Sync> RcuCell<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            data: Atomic::new(data),
+        }
+    }
+
+    // Borrows the current value. Holding the guard delays reclamation of
+    // every value retired meanwhile, not just this one, so keep it short.
+    pub fn read(&self) -> RcuGuard<'_, T> {
+        let guard = epoch::pin();
+        let data = self.data.load(Ordering::Acquire, &guard).as_raw();
+
+        RcuGuard {
+            guard,
+            data,
+            cell: PhantomData,
+        }
+    }
+
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        T::clone(&self.read())
+    }
+
+    pub fn update(&self, data: T) {
+        let guard = epoch::pin();
+        let old = self.data.swap(Owned::new(data), Ordering::AcqRel, &guard);
+
+        // Safety: `old` is unreachable from the cell now, and readers that
+        // loaded it are pinned in an epoch the collector waits for
+        unsafe { guard.defer_destroy(old) };
+    }
+
+    // Publishes `f` applied to the current value, retrying if another
+    // update lands first. `f` may run several times.
+    pub fn update_with(&self, mut f: impl FnMut(&T) -> T) {
+        let guard = epoch::pin();
+        let mut current = self.data.load(Ordering::Acquire, &guard);
+
+        loop {
+            // Safety: never null, and protected by `guard`
+            let new = Owned::new(f(unsafe { current.deref() }));
+
+            match self.data.compare_exchange(
+                current,
+                new,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+                &guard,
+            ) {
+                Ok(_) => {
+                    // Safety: see `update`
+                    unsafe { guard.defer_destroy(current) };
+                    return;
+                }
+                Err(err) => current = err.current,
+            }
+        }
+    }
+}
+
+impl<T> Drop for RcuCell<T> {
+    fn drop(&mut self) {
+        // Safety: `&mut self` means no reader can still load the value
+        unsafe {
+            let guard = epoch::unprotected();
+            drop(self.data.load(Ordering::Relaxed, guard).into_owned());
+        }
+    }
+}
+
+pub struct RcuGuard<'a, T> {
+    guard: Guard,
+    data: *const T,
+    cell: PhantomData<&'a RcuCell<T>>,
+}
+
+impl<T> Deref for RcuGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        let _ = &self.guard;
+
+        // Safety: the value can't be reclaimed while `guard` is pinned
+        unsafe { &*self.data }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, atomic::Ordering},
+        thread,
+    };
+
+    use super::*;
+    use crate::testing::{Counted, Counts};
+
+    #[test]
+    fn test_rcu_cell() {
+        let cell = RcuCell::new(String::from("first"));
+
+        let first = cell.read();
+        cell.update(String::from("second"));
+
+        // Old readers keep their value, new ones see the update
+        assert_eq!(&*first, "first");
+        assert_eq!(cell.get(), "second");
+        drop(first);
+
+        cell.update_with(|current| format!("{current}!"));
+        assert_eq!(&*cell.read(), "second!");
+    }
+
+    #[test]
+    fn test_values_are_reclaimed() {
+        let drops = Arc::new(Counts::default());
+        let cell = RcuCell::new(Counted(0, drops.clone()));
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..250 {
+                        cell.update_with(|current| Counted(current.0 + 1, drops.clone()));
+                        assert!(cell.read().0 <= 1_000);
+                    }
+                });
+            }
+        });
+        assert_eq!(cell.read().0, 1_000);
+
+        drop(cell);
+
+        // Retired values go through the collector, which may still hold a
+        // few, but the last one is dropped with the cell
+        let drops = drops.drops.load(Ordering::Acquire);
+        assert!(drops >= 1, "{drops}");
+    }
+}
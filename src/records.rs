@@ -0,0 +1,100 @@
+use alloc::boxed::Box;
+use core::{iter, ptr};
+
+use crate::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+// Announcement record of one reader. What it announces and how is up to
+// `H` and its owner, the list only hands records out and links them.
+pub(crate) struct Record<H> {
+    pub(crate) hazard: H,
+    active: AtomicBool,
+    next: *mut Record<H>,
+}
+
+// Safety: `next` is only written before the record is published, so a
+// record may be used from any thread its hazard may
+unsafe impl<H: Send> Send for Record<H> {}
+unsafe impl<H: Sync> Sync for Record<H> {}
+
+impl<H> Record<H> {
+    // Gives the record back for the next `Records::acquire`. The owner
+    // clears the hazard first.
+    pub(crate) fn release(&self) {
+        self.active.store(false, Ordering::Release);
+    }
+}
+
+// Records of the readers of a structure, a lock-free list their writers
+// scan. Records are reused by later readers and never unlinked, so readers
+// can take and give them back without further synchronization, and they
+// are only freed with the list. Empty until the first reader, in which
+// case a scan is a single load.
+pub(crate) struct Records<H> {
+    head: AtomicPtr<Record<H>>,
+}
+
+impl<H> Records<H> {
+    pub(crate) fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    // Takes an idle record, or links a new one holding `hazard()`
+    pub(crate) fn acquire(&self, hazard: impl FnOnce() -> H) -> &Record<H> {
+        if let Some(record) = self.iter().find(|record| {
+            record
+                .active
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+        }) {
+            return record;
+        }
+
+        let record = Box::into_raw(Box::new(Record {
+            hazard: hazard(),
+            active: AtomicBool::new(true),
+            next: self.head.load(Ordering::Acquire),
+        }));
+
+        // Safety: records are only freed with the list
+        let new = unsafe { &mut *record };
+
+        // SeqCst so a writer that misses this record also misses every
+        // announcement made through it
+        while let Err(head) =
+            self.head
+                .compare_exchange(new.next, record, Ordering::SeqCst, Ordering::Acquire)
+        {
+            new.next = head;
+        }
+
+        new
+    }
+
+    // Every record linked so far, newest first. The head is loaded SeqCst,
+    // pairing with the push in `acquire`.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Record<H>> {
+        let mut next = self.head.load(Ordering::SeqCst);
+
+        iter::from_fn(move || {
+            // Safety: records are only freed with the list
+            let record = unsafe { next.as_ref() }?;
+            next = record.next;
+
+            Some(record)
+        })
+    }
+}
+
+impl<H> Drop for Records<H> {
+    fn drop(&mut self) {
+        let mut next = self.head.load(Ordering::Relaxed);
+
+        while !next.is_null() {
+            // Safety: `&mut self` means no reader holds a record
+            let record = unsafe { Box::from_raw(next) };
+            next = record.next;
+        }
+    }
+}
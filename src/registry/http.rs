@@ -0,0 +1,238 @@
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use super::{CacheInfo, CacheStatus, dump};
+
+// Response of the registry's debug endpoint, independent of any HTTP
+// framework. Copy the parts into the framework's response type, or write it
+// straight to a connection with `write_to`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl HttpResponse {
+    // Writes the response as HTTP/1.1, closing the connection afterwards
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason(self.status),
+            self.content_type,
+            self.body.len(),
+            self.body
+        )?;
+
+        writer.flush()
+    }
+}
+
+// Reason phrase of the status line
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+// Handler rendering every live named cache as a JSON array, see `to_json`.
+// Mount it on a debug route, e.g. `/debug/caches`.
+pub fn http_handler() -> impl Fn() -> HttpResponse + Clone + Send + Sync + 'static {
+    || respond(dump())
+}
+
+// Like `http_handler`, but caches not updated for longer than `max_age`
+// report `stale`, and the response is a 503 if any does, for health checks
+pub fn http_handler_stale_after(
+    max_age: Duration,
+) -> impl Fn() -> HttpResponse + Clone + Send + Sync + 'static {
+    move || {
+        let mut infos = dump();
+
+        for info in &mut infos {
+            if info.status == CacheStatus::Ok && info.age > max_age {
+                info.status = CacheStatus::Stale;
+            }
+        }
+
+        respond(infos)
+    }
+}
+
+fn respond(infos: Vec<CacheInfo>) -> HttpResponse {
+    let stale = infos.iter().any(|info| info.status == CacheStatus::Stale);
+
+    HttpResponse {
+        status: if stale { 503 } else { 200 },
+        content_type: "application/json",
+        body: render(&infos),
+    }
+}
+
+// The output of `dump` as a JSON array of objects with the fields of
+// `CacheInfo`
+pub fn to_json() -> String {
+    render(&dump())
+}
+
+fn render(infos: &[CacheInfo]) -> String {
+    let mut out = String::from("[");
+
+    for (i, info) in infos.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+
+        write_info(&mut out, info);
+    }
+
+    out.push(']');
+    out
+}
+
+fn write_info(out: &mut String, info: &CacheInfo) {
+    out.push_str("{\"name\":");
+    write_string(out, &info.name);
+    // Milliseconds since the Unix epoch
+    let last_updated = info
+        .last_updated
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis());
+
+    let _ = write!(
+        out,
+        ",\"generation\":{},\"slots\":{},\"status\":\"{}\",\"last_updated_ms\":{last_updated},\"age_ms\":{}",
+        info.generation,
+        info.slots,
+        info.status.as_str(),
+        info.age.as_millis()
+    );
+
+    #[cfg(feature = "stats")]
+    {
+        let stats = &info.stats;
+        let _ = write!(
+            out,
+            ",\"stats\":{{\"reads\":{},\"writes\":{},\"lock_spins\":{},\"scan_skips\":{},\"readers\":[",
+            stats.reads, stats.writes, stats.lock_spins, stats.scan_skips
+        );
+
+        for (i, slot) in stats.slots.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+
+            let _ = write!(out, "{}", slot.readers);
+        }
+
+        out.push_str("]}");
+    }
+
+    out.push_str(",\"value\":");
+    match &info.value {
+        Some(value) => write_string(out, value),
+        None => out.push_str("null"),
+    }
+
+    out.push('}');
+}
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::Cache;
+
+    use super::*;
+
+    #[test]
+    fn test_http_handler() {
+        let cache = Cache::<String>::named_debug("test_http_handler", String::from("a\"b"));
+        let handler = http_handler();
+
+        let response = handler();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type, "application/json");
+
+        let json: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        let info = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|info| info["name"] == "test_http_handler")
+            .unwrap();
+
+        assert_eq!(info["generation"], 0);
+        assert_eq!(info["slots"], 4);
+        assert_eq!(info["status"], "ok");
+        assert!(info["last_updated_ms"].as_u64().unwrap() > 0);
+        assert!(info["age_ms"].is_u64());
+        assert_eq!(info["value"], "\"a\\\"b\"");
+
+        let mut raw = Vec::new();
+        response.write_to(&mut raw).unwrap();
+        let raw = String::from_utf8(raw).unwrap();
+        assert!(raw.starts_with("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n"));
+        assert!(raw.ends_with(&response.body));
+
+        drop(cache);
+    }
+
+    #[test]
+    fn test_http_handler_stale_after() {
+        let cache = Cache::<u32>::named("test_http_handler_stale", 1);
+        let find = |response: &HttpResponse| {
+            let json: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+            json.as_array()
+                .unwrap()
+                .iter()
+                .find(|info| info["name"] == "test_http_handler_stale")
+                .unwrap()["status"]
+                .clone()
+        };
+
+        let response = http_handler_stale_after(Duration::from_secs(3_600))();
+        assert_eq!(find(&response), "ok");
+
+        std::thread::sleep(Duration::from_millis(2));
+        let response = http_handler_stale_after(Duration::from_millis(1))();
+        assert_eq!(response.status, 503);
+        assert_eq!(find(&response), "stale");
+
+        let mut raw = Vec::new();
+        response.write_to(&mut raw).unwrap();
+        assert!(raw.starts_with(b"HTTP/1.1 503 Service Unavailable\r\n"));
+
+        // Emptied caches aren't stale
+        cache.clear();
+        let response = http_handler_stale_after(Duration::from_millis(1))();
+        assert_eq!(find(&response), "empty");
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+#[cfg(feature = "stats")]
+use crate::cache::CacheStats;
+
+mod http;
+
+pub use http::{HttpResponse, http_handler, http_handler_stale_after, to_json};
+
+// Process-wide list of the caches created with `Cache::named`, for live
+// debugging. Caches are held weakly and leave the list once dropped.
+static CACHES: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+type Describe = Arc<dyn Fn() -> Option<CacheInfo> + Send + Sync>;
+
+struct Entry {
+    name: String,
+    describe: Describe,
+}
+
+// What a named cache is doing at the time of the dump
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    Ok,
+    // Emptied with `clear` or `take` and not updated since
+    Empty,
+    // A writer retired a slot and waits for its readers to leave
+    Draining,
+    // Not updated for longer than the handler allows, see
+    // `http_handler_stale_after`. `dump` never reports it.
+    Stale,
+}
+
+impl CacheStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Empty => "empty",
+            Self::Draining => "draining",
+            Self::Stale => "stale",
+        }
+    }
+}
+
+// A named cache as seen by `dump`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheInfo {
+    pub name: String,
+    pub generation: u64,
+    pub slots: usize,
+    pub status: CacheStatus,
+    // See `Cache::last_updated` and `Cache::age`
+    pub last_updated: SystemTime,
+    pub age: Duration,
+    #[cfg(feature = "stats")]
+    pub stats: CacheStats,
+    // `Debug` rendering of the current value, for caches created with
+    // `Cache::named_debug`. `None` for the others and for empty caches.
+    pub value: Option<String>,
+}
+
+// Every live named cache, in creation order. Several caches may share a
+// name.
+pub fn dump() -> Vec<CacheInfo> {
+    collect(|_| true)
+}
+
+// Like `dump`, but only the caches called `name`
+pub fn find(name: &str) -> Vec<CacheInfo> {
+    collect(|entry| entry == name)
+}
+
+// Describes the live caches whose name passes `filter`, forgetting the
+// dropped ones along the way. The entries are copied out first, so
+// describing a cache, and its `Debug` impl, run without the lock.
+fn collect(filter: impl Fn(&str) -> bool) -> Vec<CacheInfo> {
+    let describes: Vec<Describe> = CACHES
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .iter()
+        .filter(|entry| filter(&entry.name))
+        .map(|entry| entry.describe.clone())
+        .collect();
+
+    let mut infos = Vec::new();
+    let mut dropped = Vec::new();
+
+    for describe in describes {
+        match describe() {
+            Some(info) => infos.push(info),
+            None => dropped.push(describe),
+        }
+    }
+
+    if !dropped.is_empty() {
+        let mut caches = CACHES.lock().unwrap_or_else(|err| err.into_inner());
+
+        caches.retain(|entry| {
+            !dropped
+                .iter()
+                .any(|describe| Arc::ptr_eq(describe, &entry.describe))
+        });
+    }
+
+    infos
+}
+
+pub(crate) fn register(
+    name: &str,
+    describe: impl Fn() -> Option<CacheInfo> + Send + Sync + 'static,
+) {
+    let mut caches = CACHES.lock().unwrap_or_else(|err| err.into_inner());
+
+    caches.push(Entry {
+        name: name.to_owned(),
+        describe: Arc::new(describe),
+    });
+}
@@ -0,0 +1,176 @@
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+use crate::cache::{Cache, Policy};
+
+// `Cache` for large values that rarely change. Each reader thread keeps its
+// own `Replica`, a private copy plus the generation it was cloned at, so a
+// read is a single generation check as long as nothing was published. Only
+// the first read after an update clones from the shared cache.
+pub struct ReplicatedCache<T, const LEN: usize = 4>
+where
+    T: Clone,
+{
+    cache: Cache<T, LEN>,
+}
+
+impl<T: Clone, const LEN: usize> ReplicatedCache<T, LEN> {
+    pub fn new(data: T) -> Self {
+        Self::with_policy(data, Policy::default())
+    }
+
+    pub fn with_policy(data: T, policy: Policy) -> Self {
+        Self {
+            cache: Cache::with_policy(data, policy),
+        }
+    }
+
+    pub fn update(&self, data: T) {
+        self.cache.update(data);
+    }
+
+    pub fn get_data(&self) -> T {
+        self.cache.get_data()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.cache.generation()
+    }
+
+    // Local copy for the calling thread. It is filled on the first `get`.
+    pub fn replica(&self) -> Replica<'_, T, LEN> {
+        Replica {
+            cache: self,
+            local: None,
+            #[cfg(feature = "std")]
+            checked: None,
+        }
+    }
+}
+
+pub struct Replica<'a, T, const LEN: usize = 4>
+where
+    T: Clone,
+{
+    cache: &'a ReplicatedCache<T, LEN>,
+    local: Option<(T, u64)>,
+    // When `get_recent` last compared `local` against the shared cache
+    #[cfg(feature = "std")]
+    checked: Option<Instant>,
+}
+
+impl<T: Clone, const LEN: usize> Replica<'_, T, LEN> {
+    // The latest value, re-cloned from the shared cache only if it was
+    // updated since the last call
+    pub fn get(&mut self) -> &T {
+        if self.is_stale() {
+            self.local = self.cache.cache.try_get_with_generation();
+        }
+
+        let (data, _) = self
+            .local
+            .as_ref()
+            .expect("replicated caches are never cleared");
+
+        data
+    }
+
+    // Like `get`, but trusts the local copy without looking at the shared
+    // cache if it was checked less than `max_age` ago, so a read costs a
+    // clock read instead of a load of the shared generation. The copy may
+    // be up to `max_age` behind the latest update.
+    #[cfg(feature = "std")]
+    pub fn get_recent(&mut self, max_age: Duration) -> &T {
+        let now = Instant::now();
+        let recent = matches!(self.checked, Some(checked) if now - checked <= max_age);
+
+        if !recent || self.local.is_none() {
+            self.checked = Some(now);
+            return self.get();
+        }
+
+        match &self.local {
+            Some((data, _)) => data,
+            None => unreachable!("checked above"),
+        }
+    }
+
+    pub fn is_stale(&self) -> bool {
+        !matches!(&self.local, Some((_, local)) if *local == self.cache.generation())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, atomic::Ordering},
+        thread,
+    };
+
+    use super::*;
+    use crate::testing::{Counted, Counts};
+
+    #[test]
+    fn test_replica_clones_once_per_update() {
+        let clones = Arc::new(Counts::default());
+        let cache: ReplicatedCache<Counted> = ReplicatedCache::new(Counted(1, clones.clone()));
+        let mut replica = cache.replica();
+        assert!(replica.is_stale());
+
+        for _ in 0..100 {
+            assert_eq!(replica.get().0, 1);
+        }
+        assert_eq!(clones.clones.load(Ordering::Acquire), 1);
+        assert!(!replica.is_stale());
+
+        cache.update(Counted(2, clones.clone()));
+        assert!(replica.is_stale());
+
+        for _ in 0..100 {
+            assert_eq!(replica.get().0, 2);
+        }
+        assert_eq!(clones.clones.load(Ordering::Acquire), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_get_recent() {
+        let cache: ReplicatedCache<u64> = ReplicatedCache::new(1);
+        let mut replica = cache.replica();
+
+        assert_eq!(*replica.get_recent(Duration::from_secs(60)), 1);
+
+        // Within `max_age` the update goes unnoticed
+        cache.update(2);
+        assert_eq!(*replica.get_recent(Duration::from_secs(60)), 1);
+
+        cache.update(3);
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(*replica.get_recent(Duration::from_millis(1)), 3);
+        assert_eq!(*replica.get(), 3);
+    }
+
+    #[test]
+    fn test_replicas_follow_updates() {
+        let cache: ReplicatedCache<u64, 2> = ReplicatedCache::new(0);
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let mut replica = cache.replica();
+                    let mut last = 0;
+
+                    while last != 1_000 {
+                        let value = *replica.get();
+                        assert!(value >= last);
+                        last = value;
+                    }
+                });
+            }
+
+            for i in 1..=1_000 {
+                cache.update(i);
+            }
+        });
+    }
+}
@@ -0,0 +1,185 @@
+use core::fmt;
+
+use crate::cache::Cache;
+
+mod zeroize;
+
+pub use zeroize::Zeroize;
+
+// Value wiped with `Zeroize` when dropped. `Debug` never shows it.
+#[derive(Clone)]
+pub struct Secret<T: Zeroize> {
+    data: T,
+}
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(data: T) -> Self {
+        Self { data }
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.data
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(data: T) -> Self {
+        Self::new(data)
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.data.zeroize();
+    }
+}
+
+impl<T: Zeroize> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+// Cache for key material. Every value is wiped when the cache lets go of it,
+// and `update` drops the values retired to inactive slots straight away
+// instead of leaving them around until the ring wraps.
+//
+// A retired value a reader is still cloning survives until the next
+// `update` or `drop_stale`. The copies handed out by `get` are `Secret`s
+// too, so they are wiped when the caller drops them.
+//
+// `with_uniform_access` trades some speed for reads and writes that visit
+// every slot in a fixed order without early exits, so their latency leaks
+// less about which slots are current or pinned.
+pub struct SecretCache<T, const LEN: usize = 4>
+where
+    T: Zeroize + Clone,
+{
+    cache: Cache<Secret<T>, LEN>,
+    uniform: bool,
+}
+
+impl<T: Zeroize + Clone, const LEN: usize> SecretCache<T, LEN> {
+    pub fn new(data: T) -> Self {
+        Self {
+            cache: Cache::new(Secret::new(data)),
+            uniform: false,
+        }
+    }
+
+    pub fn with_uniform_access(data: T) -> Self {
+        Self {
+            uniform: true,
+            ..Self::new(data)
+        }
+    }
+
+    pub fn get(&self) -> Secret<T> {
+        self.try_get().expect("cache is empty")
+    }
+
+    pub fn try_get(&self) -> Option<Secret<T>> {
+        if self.uniform {
+            self.cache.try_get_uniform()
+        } else {
+            self.cache.try_get_data()
+        }
+    }
+
+    pub fn update(&self, data: T) {
+        if self.uniform {
+            self.cache.update_uniform(Secret::new(data));
+        } else {
+            self.cache.update(Secret::new(data));
+        }
+
+        self.cache.drop_stale();
+    }
+
+    // Wipes the values retired slots still hold, see `Cache::drop_stale`
+    pub fn drop_stale(&self) {
+        self.cache.drop_stale();
+    }
+
+    // Wipes every value, leaving the cache empty
+    pub fn clear(&self) {
+        self.cache.clear();
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.cache.generation()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct Key {
+        bytes: Vec<u8>,
+        wiped: Arc<AtomicUsize>,
+    }
+
+    impl Zeroize for Key {
+        fn zeroize(&mut self) {
+            self.bytes.zeroize();
+            self.wiped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_zeroize() {
+        let mut key = String::from("hunter2");
+        key.zeroize();
+        assert!(key.is_empty());
+
+        let mut words = [1u32, 2, 3];
+        words.zeroize();
+        assert_eq!(words, [0; 3]);
+
+        assert_eq!(format!("{:?}", Secret::new(7u64)), "Secret(..)");
+    }
+
+    #[test]
+    fn test_secret_cache() {
+        let wiped = Arc::new(AtomicUsize::new(0));
+        let key = |byte| Key {
+            bytes: vec![byte; 16],
+            wiped: wiped.clone(),
+        };
+
+        let cache: SecretCache<Key> = SecretCache::new(key(1));
+
+        let copy = cache.get();
+        assert_eq!(copy.expose().bytes, vec![1; 16]);
+        drop(copy);
+        assert_eq!(wiped.load(Ordering::Relaxed), 1);
+
+        // The retired key is wiped by the update, not when its slot is reused
+        cache.update(key(2));
+        assert_eq!(wiped.load(Ordering::Relaxed), 2);
+        assert_eq!(cache.get().expose().bytes, vec![2; 16]);
+
+        cache.clear();
+        assert!(cache.try_get().is_none());
+        assert_eq!(wiped.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn test_uniform_secret_cache() {
+        let cache: SecretCache<[u8; 32]> = SecretCache::with_uniform_access([1; 32]);
+
+        for byte in 2..10 {
+            cache.update([byte; 32]);
+            assert_eq!(cache.get().expose(), &[byte; 32]);
+        }
+
+        assert_eq!(cache.generation(), 8);
+    }
+}
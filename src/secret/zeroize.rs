@@ -0,0 +1,69 @@
+use alloc::{string::String, vec::Vec};
+use core::{
+    ptr,
+    sync::atomic::{self, Ordering},
+};
+
+// Overwrites a value with zeroes in a way the compiler can't optimize out,
+// for key material that must not outlive its last use.
+//
+// Only the memory the value owns right now is wiped. Copies left behind by
+// moves or by a `Vec` reallocating are out of reach.
+pub trait Zeroize {
+    fn zeroize(&mut self);
+}
+
+// Volatile so the store survives even though the value is never read again
+fn wipe<T: Copy>(place: &mut T, zero: T) {
+    // Safety: `place` is a valid, exclusive reference
+    unsafe { ptr::write_volatile(place, zero) };
+    atomic::compiler_fence(Ordering::SeqCst);
+}
+
+macro_rules! impl_zeroize {
+    ($($ty:ty => $zero:expr),* $(,)?) => {
+        $(
+            impl Zeroize for $ty {
+                fn zeroize(&mut self) {
+                    wipe(self, $zero);
+                }
+            }
+        )*
+    };
+}
+
+impl_zeroize! {
+    u8 => 0, u16 => 0, u32 => 0, u64 => 0, u128 => 0, usize => 0,
+    i8 => 0, i16 => 0, i32 => 0, i64 => 0, i128 => 0, isize => 0,
+    bool => false,
+    char => '\0',
+}
+
+impl<T: Zeroize, const N: usize> Zeroize for [T; N] {
+    fn zeroize(&mut self) {
+        self.iter_mut().for_each(Zeroize::zeroize);
+    }
+}
+
+impl<T: Zeroize> Zeroize for Option<T> {
+    fn zeroize(&mut self) {
+        if let Some(data) = self {
+            data.zeroize();
+        }
+    }
+}
+
+// Wipes the elements, then empties the vector
+impl<T: Zeroize> Zeroize for Vec<T> {
+    fn zeroize(&mut self) {
+        self.iter_mut().for_each(Zeroize::zeroize);
+        self.clear();
+    }
+}
+
+impl Zeroize for String {
+    fn zeroize(&mut self) {
+        // Safety: zero bytes are valid UTF-8, and the string is emptied
+        unsafe { self.as_mut_vec() }.zeroize();
+    }
+}
@@ -0,0 +1,295 @@
+use core::{
+    cell::UnsafeCell,
+    mem::{self, MaybeUninit},
+    sync::atomic::AtomicUsize as AtomicWord,
+};
+
+use crate::{
+    sync::atomic::{self, AtomicUsize, Ordering},
+    utils::{Backoff, Padded},
+};
+
+// Types without padding or other uninitialized bytes, which `SeqCache` can
+// copy as whole words. Implemented for primitives, references and arrays.
+// For a struct of such fields, make it `#[repr(C)]` with its fields laid
+// out so none needs padding, or add explicit padding fields.
+//
+// Safety: every byte of every value of the type must be initialized
+#[allow(clippy::missing_safety_doc)]
+pub unsafe trait NoPadding: Copy {}
+
+macro_rules! no_padding {
+    ($($ty:ty),*) => {
+        $(
+            // Safety: primitives have no padding
+            unsafe impl NoPadding for $ty {}
+        )*
+    };
+}
+
+no_padding!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64
+);
+
+// Safety: a pointer, possibly with its metadata, is all initialized
+unsafe impl<T: ?Sized> NoPadding for &T {}
+unsafe impl<T: ?Sized> NoPadding for *const T {}
+unsafe impl<T: ?Sized> NoPadding for *mut T {}
+
+// Safety: array elements follow each other without padding
+unsafe impl<T: NoPadding, const N: usize> NoPadding for [T; N] {}
+
+// The value as whole words, so readers and writers can copy it with atomic
+// loads and stores instead of racing on plain memory. The empty array
+// aligns it to a word, which also rounds its size up to whole words.
+#[repr(C)]
+#[derive(Clone, Copy)]
+union Words<T: NoPadding> {
+    data: MaybeUninit<T>,
+    _align: [usize; 0],
+}
+
+impl<T: NoPadding> Words<T> {
+    const LEN: usize = mem::size_of::<Self>() / mem::size_of::<usize>();
+
+    // Zeroed first so the bytes past `data` are initialized too, `data`
+    // itself has none uninitialized
+    fn new(data: T) -> Self {
+        let mut words = Self { _align: [] };
+
+        // Safety: a zeroed `MaybeUninit` is valid, and `data` is written
+        // over it as a whole
+        unsafe {
+            core::ptr::write_bytes(&mut words, 0, 1);
+            words.data.as_mut_ptr().write(data);
+        }
+
+        words
+    }
+}
+
+// Sequence-lock based cache for small `Copy` values. Readers never write to
+// shared memory: they copy the value out and retry if a writer was active
+// meanwhile, which makes reads far cheaper than `Cache`'s slot pinning for
+// timestamps, prices and other plain data.
+//
+// The value is copied word by word with relaxed atomics, and a copy only
+// becomes a `T` once the sequence check showed it isn't torn. Words can't
+// hold uninitialized bytes, hence the `NoPadding` bound.
+pub struct SeqCache<T: NoPadding> {
+    // Odd while a writer is copying a new value in
+    seq: Padded<AtomicUsize>,
+    data: UnsafeCell<Words<T>>,
+}
+
+// Safety:
+// - Writers serialize on `seq` and only write `data` while it is odd
+// - `data` is only ever accessed through atomic words
+// - Readers only turn their copy into a `T` if `seq` was even and
+//   unchanged around the copy, so torn copies are discarded
+unsafe impl<T: NoPadding + Send> Sync for SeqCache<T> {}
+
+impl<T: NoPadding> SeqCache<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            seq: Padded::new(AtomicUsize::new(0)),
+            data: UnsafeCell::new(Words::new(data)),
+        }
+    }
+
+    // Word `index` of the shared copy
+    fn word(&self, index: usize) -> &AtomicWord {
+        debug_assert!(index < Words::<T>::LEN);
+
+        // Safety: `Words` is word-aligned and `LEN` words long, and outside
+        // of `new` its memory is only accessed through atomics
+        unsafe { AtomicWord::from_ptr(self.data.get().cast::<usize>().add(index)) }
+    }
+
+    pub fn get_data(&self) -> T {
+        let backoff = Backoff::new();
+
+        loop {
+            let before = self.seq.load(Ordering::Acquire);
+
+            if before & 1 == 0 {
+                let mut copy = MaybeUninit::<Words<T>>::uninit();
+                let words = copy.as_mut_ptr().cast::<usize>();
+
+                for index in 0..Words::<T>::LEN {
+                    // Safety: `copy` is `LEN` words long
+                    unsafe {
+                        words
+                            .add(index)
+                            .write(self.word(index).load(Ordering::Relaxed))
+                    };
+                }
+
+                atomic::fence(Ordering::Acquire);
+
+                if self.seq.load(Ordering::Relaxed) == before {
+                    // Safety: no writer ran during the copy, so it holds
+                    // every word of a valid value
+                    return unsafe { copy.assume_init().data.assume_init() };
+                }
+            }
+
+            backoff.spin();
+        }
+    }
+
+    pub fn update(&self, data: T) {
+        let backoff = Backoff::new();
+        let words = Words::new(data);
+
+        let seq = loop {
+            let seq = self.seq.load(Ordering::Relaxed);
+
+            if seq & 1 == 0
+                && self
+                    .seq
+                    .compare_exchange_weak(seq, seq + 1, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                break seq;
+            }
+
+            backoff.spin();
+        };
+
+        // Keeps the stores below from being reordered before the odd `seq`
+        atomic::fence(Ordering::Release);
+
+        let source = (&raw const words).cast::<usize>();
+
+        for index in 0..Words::<T>::LEN {
+            // Safety: `words` is `LEN` words long
+            self.word(index)
+                .store(unsafe { source.add(index).read() }, Ordering::Relaxed);
+        }
+
+        self.seq.store(seq.wrapping_add(2), Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seq_cache() {
+        let cache = SeqCache::new([0_u64, 0]);
+        assert_eq!(cache.get_data(), [0, 0]);
+
+        cache.update([1, 1]);
+        assert_eq!(cache.get_data(), [1, 1]);
+
+        // Both halves are always written together, so a torn read would
+        // show them apart
+        std::thread::scope(|s| {
+            for _ in 0..2 {
+                s.spawn(|| {
+                    for i in 2..10_000 {
+                        cache.update([i, i]);
+                    }
+                });
+            }
+
+            for _ in 0..2 {
+                s.spawn(|| {
+                    for _ in 0..10_000 {
+                        let [a, b] = cache.get_data();
+                        assert_eq!(a, b);
+                    }
+                });
+            }
+        });
+    }
+
+    // Small enough for `make test-miri`, which checks that readers racing a
+    // writer never touch the value non-atomically. A torn copy of this one
+    // would be an invalid value, not just a wrong one.
+    #[test]
+    fn test_seq_cache_concurrent_writer() {
+        #[derive(Clone, Copy, PartialEq, Debug)]
+        #[repr(u32)]
+        enum Side {
+            Bid(u32),
+            Ask(u32),
+        }
+
+        #[derive(Clone, Copy, PartialEq, Debug)]
+        #[repr(C)]
+        struct Quote {
+            side: Side,
+            price: &'static u64,
+        }
+
+        // Safety: a `u32` tag and a `u32` field, then a word-aligned pointer
+        unsafe impl NoPadding for Quote {}
+
+        static BID: u64 = 1;
+        static ASK: u64 = 2;
+
+        let cache = SeqCache::new(Quote {
+            side: Side::Bid(0),
+            price: &BID,
+        });
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for i in 1..100_u32 {
+                    let quote = if i % 2 == 0 {
+                        Quote {
+                            side: Side::Bid(i),
+                            price: &BID,
+                        }
+                    } else {
+                        Quote {
+                            side: Side::Ask(i),
+                            price: &ASK,
+                        }
+                    };
+                    cache.update(quote);
+                }
+            });
+
+            for _ in 0..100 {
+                match cache.get_data() {
+                    Quote {
+                        side: Side::Bid(_),
+                        price,
+                    } => assert_eq!(*price, BID),
+                    Quote {
+                        side: Side::Ask(_),
+                        price,
+                    } => assert_eq!(*price, ASK),
+                }
+            }
+        });
+
+        assert_eq!(
+            cache.get_data(),
+            Quote {
+                side: Side::Ask(99),
+                price: &ASK
+            }
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use core::{
+    cell::UnsafeCell,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ptr::{self, NonNull},
+    sync::atomic::{self, AtomicBool, AtomicU64, Ordering},
+};
+use std::{ffi::CString, io, thread};
+
+use crate::utils::Backoff;
+
+// Cache living in a POSIX shared memory object, so a writer process can
+// publish values that reader processes pick up without a socket in between.
+// Every process maps the same ring of `LEN` slots; each slot is a sequence
+// lock like `SeqCache`. Readers copy the current slot and only retry if
+// writers lapped the whole ring while they were copying.
+//
+// Values are copied byte for byte between processes, so `T` must be plain
+// data: no pointers, references or handles, which would be meaningless in
+// the other process.
+pub struct ShmCache<T: Copy, const LEN: usize = 4> {
+    region: NonNull<Region<T, LEN>>,
+    _marker: PhantomData<T>,
+}
+
+// Layout of the shared object. `magic` is stored last when creating it, so
+// an opener never sees a half-initialized region.
+#[repr(C)]
+struct Region<T, const LEN: usize> {
+    magic: AtomicU64,
+    size: u64,
+    align: u64,
+    len: u64,
+    // Serializes writers, from any process
+    writing: AtomicBool,
+    // Generation of the current value above the slot it lives in
+    index: AtomicU64,
+    slots: [Slot<T>; LEN],
+}
+
+#[repr(C)]
+struct Slot<T> {
+    // Odd while a writer is copying a new value in
+    seq: AtomicU64,
+    data: UnsafeCell<MaybeUninit<T>>,
+}
+
+const MAGIC: u64 = u64::from_le_bytes(*b"slothshm");
+
+// Safety: the region is only accessed through atomics and the sequence
+// locks, and `T: Copy` values carry no ownership across threads
+unsafe impl<T: Copy + Send, const LEN: usize> Send for ShmCache<T, LEN> {}
+unsafe impl<T: Copy + Send, const LEN: usize> Sync for ShmCache<T, LEN> {}
+
+impl<T: Copy, const LEN: usize> ShmCache<T, LEN> {
+    const CHECK_LEN_IS_POWER_OF_TWO: () = assert!(LEN.is_power_of_two());
+    const SLOT_BITS: u32 = LEN.trailing_zeros();
+
+    // Creates the shared memory object `name` (such as "/routes") holding
+    // `data`. Fails if it already exists; a writer restarting after a crash
+    // should `unlink` the old one first.
+    pub fn create(name: &str, data: T) -> io::Result<Self> {
+        let () = Self::CHECK_LEN_IS_POWER_OF_TWO;
+
+        let cache = Self::map(name, libc::O_CREAT | libc::O_EXCL)?;
+        let region = cache.region.as_ptr();
+
+        // Safety: nobody can open the region before `magic` is set, so the
+        // plain fields are ours to write
+        unsafe {
+            (*region).size = mem::size_of::<T>() as u64;
+            (*region).align = mem::align_of::<T>() as u64;
+            (*region).len = LEN as u64;
+            (*(*region).slots[0].data.get()).write(data);
+        }
+
+        cache.region().magic.store(MAGIC, Ordering::Release);
+
+        Ok(cache)
+    }
+
+    // Opens a shared memory object created by `ShmCache::create`.
+    //
+    // Safety: the creator must have used the same `T`. Size, alignment and
+    // `LEN` are checked, but two types with the same size can't be told
+    // apart.
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn open(name: &str) -> io::Result<Self> {
+        let cache = Self::map(name, 0)?;
+        let region = cache.region();
+
+        if region.magic.load(Ordering::Acquire) != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a sloth shared memory cache, or still being created",
+            ));
+        }
+
+        if region.size != mem::size_of::<T>() as u64
+            || region.align != mem::align_of::<T>() as u64
+            || region.len != LEN as u64
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "shared memory cache was created for a different value type or length",
+            ));
+        }
+
+        Ok(cache)
+    }
+
+    // Removes the shared memory object. Processes that have it mapped keep
+    // using it.
+    pub fn unlink(name: &str) -> io::Result<()> {
+        let name = c_name(name)?;
+
+        // Safety: `name` is a valid C string
+        if unsafe { libc::shm_unlink(name.as_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    fn map(name: &str, flags: libc::c_int) -> io::Result<Self> {
+        let name = c_name(name)?;
+        let size = mem::size_of::<Region<T, LEN>>();
+
+        // Safety: plain libc calls on a valid C string and the fd they return
+        unsafe {
+            let fd = libc::shm_open(name.as_ptr(), flags | libc::O_RDWR, 0o600);
+
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            // A fresh object is grown to size and zeroed; an existing one
+            // already has it, unless it was created by something else
+            if flags & libc::O_CREAT != 0 && libc::ftruncate(fd, size as libc::off_t) != 0 {
+                let err = io::Error::last_os_error();
+                libc::close(fd);
+                libc::shm_unlink(name.as_ptr());
+                return Err(err);
+            }
+
+            let mut stat = mem::zeroed::<libc::stat>();
+
+            if libc::fstat(fd, &mut stat) != 0 {
+                let err = io::Error::last_os_error();
+                libc::close(fd);
+                return Err(err);
+            }
+
+            if (stat.st_size as usize) < size {
+                libc::close(fd);
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "shared memory object is too small",
+                ));
+            }
+
+            let addr = libc::mmap(
+                ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            );
+
+            // The mapping keeps the object alive on its own
+            libc::close(fd);
+
+            if addr == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self {
+                region: NonNull::new_unchecked(addr.cast()),
+                _marker: PhantomData,
+            })
+        }
+    }
+
+    fn region(&self) -> &Region<T, LEN> {
+        // Safety: mapped until `drop`
+        unsafe { self.region.as_ref() }
+    }
+
+    pub fn get_data(&self) -> T {
+        let region = self.region();
+        let backoff = Backoff::new();
+
+        loop {
+            let stamp = region.index.load(Ordering::Acquire);
+            let slot = &region.slots[stamp as usize & (LEN - 1)];
+            let before = slot.seq.load(Ordering::Acquire);
+
+            if before & 1 == 0 {
+                // Volatile so the compiler can't assume the value is stable
+                // across the sequence checks. Kept uninit until then, as a
+                // torn copy may not be a valid `T`.
+                let data = unsafe { ptr::read_volatile(slot.data.get()) };
+
+                atomic::fence(Ordering::Acquire);
+
+                if slot.seq.load(Ordering::Relaxed) == before {
+                    // Safety: no writer ran during the copy, and every slot
+                    // the index points at has been written
+                    return unsafe { data.assume_init() };
+                }
+            }
+
+            backoff.spin();
+        }
+    }
+
+    pub fn update(&self, data: T) {
+        let region = self.region();
+        let backoff = Backoff::new();
+
+        while region
+            .writing
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            // The other writer may be a descheduled process
+            if backoff.is_completed() {
+                thread::yield_now();
+            } else {
+                backoff.snooze();
+            }
+        }
+
+        let stamp = region.index.load(Ordering::Relaxed);
+        let next = (stamp as usize + 1) & (LEN - 1);
+        let generation = (stamp >> Self::SLOT_BITS) + 1;
+        let slot = &region.slots[next];
+
+        let seq = slot.seq.load(Ordering::Relaxed);
+        slot.seq.store(seq + 1, Ordering::Relaxed);
+
+        // Keeps the write below from being reordered before the odd `seq`
+        atomic::fence(Ordering::Release);
+
+        unsafe { ptr::write_volatile(slot.data.get(), MaybeUninit::new(data)) };
+
+        slot.seq.store(seq + 2, Ordering::Release);
+        region.index.store(
+            (generation << Self::SLOT_BITS) | next as u64,
+            Ordering::Release,
+        );
+
+        region.writing.store(false, Ordering::Release);
+    }
+
+    // Bumped by every `update`, from any process
+    pub fn generation(&self) -> u64 {
+        self.region().index.load(Ordering::Acquire) >> Self::SLOT_BITS
+    }
+}
+
+impl<T: Copy, const LEN: usize> Drop for ShmCache<T, LEN> {
+    fn drop(&mut self) {
+        // Safety: mapped by `map` with this size
+        unsafe {
+            libc::munmap(
+                self.region.as_ptr().cast(),
+                mem::size_of::<Region<T, LEN>>(),
+            );
+        }
+    }
+}
+
+fn c_name(name: &str) -> io::Result<CString> {
+    CString::new(name).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shm_cache() {
+        let name = format!("/sloth-test-{}", std::process::id());
+        let _ = ShmCache::<(u64, u64)>::unlink(&name);
+
+        let writer: ShmCache<(u64, u64)> = ShmCache::create(&name, (0, 0)).unwrap();
+        assert_eq!(
+            ShmCache::<(u64, u64)>::create(&name, (0, 0))
+                .err()
+                .unwrap()
+                .kind(),
+            io::ErrorKind::AlreadyExists
+        );
+
+        // A second mapping stands in for another process
+        let reader: ShmCache<(u64, u64)> = unsafe { ShmCache::open(&name) }.unwrap();
+        assert!(unsafe { ShmCache::<u32>::open(&name) }.is_err());
+        assert!(unsafe { ShmCache::<(u64, u64), 8>::open(&name) }.is_err());
+
+        writer.update((1, 1));
+        assert_eq!(reader.get_data(), (1, 1));
+        assert_eq!(reader.generation(), 1);
+
+        // Both halves are always written together, so a torn read would
+        // show them apart
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 2..10_000 {
+                    writer.update((i, i));
+                }
+            });
+
+            for _ in 0..10_000 {
+                let (a, b) = reader.get_data();
+                assert_eq!(a, b);
+            }
+        });
+
+        assert_eq!(reader.get_data(), (9_999, 9_999));
+
+        ShmCache::<(u64, u64)>::unlink(&name).unwrap();
+        assert_eq!(reader.generation(), 9_999);
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+};
+
+// Deduplicates concurrent calls per key: while a call for a key is running,
+// other callers for the same key wait for it and get a clone of its result
+// instead of running their own. Results are not kept once the call is done,
+// see `MemoCache` for that.
+pub struct Group<K, V> {
+    flights: Mutex<HashMap<K, Arc<Flight<V>>>>,
+}
+
+enum State<V> {
+    Running,
+    Done(V),
+    // The call panicked, waiters retry
+    Failed,
+}
+
+struct Flight<V> {
+    state: Mutex<State<V>>,
+    finished: Condvar,
+}
+
+impl<V: Clone> Flight<V> {
+    fn state(&self) -> MutexGuard<'_, State<V>> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn finish(&self, state: State<V>) {
+        *self.state() = state;
+        self.finished.notify_all();
+    }
+
+    // `None` if the call failed
+    fn wait(&self) -> Option<V> {
+        let mut state = self.state();
+
+        loop {
+            match &*state {
+                State::Running => {}
+                State::Done(value) => return Some(value.clone()),
+                State::Failed => return None,
+            }
+
+            state = self
+                .finished
+                .wait(state)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+    }
+}
+
+impl<K, V> Group<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            flights: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Runs `f`, unless a call for `key` is already running, in which case
+    // its result is returned instead. If the running call panics, the panic
+    // propagates to its caller and one of the waiting callers runs its own
+    // `f`.
+    pub fn do_call(&self, key: K, f: impl FnOnce() -> V) -> V {
+        loop {
+            let flights = self.flights();
+
+            if let Some(flight) = flights.get(&key) {
+                let flight = flight.clone();
+                drop(flights);
+
+                match flight.wait() {
+                    Some(value) => return value,
+                    None => continue,
+                }
+            }
+
+            return self.lead(flights, key, f);
+        }
+    }
+
+    // Like `do_call`, but returns `None` instead of waiting if a call for
+    // `key` is already running
+    pub fn try_call(&self, key: K, f: impl FnOnce() -> V) -> Option<V> {
+        let flights = self.flights();
+
+        if flights.contains_key(&key) {
+            return None;
+        }
+
+        Some(self.lead(flights, key, f))
+    }
+
+    // Registers a flight for `key`, which must not have one, and runs `f`
+    fn lead(
+        &self,
+        mut flights: MutexGuard<'_, HashMap<K, Arc<Flight<V>>>>,
+        key: K,
+        f: impl FnOnce() -> V,
+    ) -> V {
+        let flight = Arc::new(Flight {
+            state: Mutex::new(State::Running),
+            finished: Condvar::new(),
+        });
+        flights.insert(key.clone(), flight.clone());
+        drop(flights);
+
+        let mut landing = Landing {
+            group: self,
+            key,
+            flight,
+            value: None,
+        };
+
+        let value = f();
+        landing.value = Some(value.clone());
+
+        value
+    }
+
+    // Number of calls currently running
+    pub fn in_flight(&self) -> usize {
+        self.flights().len()
+    }
+
+    fn flights(&self) -> MutexGuard<'_, HashMap<K, Arc<Flight<V>>>> {
+        self.flights.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<K, V> Default for Group<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Ends the leader's flight, also when its call unwinds
+struct Landing<'a, K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    group: &'a Group<K, V>,
+    key: K,
+    flight: Arc<Flight<V>>,
+    value: Option<V>,
+}
+
+impl<K, V> Drop for Landing<'_, K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn drop(&mut self) {
+        self.group.flights().remove(&self.key);
+
+        self.flight.finish(match self.value.take() {
+            Some(value) => State::Done(value),
+            None => State::Failed,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_do_call() {
+        let group: Group<&str, u64> = Group::new();
+        let calls = AtomicUsize::new(0);
+
+        thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    let value = group.do_call("key", || {
+                        calls.fetch_add(1, Ordering::AcqRel);
+                        thread::sleep(Duration::from_millis(20));
+                        7
+                    });
+                    assert_eq!(value, 7);
+                });
+            }
+        });
+
+        // Callers that arrive after a call finished run their own, so only
+        // bound the count
+        assert!(calls.load(Ordering::Acquire) < 8);
+        assert_eq!(group.in_flight(), 0);
+
+        // Nothing is remembered once the call is done
+        assert_eq!(group.do_call("key", || 8), 8);
+    }
+
+    #[test]
+    fn test_panicking_call() {
+        let group: Group<u64, u64> = Group::new();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            group.do_call(1, || panic!("call panicked"))
+        }));
+        assert!(result.is_err());
+        assert_eq!(group.in_flight(), 0);
+
+        assert_eq!(group.do_call(1, || 2), 2);
+    }
+
+    #[test]
+    fn test_try_call() {
+        let group: Group<u64, u64> = Group::new();
+
+        let value = group.try_call(1, || {
+            // The call for 1 is running, 2 is free
+            assert_eq!(group.try_call(1, || 3), None);
+            assert_eq!(group.try_call(2, || 4), Some(4));
+            5
+        });
+
+        assert_eq!(value, Some(5));
+        assert_eq!(group.in_flight(), 0);
+    }
+}
@@ -0,0 +1,195 @@
+use alloc::{sync::Arc, vec::Vec};
+
+use crate::cache::Cache;
+
+// Values per chunk. Appending copies at most the last chunk, and the list
+// of chunk pointers.
+const CHUNK: usize = 32;
+
+// Immutable view of a `SnapVec` at some point in time
+pub struct Snapshot<T> {
+    chunks: Vec<Arc<Vec<T>>>,
+    len: usize,
+}
+
+impl<T> Snapshot<T> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.chunks.get(index / CHUNK)?.get(index % CHUNK)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.chunks.iter().flat_map(|chunk| chunk.iter())
+    }
+}
+
+impl<T: Clone> Snapshot<T> {
+    fn from_vec(values: Vec<T>) -> Self {
+        Self {
+            len: values.len(),
+            chunks: values
+                .chunks(CHUNK)
+                .map(|chunk| Arc::new(chunk.to_vec()))
+                .collect(),
+        }
+    }
+
+    fn extended(&self, values: impl IntoIterator<Item = T>) -> Self {
+        let mut chunks = self.chunks.clone();
+        let mut len = self.len;
+
+        for value in values {
+            // Copies the last chunk on the first push only, it is unique
+            // to this version afterwards
+            match chunks.last_mut() {
+                Some(last) if last.len() < CHUNK => Arc::make_mut(last).push(value),
+                _ => chunks.push(Arc::new(Vec::from([value]))),
+            }
+
+            len += 1;
+        }
+
+        Self { chunks, len }
+    }
+}
+
+// Append-mostly vector. Readers take a `Snapshot` and iterate it without
+// locking while writers publish new versions through a `Cache`. Appends
+// share every full chunk with the previous version; removals rebuild the
+// whole list, which is fine for membership-style changes.
+pub struct SnapVec<T>
+where
+    T: Clone,
+{
+    snapshot: Cache<Arc<Snapshot<T>>>,
+}
+
+impl<T: Clone> SnapVec<T> {
+    pub fn new() -> Self {
+        Self::from_vec(Vec::new())
+    }
+
+    pub fn from_vec(values: Vec<T>) -> Self {
+        Self {
+            snapshot: Cache::new(Arc::new(Snapshot::from_vec(values))),
+        }
+    }
+
+    pub fn snapshot(&self) -> Arc<Snapshot<T>> {
+        self.snapshot.get_data()
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshot().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshot().is_empty()
+    }
+
+    pub fn push(&self, value: T) {
+        self.extend([value]);
+    }
+
+    pub fn extend(&self, values: impl IntoIterator<Item = T>) {
+        self.modify(|snapshot| snapshot.extended(values));
+    }
+
+    // Keeps the values `f` returns `true` for, in order
+    pub fn retain(&self, mut f: impl FnMut(&T) -> bool) {
+        self.modify(|snapshot| {
+            Snapshot::from_vec(snapshot.iter().filter(|value| f(value)).cloned().collect())
+        });
+    }
+
+    pub fn replace_all(&self, values: Vec<T>) {
+        self.snapshot.update(Arc::new(Snapshot::from_vec(values)));
+    }
+
+    fn modify(&self, f: impl FnOnce(&Snapshot<T>) -> Snapshot<T>) {
+        self.snapshot.update_with(|current| {
+            let current = current.expect("snapshot vectors are never cleared");
+            Arc::new(f(current))
+        });
+    }
+}
+
+impl<T: Clone> Default for SnapVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_snap_vec() {
+        let backends = SnapVec::from_vec(vec!["a", "b"]);
+        let before = backends.snapshot();
+
+        backends.push("c");
+        backends.extend(["d", "e"]);
+        assert_eq!(backends.len(), 5);
+        assert_eq!(before.iter().copied().collect::<Vec<_>>(), ["a", "b"]);
+
+        backends.retain(|backend| *backend != "b");
+        let snapshot = backends.snapshot();
+        assert_eq!(
+            snapshot.iter().copied().collect::<Vec<_>>(),
+            ["a", "c", "d", "e"]
+        );
+        assert_eq!(snapshot.get(1), Some(&"c"));
+        assert_eq!(snapshot.get(4), None);
+
+        backends.replace_all(Vec::new());
+        assert!(backends.is_empty());
+    }
+
+    #[test]
+    fn test_appends_share_full_chunks() {
+        let values = SnapVec::from_vec((0..CHUNK as u64 * 2).collect());
+        let before = values.snapshot();
+
+        values.push(64);
+        let after = values.snapshot();
+
+        assert!(Arc::ptr_eq(&before.chunks[0], &after.chunks[0]));
+        assert!(Arc::ptr_eq(&before.chunks[1], &after.chunks[1]));
+        assert_eq!(after.get(64), Some(&64));
+    }
+
+    #[test]
+    fn test_concurrent_pushes() {
+        let values = SnapVec::new();
+
+        thread::scope(|s| {
+            for t in 0..4 {
+                let values = &values;
+
+                s.spawn(move || {
+                    for i in 0..100 {
+                        values.push(t * 100 + i);
+
+                        let snapshot = values.snapshot();
+                        assert_eq!(snapshot.iter().count(), snapshot.len());
+                    }
+                });
+            }
+        });
+
+        let mut all: Vec<u64> = values.snapshot().iter().copied().collect();
+        all.sort_unstable();
+        assert_eq!(all, (0..400).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,33 @@
+use std::{error::Error, fmt};
+
+pub(crate) type BoxError = Box<dyn Error + Send + Sync>;
+
+// Why a source could not publish a new value. The cache keeps its current
+// value in every case.
+#[derive(Debug)]
+pub enum SourceError {
+    Fetch(BoxError),
+    Decode(BoxError),
+    // Refused by the cache's validator, see `Cache::set_validator`
+    Rejected(BoxError),
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceError::Fetch(err) => write!(f, "failed to fetch value: {err}"),
+            SourceError::Decode(err) => write!(f, "failed to decode value: {err}"),
+            SourceError::Rejected(err) => write!(f, "value rejected by the validator: {err}"),
+        }
+    }
+}
+
+impl Error for SourceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SourceError::Fetch(err) | SourceError::Decode(err) | SourceError::Rejected(err) => {
+                Some(&**err)
+            }
+        }
+    }
+}
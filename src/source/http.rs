@@ -0,0 +1,213 @@
+use std::{
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use super::Response;
+
+// Minimal plain-text HTTP GET for `Remote::http`. Speaks HTTP/1.0 with one
+// connection per request and no TLS, which is enough for a control plane
+// reached through a local proxy or sidecar. Use `Remote::new` with a real
+// client for anything else.
+#[derive(Debug, Clone)]
+pub struct HttpGet {
+    host: String,
+    port: u16,
+    path: String,
+    timeout: Duration,
+}
+
+impl HttpGet {
+    // Accepts `http://host[:port][/path]`
+    pub fn new(url: &str) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("invalid url {url}"));
+
+        let rest = url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, "/"),
+        };
+
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid())?),
+            None => (authority, 80),
+        };
+
+        if host.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            host: host.to_owned(),
+            port,
+            path: path.to_owned(),
+            timeout: Duration::from_secs(10),
+        })
+    }
+
+    // Applies to connecting and to each read and write. Defaults to 10s.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn fetch(&self, etag: Option<&str>) -> io::Result<Response> {
+        let address = (self.host.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host did not resolve"))?;
+
+        let mut stream = TcpStream::connect_timeout(&address, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        let mut request = format!("GET {} HTTP/1.0\r\nHost: {}\r\n", self.path, self.host);
+
+        if let Some(etag) = etag {
+            request.push_str(&format!("If-None-Match: {etag}\r\n"));
+        }
+
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes())?;
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw)?;
+
+        parse(&raw)
+    }
+}
+
+fn parse(raw: &[u8]) -> io::Result<Response> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed HTTP response");
+
+    let end = raw
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(malformed)?;
+    let head = std::str::from_utf8(&raw[..end]).map_err(|_| malformed())?;
+    let body = &raw[end + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status: u16 = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(malformed)?;
+
+    let headers: Vec<(&str, &str)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect();
+    let header = |wanted: &str| {
+        headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(wanted))
+            .map(|&(_, value)| value)
+    };
+
+    match status {
+        304 => Ok(Response::NotModified),
+        200..=299 => {
+            let body = match header("Transfer-Encoding") {
+                Some(encoding) if encoding.eq_ignore_ascii_case("chunked") => dechunk(body)?,
+                _ => body.to_vec(),
+            };
+
+            Ok(Response::Body {
+                body,
+                etag: header("ETag").map(str::to_owned),
+            })
+        }
+        status => Err(io::Error::other(format!("unexpected HTTP status {status}"))),
+    }
+}
+
+// For servers that answer HTTP/1.0 requests with a chunked body anyway
+fn dechunk(mut raw: &[u8]) -> io::Result<Vec<u8>> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed chunked body");
+    let mut body = Vec::new();
+
+    loop {
+        let line = raw
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .ok_or_else(malformed)?;
+        let size = std::str::from_utf8(&raw[..line]).map_err(|_| malformed())?;
+        let size = size.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| malformed())?;
+
+        raw = &raw[line + 2..];
+
+        if size == 0 {
+            return Ok(body);
+        }
+
+        body.extend_from_slice(raw.get(..size).ok_or_else(malformed)?);
+        raw = raw.get(size + 2..).ok_or_else(malformed)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread};
+
+    use super::*;
+
+    #[test]
+    fn test_http_get() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = thread::spawn(move || {
+            let replies = [
+                "HTTP/1.0 200 OK\r\nETag: \"v1\"\r\n\r\n7",
+                "HTTP/1.0 304 Not Modified\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n1\r\n4\r\n1\r\n2\r\n0\r\n\r\n",
+                "HTTP/1.0 503 Service Unavailable\r\n\r\n",
+            ];
+            let mut requests = Vec::new();
+
+            for reply in replies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 1024];
+                let read = stream.read(&mut request).unwrap();
+
+                requests.push(String::from_utf8_lossy(&request[..read]).into_owned());
+                stream.write_all(reply.as_bytes()).unwrap();
+            }
+
+            requests
+        });
+
+        let get = HttpGet::new(&format!("http://127.0.0.1:{port}/config")).unwrap();
+        assert_eq!(
+            get.fetch(None).unwrap(),
+            Response::Body {
+                body: b"7".to_vec(),
+                etag: Some(String::from("\"v1\"")),
+            }
+        );
+        assert_eq!(get.fetch(Some("\"v1\"")).unwrap(), Response::NotModified);
+        assert_eq!(
+            get.fetch(None).unwrap(),
+            Response::Body {
+                body: b"42".to_vec(),
+                etag: None,
+            }
+        );
+        assert!(get.fetch(None).is_err());
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /config HTTP/1.0\r\nHost: 127.0.0.1\r\n"));
+        assert!(requests[1].contains("If-None-Match: \"v1\"\r\n"));
+    }
+
+    #[test]
+    fn test_invalid_url() {
+        assert!(HttpGet::new("https://example.com").is_err());
+        assert!(HttpGet::new("http://:80/").is_err());
+        assert!(HttpGet::new("http://host:port/").is_err());
+        assert_eq!(HttpGet::new("http://host").unwrap().path, "/");
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::cache::{Cache, Refresher};
+
+use super::{SourceError, error::BoxError};
+
+type Decode<T> = Box<dyn Fn(&BTreeMap<String, Vec<u8>>) -> Result<T, BoxError> + Send>;
+
+// Feeds a cache from a ConfigMap or Secret mounted as a volume. The kubelet
+// writes every update to a fresh directory and swaps the `..data` symlink
+// to it, so a poll only rereads the keys once that link has moved.
+//
+// Each key of the ConfigMap is a file in `dir`. `decode` receives them all,
+// keyed by name, and its result is published with `Cache::try_publish`.
+pub struct MountedConfigMap<T>
+where
+    T: Clone,
+{
+    cache: Arc<Cache<T>>,
+    dir: PathBuf,
+    decode: Decode<T>,
+    // Target of `..data` when the current value was read, or the keys
+    // themselves for directories not managed by the kubelet
+    seen: Option<Seen>,
+}
+
+#[derive(PartialEq, Eq)]
+enum Seen {
+    Link(PathBuf),
+    Keys(BTreeMap<String, Vec<u8>>),
+}
+
+impl<T: Clone + Send + Sync + 'static> MountedConfigMap<T> {
+    pub fn new<E>(
+        cache: Arc<Cache<T>>,
+        dir: impl Into<PathBuf>,
+        decode: impl Fn(&BTreeMap<String, Vec<u8>>) -> Result<T, E> + Send + 'static,
+    ) -> Self
+    where
+        E: Into<BoxError>,
+    {
+        Self {
+            cache,
+            dir: dir.into(),
+            decode: Box::new(move |keys| decode(keys).map_err(Into::into)),
+            seen: None,
+        }
+    }
+
+    pub fn cache(&self) -> &Arc<Cache<T>> {
+        &self.cache
+    }
+
+    // Rereads the mount, returning whether a new value was published
+    pub fn poll(&mut self) -> Result<bool, SourceError> {
+        let fetch = |err: io::Error| SourceError::Fetch(err.into());
+
+        let link = fs::read_link(self.dir.join("..data")).ok();
+
+        if let (Some(link), Some(Seen::Link(seen))) = (&link, &self.seen)
+            && link == seen
+        {
+            return Ok(false);
+        }
+
+        let keys = read_keys(&self.dir).map_err(fetch)?;
+        let seen = match link {
+            Some(link) => Seen::Link(link),
+            None => Seen::Keys(keys.clone()),
+        };
+
+        if self.seen.as_ref() == Some(&seen) {
+            return Ok(false);
+        }
+
+        let data = (self.decode)(&keys).map_err(SourceError::Decode)?;
+
+        self.cache
+            .try_publish(data)
+            .map_err(|rejected| SourceError::Rejected(rejected.error))?;
+        self.seen = Some(seen);
+
+        Ok(true)
+    }
+
+    // Polls every `interval` on a background thread, handing failures to
+    // `on_error`
+    pub fn spawn(
+        mut self,
+        interval: Duration,
+        mut on_error: impl FnMut(SourceError) + Send + 'static,
+    ) -> Refresher {
+        Refresher::spawn(interval, move || {
+            if let Err(err) = self.poll() {
+                on_error(err);
+            }
+        })
+    }
+}
+
+// Every key in the mount. The kubelet's own entries start with `..`.
+fn read_keys(dir: &Path) -> io::Result<BTreeMap<String, Vec<u8>>> {
+    let mut keys = BTreeMap::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if name.starts_with("..") || !entry.path().is_file() {
+            continue;
+        }
+
+        keys.insert(name, fs::read(entry.path())?);
+    }
+
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    use std::os::unix::fs::symlink;
+
+    use super::*;
+
+    fn decode(keys: &BTreeMap<String, Vec<u8>>) -> Result<String, &'static str> {
+        let value = keys.get("mode").ok_or("missing mode")?;
+
+        Ok(String::from_utf8_lossy(value).into_owned())
+    }
+
+    // Lays out `dir` the way the kubelet does, with the keys in a versioned
+    // directory behind `..data`
+    #[cfg(unix)]
+    fn publish(dir: &Path, version: u32, mode: &str) {
+        let versioned = dir.join(format!("..{version}"));
+        fs::create_dir(&versioned).unwrap();
+        fs::write(versioned.join("mode"), mode).unwrap();
+
+        let _ = fs::remove_file(dir.join("..data_tmp"));
+        symlink(&versioned, dir.join("..data_tmp")).unwrap();
+        fs::rename(dir.join("..data_tmp"), dir.join("..data")).unwrap();
+
+        if !dir.join("mode").exists() {
+            symlink(dir.join("..data/mode"), dir.join("mode")).unwrap();
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_mounted_config_map() {
+        let dir = std::env::temp_dir().join(format!("sloth-k8s-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+
+        publish(&dir, 1, "blue");
+
+        let cache = Arc::new(Cache::new(String::new()));
+        let mut source = MountedConfigMap::new(cache.clone(), &dir, decode);
+
+        assert!(source.poll().unwrap());
+        assert_eq!(cache.get_data(), "blue");
+        assert!(!source.poll().unwrap());
+
+        publish(&dir, 2, "green");
+        assert!(source.poll().unwrap());
+        assert_eq!(cache.get_data(), "green");
+        assert!(!source.poll().unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_plain_directory() {
+        let dir = std::env::temp_dir().join(format!("sloth-k8s-plain-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("mode"), "red").unwrap();
+
+        let cache = Arc::new(Cache::new(String::new()));
+        let mut source = MountedConfigMap::new(cache.clone(), &dir, decode);
+
+        assert!(source.poll().unwrap());
+        assert!(!source.poll().unwrap());
+
+        fs::write(dir.join("mode"), "amber").unwrap();
+        assert!(source.poll().unwrap());
+        assert_eq!(cache.get_data(), "amber");
+
+        fs::remove_file(dir.join("mode")).unwrap();
+        assert!(matches!(source.poll(), Err(SourceError::Decode(_))));
+        assert_eq!(cache.get_data(), "amber");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    hash::{BuildHasher, RandomState},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::cache::{Cache, Refresher};
+
+mod error;
+mod http;
+#[cfg(feature = "k8s")]
+mod k8s;
+#[cfg(feature = "redis")]
+mod redis;
+
+pub use error::SourceError;
+pub use http::HttpGet;
+#[cfg(feature = "k8s")]
+pub use k8s::MountedConfigMap;
+#[cfg(feature = "redis")]
+pub use redis::RedisInvalidation;
+
+use error::BoxError;
+
+type Fetch = Box<dyn FnMut(Option<&str>) -> Result<Response, BoxError> + Send>;
+type Decode<T> = Box<dyn Fn(&[u8]) -> Result<T, BoxError> + Send>;
+
+// What a fetch came back with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    // The value is unchanged since the fetch that returned this ETag
+    NotModified,
+    Body { body: Vec<u8>, etag: Option<String> },
+}
+
+// Feeds a cache from a remote endpoint: fetches the body, decodes it and
+// publishes it through `Cache::try_publish`, so the cache's validator gets
+// the last word. The ETag of the last response is sent back with the next
+// fetch, and unchanged values are not republished.
+pub struct Remote<T>
+where
+    T: Clone,
+{
+    cache: Arc<Cache<T>>,
+    fetch: Fetch,
+    decode: Decode<T>,
+    etag: Option<String>,
+    jitter: f64,
+}
+
+impl<T: Clone + Send + Sync + 'static> Remote<T> {
+    // `fetch` receives the ETag of the last response, if any
+    pub fn new<E, D>(
+        cache: Arc<Cache<T>>,
+        mut fetch: impl FnMut(Option<&str>) -> Result<Response, E> + Send + 'static,
+        decode: impl Fn(&[u8]) -> Result<T, D> + Send + 'static,
+    ) -> Self
+    where
+        E: Into<BoxError>,
+        D: Into<BoxError>,
+    {
+        Self {
+            cache,
+            fetch: Box::new(move |etag| fetch(etag).map_err(Into::into)),
+            decode: Box::new(move |body| decode(body).map_err(Into::into)),
+            etag: None,
+            jitter: 0.0,
+        }
+    }
+
+    // Fetches with a plain HTTP GET, see `HttpGet`
+    pub fn http<D>(
+        cache: Arc<Cache<T>>,
+        url: &str,
+        decode: impl Fn(&[u8]) -> Result<T, D> + Send + 'static,
+    ) -> Result<Self, SourceError>
+    where
+        D: Into<BoxError>,
+    {
+        let get = HttpGet::new(url).map_err(|err| SourceError::Fetch(err.into()))?;
+
+        Ok(Self::new(cache, move |etag| get.fetch(etag), decode))
+    }
+
+    // Spreads polls over `interval` plus or minus `fraction` of it, so a
+    // fleet started together doesn't hit the endpoint in lockstep
+    pub fn jitter(mut self, fraction: f64) -> Self {
+        self.jitter = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn cache(&self) -> &Arc<Cache<T>> {
+        &self.cache
+    }
+
+    // Fetches once, returning whether a new value was published
+    pub fn poll(&mut self) -> Result<bool, SourceError> {
+        let (body, etag) = match (self.fetch)(self.etag.as_deref()).map_err(SourceError::Fetch)? {
+            Response::NotModified => return Ok(false),
+            Response::Body { body, etag } => (body, etag),
+        };
+
+        let data = (self.decode)(&body).map_err(SourceError::Decode)?;
+
+        self.cache
+            .try_publish(data)
+            .map_err(|rejected| SourceError::Rejected(rejected.error))?;
+
+        // Only remembered once published, so a refused value is fetched
+        // again instead of being reported as not modified
+        self.etag = etag;
+
+        Ok(true)
+    }
+
+    // Polls every `interval` on a background thread, handing failures to
+    // `on_error`
+    pub fn spawn(
+        mut self,
+        interval: Duration,
+        mut on_error: impl FnMut(SourceError) + Send + 'static,
+    ) -> Refresher {
+        let jitter = self.jitter;
+        let random = RandomState::new();
+        let mut polls = 0u64;
+
+        let delay = move || {
+            polls += 1;
+
+            // Uniform in [-1, 1], from a randomly keyed hash of the counter
+            let unit = random.hash_one(polls) as f64 / u64::MAX as f64 * 2.0 - 1.0;
+
+            interval.mul_f64(1.0 + jitter * unit)
+        };
+
+        Refresher::spawn_with(delay, move || {
+            if let Err(err) = self.poll() {
+                on_error(err);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    fn numbers(body: &[u8]) -> Result<u64, std::num::ParseIntError> {
+        String::from_utf8_lossy(body).trim().parse()
+    }
+
+    #[test]
+    fn test_remote_poll() {
+        let cache = Arc::new(Cache::new(0));
+        cache.set_validator(|data: &u64| if *data < 100 { Ok(()) } else { Err("too big") });
+
+        let (send, responses) = mpsc::channel();
+        let (etags, sent) = mpsc::channel();
+
+        let mut remote = Remote::new(
+            cache.clone(),
+            move |etag: Option<&str>| {
+                etags.send(etag.map(str::to_owned)).unwrap();
+                Ok::<_, BoxError>(responses.recv().unwrap())
+            },
+            numbers,
+        );
+
+        let body = |body: &str, etag: &str| Response::Body {
+            body: body.as_bytes().to_vec(),
+            etag: Some(etag.to_owned()),
+        };
+
+        send.send(body("1", "a")).unwrap();
+        assert!(remote.poll().unwrap());
+        assert_eq!(cache.get_data(), 1);
+        assert_eq!(sent.recv().unwrap(), None);
+
+        send.send(Response::NotModified).unwrap();
+        assert!(!remote.poll().unwrap());
+        assert_eq!(sent.recv().unwrap().as_deref(), Some("a"));
+
+        send.send(body("x", "b")).unwrap();
+        assert!(matches!(remote.poll(), Err(SourceError::Decode(_))));
+
+        send.send(body("500", "c")).unwrap();
+        assert!(matches!(remote.poll(), Err(SourceError::Rejected(_))));
+        assert_eq!(cache.get_data(), 1);
+        assert_eq!(sent.recv().unwrap().as_deref(), Some("a"));
+        assert_eq!(sent.recv().unwrap().as_deref(), Some("a"));
+    }
+}
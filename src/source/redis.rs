@@ -0,0 +1,300 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, TcpStream},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::cache::Cache;
+
+use super::{SourceError, error::BoxError};
+
+// Reloads a cache whenever a message arrives on a Redis pub/sub channel.
+// Each message means "the authoritative value changed": `load` is called
+// and its result published through `Cache::try_publish`. The payload is
+// ignored.
+//
+// Talks RESP over plain TCP. The connection is retried with a growing delay
+// when it drops, and the value is reloaded after every (re)subscription
+// since invalidations sent in between were missed. Stops when the handle is
+// dropped.
+pub struct RedisInvalidation {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    wakeup: Condvar,
+}
+
+struct State {
+    stopped: bool,
+    // Shut down by `stop` to interrupt a blocking read
+    stream: Option<TcpStream>,
+}
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+impl RedisInvalidation {
+    pub fn spawn<T, E>(
+        cache: Arc<Cache<T>>,
+        address: impl Into<String>,
+        channel: impl Into<String>,
+        mut load: impl FnMut() -> Result<T, E> + Send + 'static,
+        mut on_error: impl FnMut(SourceError) + Send + 'static,
+    ) -> Self
+    where
+        T: Clone + Send + Sync + 'static,
+        E: Into<BoxError>,
+    {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                stopped: false,
+                stream: None,
+            }),
+            wakeup: Condvar::new(),
+        });
+
+        let address = address.into();
+        let channel = channel.into();
+        let signal = shared.clone();
+
+        let thread = thread::spawn(move || {
+            let mut reload = || {
+                let data = load().map_err(|err| SourceError::Fetch(err.into()))?;
+
+                cache
+                    .try_publish(data)
+                    .map_err(|rejected| SourceError::Rejected(rejected.error))
+            };
+
+            let mut delay = Duration::from_millis(100);
+
+            loop {
+                let result = subscribe(&signal, &address, &channel, &mut || {
+                    // Connected again, so the next failure retries quickly
+                    delay = Duration::from_millis(100);
+
+                    if let Err(err) = reload() {
+                        on_error(err);
+                    }
+                });
+
+                if let Err(err) = result {
+                    on_error(SourceError::Fetch(err.into()));
+                }
+
+                let state = signal.state();
+                let (state, _) = signal
+                    .wakeup
+                    .wait_timeout_while(state, delay, |state| !state.stopped)
+                    .unwrap_or_else(|err| err.into_inner());
+
+                if state.stopped {
+                    return;
+                }
+
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+        });
+
+        Self {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    // Closes the connection and waits for the thread to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        let mut state = self.shared.state();
+        state.stopped = true;
+
+        if let Some(stream) = state.stream.take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+
+        drop(state);
+        self.shared.wakeup.notify_one();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for RedisInvalidation {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+// Subscribes and calls `invalidate` once subscribed and then for every
+// message, until the connection drops or the handle is stopped
+fn subscribe(
+    shared: &Shared,
+    address: &str,
+    channel: &str,
+    invalidate: &mut dyn FnMut(),
+) -> io::Result<()> {
+    let mut stream = TcpStream::connect(address)?;
+
+    {
+        let mut state = shared.state();
+
+        if state.stopped {
+            return Ok(());
+        }
+
+        state.stream = Some(stream.try_clone()?);
+    }
+
+    let command = format!(
+        "*2\r\n$9\r\nSUBSCRIBE\r\n${}\r\n{channel}\r\n",
+        channel.len()
+    );
+    stream.write_all(command.as_bytes())?;
+
+    let mut reader = BufReader::new(stream);
+
+    loop {
+        let frame = match read_frame(&mut reader) {
+            Ok(frame) => frame,
+            // Shut down by `stop`
+            Err(_) if shared.state().stopped => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        let Frame::Array(parts) = frame else {
+            continue;
+        };
+
+        match parts.first() {
+            Some(Frame::Bulk(Some(kind))) if kind == b"subscribe" || kind == b"message" => {
+                invalidate();
+            }
+            Some(Frame::Error(err)) => return Err(io::Error::other(err.clone())),
+            _ => {}
+        }
+    }
+}
+
+enum Frame {
+    Simple,
+    Error(String),
+    Integer,
+    Bulk(Option<Vec<u8>>),
+    Array(Vec<Frame>),
+}
+
+fn read_frame(reader: &mut impl BufRead) -> io::Result<Frame> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed RESP frame");
+
+    let mut line = String::new();
+
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    let line = line.trim_end_matches("\r\n");
+    let (kind, rest) = line.split_at_checked(1).ok_or_else(malformed)?;
+    let length = || rest.parse::<i64>().map_err(|_| malformed());
+
+    match kind {
+        "+" => Ok(Frame::Simple),
+        "-" => Ok(Frame::Error(rest.to_owned())),
+        ":" => Ok(Frame::Integer),
+        "$" => {
+            let Ok(length) = usize::try_from(length()?) else {
+                return Ok(Frame::Bulk(None));
+            };
+
+            let mut data = vec![0; length + 2];
+            reader.read_exact(&mut data)?;
+            data.truncate(length);
+
+            Ok(Frame::Bulk(Some(data)))
+        }
+        "*" => {
+            let count = usize::try_from(length()?).unwrap_or(0);
+
+            (0..count)
+                .map(|_| read_frame(reader))
+                .collect::<io::Result<_>>()
+                .map(Frame::Array)
+        }
+        _ => Err(malformed()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Read,
+        net::TcpListener,
+        sync::atomic::{AtomicU64, Ordering},
+    };
+
+    use super::*;
+
+    fn message(channel: &str, payload: &str) -> String {
+        format!(
+            "*3\r\n$7\r\nmessage\r\n${}\r\n{channel}\r\n${}\r\n{payload}\r\n",
+            channel.len(),
+            payload.len()
+        )
+    }
+
+    #[test]
+    fn test_redis_invalidation() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let cache = Arc::new(Cache::new(0));
+        let version = Arc::new(AtomicU64::new(1));
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let expected = b"*2\r\n$9\r\nSUBSCRIBE\r\n$6\r\nconfig\r\n";
+            let mut command = [0; 31];
+            stream.read_exact(&mut command).unwrap();
+            assert_eq!(&command, expected);
+
+            stream
+                .write_all(b"*3\r\n$9\r\nsubscribe\r\n$6\r\nconfig\r\n:1\r\n")
+                .unwrap();
+            stream
+        });
+
+        let loads = version.clone();
+        let invalidation = RedisInvalidation::spawn(
+            cache.clone(),
+            address,
+            "config",
+            move || Ok::<_, BoxError>(loads.load(Ordering::Acquire)),
+            |err| panic!("{err}"),
+        );
+
+        // Loaded once subscribed
+        cache.wait_for_blocking(|data| *data == 1);
+
+        let mut stream = server.join().unwrap();
+        version.store(2, Ordering::Release);
+        stream.write_all(message("config", "").as_bytes()).unwrap();
+        cache.wait_for_blocking(|data| *data == 2);
+
+        invalidation.stop();
+        assert_eq!(stream.read(&mut [0; 1]).unwrap(), 0);
+    }
+}
@@ -0,0 +1,73 @@
+// Synchronization primitives used by the concurrent internals. Building with
+// the `loom` or `shuttle` feature, or the matching `RUSTFLAGS="--cfg ..."`,
+// swaps them for the model-checked versions, see `tests/loom.rs` and
+// `tests/shuttle.rs`.
+//
+// Targets without atomics get the critical-section stand-ins from `single`,
+// and lose the modules built on `Arc`, which `alloc` only has with
+// pointer-sized atomics. `wasm32-unknown-unknown` does have atomics, lowered
+// to plain accesses while it is single-threaded, so it keeps the real ones.
+
+#[cfg(any(test, not(target_has_atomic = "ptr")))]
+pub(crate) mod single;
+
+#[cfg(all(not(any(loom, shuttle)), target_has_atomic = "ptr"))]
+pub(crate) use core::sync::atomic;
+
+#[cfg(all(not(any(loom, shuttle)), not(target_has_atomic = "ptr")))]
+pub(crate) use single as atomic;
+
+#[cfg(all(feature = "std", not(any(loom, shuttle))))]
+pub(crate) use std::{
+    sync::{Arc, Mutex},
+    thread,
+};
+
+#[cfg(loom)]
+pub(crate) use loom::{
+    sync::{Arc, Mutex, atomic},
+    thread,
+};
+
+#[cfg(shuttle)]
+pub(crate) use shuttle::{
+    sync::{Arc, Mutex, atomic},
+    thread,
+};
+
+// Hint for busy-wait loops. Under the model checkers it yields so the
+// scheduler can make progress on other threads.
+#[inline]
+pub(crate) fn spin_loop() {
+    #[cfg(not(any(loom, shuttle)))]
+    core::hint::spin_loop();
+
+    #[cfg(any(loom, shuttle))]
+    thread::yield_now();
+}
+
+// `UnsafeCell` with loom's closure-based API, so access to the cell can be
+// tracked when model checking.
+#[cfg(not(loom))]
+#[derive(Debug)]
+pub(crate) struct UnsafeCell<T>(core::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    pub(crate) const fn new(data: T) -> Self {
+        Self(core::cell::UnsafeCell::new(data))
+    }
+
+    #[inline]
+    pub(crate) fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+
+    #[inline]
+    pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
+
+#[cfg(loom)]
+pub(crate) use loom::cell::UnsafeCell;
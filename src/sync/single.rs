@@ -0,0 +1,289 @@
+// Stand-ins for the atomics on targets without them, such as
+// `thumbv6m-none-eabi` or `riscv32i`: `Cell`s with the same API, each
+// operation run in a critical section so interrupt handlers can't land in
+// the middle of one. The target's `critical-section` implementation, e.g.
+// the one in `cortex-m`, has to be linked in.
+
+// Mirrors the whole `core` API, whatever the crate happens to use
+#![allow(dead_code)]
+
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+#[allow(unused_imports)]
+pub(crate) use core::sync::atomic::{Ordering, compiler_fence, fence};
+
+macro_rules! integer {
+    ($name:ident, $ty:ty) => {
+        #[derive(Debug)]
+        pub(crate) struct $name(Mutex<Cell<$ty>>);
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new(0)
+            }
+        }
+
+        impl $name {
+            pub(crate) const fn new(value: $ty) -> Self {
+                Self(Mutex::new(Cell::new(value)))
+            }
+
+            fn with<R>(&self, f: impl FnOnce(&Cell<$ty>) -> R) -> R {
+                critical_section::with(|cs| f(self.0.borrow(cs)))
+            }
+
+            pub(crate) fn load(&self, _: Ordering) -> $ty {
+                self.with(Cell::get)
+            }
+
+            pub(crate) fn store(&self, value: $ty, _: Ordering) {
+                self.with(|cell| cell.set(value));
+            }
+
+            pub(crate) fn swap(&self, value: $ty, _: Ordering) -> $ty {
+                self.with(|cell| cell.replace(value))
+            }
+
+            pub(crate) fn compare_exchange(
+                &self,
+                current: $ty,
+                new: $ty,
+                _: Ordering,
+                _: Ordering,
+            ) -> Result<$ty, $ty> {
+                self.with(|cell| {
+                    let value = cell.get();
+
+                    if value != current {
+                        return Err(value);
+                    }
+
+                    cell.set(new);
+                    Ok(value)
+                })
+            }
+
+            pub(crate) fn compare_exchange_weak(
+                &self,
+                current: $ty,
+                new: $ty,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$ty, $ty> {
+                self.compare_exchange(current, new, success, failure)
+            }
+
+            pub(crate) fn fetch_add(&self, value: $ty, _: Ordering) -> $ty {
+                self.with(|cell| cell.replace(cell.get().wrapping_add(value)))
+            }
+
+            pub(crate) fn fetch_sub(&self, value: $ty, _: Ordering) -> $ty {
+                self.with(|cell| cell.replace(cell.get().wrapping_sub(value)))
+            }
+
+            pub(crate) fn fetch_or(&self, value: $ty, _: Ordering) -> $ty {
+                self.with(|cell| cell.replace(cell.get() | value))
+            }
+
+            pub(crate) fn fetch_and(&self, value: $ty, _: Ordering) -> $ty {
+                self.with(|cell| cell.replace(cell.get() & value))
+            }
+
+            pub(crate) fn fetch_max(&self, value: $ty, _: Ordering) -> $ty {
+                self.with(|cell| cell.replace(cell.get().max(value)))
+            }
+
+            pub(crate) fn fetch_update(
+                &self,
+                _: Ordering,
+                _: Ordering,
+                mut f: impl FnMut($ty) -> Option<$ty>,
+            ) -> Result<$ty, $ty> {
+                self.with(|cell| {
+                    let value = cell.get();
+
+                    match f(value) {
+                        Some(new) => {
+                            cell.set(new);
+                            Ok(value)
+                        }
+                        None => Err(value),
+                    }
+                })
+            }
+
+            pub(crate) fn get_mut(&mut self) -> &mut $ty {
+                self.0.get_mut().get_mut()
+            }
+
+            pub(crate) fn into_inner(self) -> $ty {
+                self.0.into_inner().into_inner()
+            }
+        }
+    };
+}
+
+integer!(AtomicU8, u8);
+integer!(AtomicU32, u32);
+integer!(AtomicU64, u64);
+integer!(AtomicUsize, usize);
+
+#[derive(Debug)]
+pub(crate) struct AtomicBool(Mutex<Cell<bool>>);
+
+impl Default for AtomicBool {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl AtomicBool {
+    pub(crate) const fn new(value: bool) -> Self {
+        Self(Mutex::new(Cell::new(value)))
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&Cell<bool>) -> R) -> R {
+        critical_section::with(|cs| f(self.0.borrow(cs)))
+    }
+
+    pub(crate) fn load(&self, _: Ordering) -> bool {
+        self.with(Cell::get)
+    }
+
+    pub(crate) fn store(&self, value: bool, _: Ordering) {
+        self.with(|cell| cell.set(value));
+    }
+
+    pub(crate) fn swap(&self, value: bool, _: Ordering) -> bool {
+        self.with(|cell| cell.replace(value))
+    }
+
+    pub(crate) fn compare_exchange(
+        &self,
+        current: bool,
+        new: bool,
+        _: Ordering,
+        _: Ordering,
+    ) -> Result<bool, bool> {
+        self.with(|cell| {
+            let value = cell.get();
+
+            if value != current {
+                return Err(value);
+            }
+
+            cell.set(new);
+            Ok(value)
+        })
+    }
+
+    pub(crate) fn compare_exchange_weak(
+        &self,
+        current: bool,
+        new: bool,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<bool, bool> {
+        self.compare_exchange(current, new, success, failure)
+    }
+
+    pub(crate) fn fetch_or(&self, value: bool, _: Ordering) -> bool {
+        self.with(|cell| cell.replace(cell.get() | value))
+    }
+
+    pub(crate) fn fetch_and(&self, value: bool, _: Ordering) -> bool {
+        self.with(|cell| cell.replace(cell.get() & value))
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct AtomicPtr<T>(Mutex<Cell<*mut T>>);
+
+// Safety: like `core`'s `AtomicPtr`, it only shares the address, and every
+// access to it is serialized by a critical section
+unsafe impl<T> Sync for AtomicPtr<T> {}
+unsafe impl<T> Send for AtomicPtr<T> {}
+
+impl<T> AtomicPtr<T> {
+    pub(crate) const fn new(value: *mut T) -> Self {
+        Self(Mutex::new(Cell::new(value)))
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&Cell<*mut T>) -> R) -> R {
+        critical_section::with(|cs| f(self.0.borrow(cs)))
+    }
+
+    pub(crate) fn load(&self, _: Ordering) -> *mut T {
+        self.with(Cell::get)
+    }
+
+    pub(crate) fn store(&self, value: *mut T, _: Ordering) {
+        self.with(|cell| cell.set(value));
+    }
+
+    pub(crate) fn swap(&self, value: *mut T, _: Ordering) -> *mut T {
+        self.with(|cell| cell.replace(value))
+    }
+
+    pub(crate) fn compare_exchange(
+        &self,
+        current: *mut T,
+        new: *mut T,
+        _: Ordering,
+        _: Ordering,
+    ) -> Result<*mut T, *mut T> {
+        self.with(|cell| {
+            let value = cell.get();
+
+            if value != current {
+                return Err(value);
+            }
+
+            cell.set(new);
+            Ok(value)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_threaded_atomics() {
+        let count = AtomicUsize::new(1);
+        assert_eq!(count.fetch_add(2, Ordering::SeqCst), 1);
+        assert_eq!(count.fetch_sub(1, Ordering::SeqCst), 3);
+        assert_eq!(
+            count.compare_exchange(1, 5, Ordering::SeqCst, Ordering::SeqCst),
+            Err(2)
+        );
+        assert_eq!(
+            count.compare_exchange(2, 5, Ordering::SeqCst, Ordering::SeqCst),
+            Ok(2)
+        );
+        assert_eq!(count.swap(usize::MAX, Ordering::SeqCst), 5);
+        assert_eq!(count.fetch_add(1, Ordering::SeqCst), usize::MAX);
+        assert_eq!(count.into_inner(), 0);
+
+        let flag = AtomicBool::new(false);
+        assert!(!flag.fetch_or(true, Ordering::SeqCst));
+        assert!(flag.load(Ordering::SeqCst));
+
+        let mut value = 7;
+        let ptr = AtomicPtr::new(core::ptr::null_mut());
+        ptr.store(&mut value, Ordering::SeqCst);
+        assert_eq!(unsafe { *ptr.load(Ordering::SeqCst) }, 7);
+    }
+
+    // Shared through `critical_section::Mutex`, not an `unsafe impl`
+    #[test]
+    fn test_sync_through_critical_sections() {
+        fn is_sync<T: Sync>() {}
+
+        is_sync::<AtomicUsize>();
+        is_sync::<AtomicBool>();
+    }
+}
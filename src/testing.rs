@@ -0,0 +1,28 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+// How often the values sharing them were cloned and dropped
+#[derive(Default)]
+pub(crate) struct Counts {
+    pub(crate) clones: AtomicUsize,
+    pub(crate) drops: AtomicUsize,
+}
+
+// Value counting its clones and drops, for tests checking that cells copy
+// and reclaim what they should
+pub(crate) struct Counted(pub(crate) u64, pub(crate) Arc<Counts>);
+
+impl Clone for Counted {
+    fn clone(&self) -> Self {
+        self.1.clones.fetch_add(1, Ordering::AcqRel);
+        Self(self.0, self.1.clone())
+    }
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.1.drops.fetch_add(1, Ordering::AcqRel);
+    }
+}
@@ -0,0 +1,111 @@
+// Read and write halves shared by the snapshot containers, so code can be
+// generic over the backing strategy.
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use crate::{
+    cache::Cache,
+    seqlock::{NoPadding, SeqCache},
+};
+
+pub trait SnapshotRead<T> {
+    // The current value
+    fn load(&self) -> T;
+}
+
+pub trait SnapshotWrite<T> {
+    // Replaces the current value
+    fn store(&self, data: T);
+}
+
+impl<T: Clone, const LEN: usize> SnapshotRead<T> for Cache<T, LEN> {
+    fn load(&self) -> T {
+        self.get_data()
+    }
+}
+
+impl<T: Clone, const LEN: usize> SnapshotWrite<T> for Cache<T, LEN> {
+    fn store(&self, data: T) {
+        self.update(data);
+    }
+}
+
+impl<T: NoPadding> SnapshotRead<T> for SeqCache<T> {
+    fn load(&self) -> T {
+        self.get_data()
+    }
+}
+
+impl<T: NoPadding> SnapshotWrite<T> for SeqCache<T> {
+    fn store(&self, data: T) {
+        self.update(data);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone + Send + Sync> SnapshotRead<T> for crate::hazard::HpCell<T> {
+    fn load(&self) -> T {
+        self.get()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Send + Sync> SnapshotWrite<T> for crate::hazard::HpCell<T> {
+    fn store(&self, data: T) {
+        self.update(data);
+    }
+}
+
+#[cfg(feature = "rcu")]
+impl<T: Clone + Send + Sync> SnapshotRead<T> for crate::rcu::RcuCell<T> {
+    fn load(&self) -> T {
+        self.get()
+    }
+}
+
+#[cfg(feature = "rcu")]
+impl<T: Send + Sync> SnapshotWrite<T> for crate::rcu::RcuCell<T> {
+    fn store(&self, data: T) {
+        self.update(data);
+    }
+}
+
+// A poisoned lock still holds a complete value, since `store` only ever
+// assigns it
+#[cfg(feature = "std")]
+impl<T: Clone> SnapshotRead<T> for RwLock<T> {
+    fn load(&self) -> T {
+        self.read().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> SnapshotWrite<T> for RwLock<T> {
+    fn store(&self, data: T) {
+        *self.write().unwrap_or_else(|err| err.into_inner()) = data;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bump<C: SnapshotRead<u64> + SnapshotWrite<u64>>(cache: &C) -> u64 {
+        cache.store(cache.load() + 1);
+        cache.load()
+    }
+
+    #[test]
+    fn test_generic_backends() {
+        assert_eq!(bump(&Cache::<u64>::new(1)), 2);
+        assert_eq!(bump(&SeqCache::new(2)), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_std_backends() {
+        assert_eq!(bump(&RwLock::new(3)), 4);
+        assert_eq!(bump(&crate::hazard::HpCell::new(4)), 5);
+    }
+}
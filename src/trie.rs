@@ -0,0 +1,328 @@
+use alloc::sync::Arc;
+
+use crate::cache::Cache;
+
+// Binary trie over the bits of byte-string keys, so prefixes can end at any
+// bit as IP routes do. Nodes are shared between versions and a change copies
+// only the path to the key it touches.
+struct Node<V> {
+    value: Option<V>,
+    children: [Option<Arc<Node<V>>>; 2],
+}
+
+impl<V> Node<V> {
+    fn new() -> Self {
+        Self {
+            value: None,
+            children: [None, None],
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.value.is_none() && self.children.iter().all(Option::is_none)
+    }
+}
+
+impl<V: Clone> Clone for Node<V> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            children: self.children.clone(),
+        }
+    }
+}
+
+fn bit(key: &[u8], index: usize) -> usize {
+    usize::from(key[index / 8] >> (7 - index % 8) & 1)
+}
+
+// One version of a `SnapshotTrie`, cheap to clone. Keys are byte strings,
+// prefixes of a bit length for `insert_bits` are the first `bits` bits of
+// theirs.
+pub struct Trie<V> {
+    root: Option<Arc<Node<V>>>,
+    len: usize,
+}
+
+impl<V> Trie<V> {
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Value of the exact key, all of its bits
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.get_bits(key, key.len() * 8)
+    }
+
+    pub fn get_bits(&self, key: &[u8], bits: usize) -> Option<&V> {
+        assert!(bits <= key.len() * 8, "prefix longer than its key");
+
+        let mut node = self.root.as_deref()?;
+
+        for index in 0..bits {
+            node = node.children[bit(key, index)].as_deref()?;
+        }
+
+        node.value.as_ref()
+    }
+
+    // Value of the longest prefix of `key` in the trie, and that prefix's
+    // length in bits
+    pub fn longest_match(&self, key: &[u8]) -> Option<(usize, &V)> {
+        let mut node = self.root.as_deref()?;
+        let mut found = node.value.as_ref().map(|value| (0, value));
+
+        for index in 0..key.len() * 8 {
+            let Some(child) = node.children[bit(key, index)].as_deref() else {
+                break;
+            };
+
+            node = child;
+
+            if let Some(value) = &node.value {
+                found = Some((index + 1, value));
+            }
+        }
+
+        found
+    }
+}
+
+impl<V: Clone> Trie<V> {
+    pub fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        self.insert_bits(key, key.len() * 8, value)
+    }
+
+    // Inserts the prefix made of the first `bits` bits of `key`
+    pub fn insert_bits(&mut self, key: &[u8], bits: usize, value: V) -> Option<V> {
+        assert!(bits <= key.len() * 8, "prefix longer than its key");
+
+        let mut node = Arc::make_mut(self.root.get_or_insert_with(|| Arc::new(Node::new())));
+
+        for index in 0..bits {
+            let child = node.children[bit(key, index)].get_or_insert_with(|| Arc::new(Node::new()));
+            node = Arc::make_mut(child);
+        }
+
+        let old = node.value.replace(value);
+
+        if old.is_none() {
+            self.len += 1;
+        }
+
+        old
+    }
+
+    pub fn remove(&mut self, key: &[u8]) -> Option<V> {
+        self.remove_bits(key, key.len() * 8)
+    }
+
+    pub fn remove_bits(&mut self, key: &[u8], bits: usize) -> Option<V> {
+        // Don't copy the path for a prefix that isn't there
+        self.get_bits(key, bits)?;
+
+        let root = self.root.as_mut()?;
+        let old = Self::remove_from(root, key, bits, 0);
+
+        if root.is_empty() {
+            self.root = None;
+        }
+
+        self.len -= 1;
+
+        old
+    }
+
+    // Takes the value at depth `bits` below `node`, pruning the nodes left
+    // empty on the way back up
+    fn remove_from(node: &mut Arc<Node<V>>, key: &[u8], bits: usize, depth: usize) -> Option<V> {
+        let node = Arc::make_mut(node);
+
+        if depth == bits {
+            return node.value.take();
+        }
+
+        let slot = &mut node.children[bit(key, depth)];
+        let old = Self::remove_from(slot.as_mut()?, key, bits, depth + 1);
+
+        if slot.as_ref().is_some_and(|child| child.is_empty()) {
+            *slot = None;
+        }
+
+        old
+    }
+}
+
+impl<V> Clone for Trie<V> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Read-mostly trie for longest-prefix matching, such as routing tables and
+// topic subscriptions. Readers look keys up in the current version without
+// blocking, writers copy the paths they change and publish a new version.
+pub struct SnapshotTrie<V> {
+    trie: Cache<Trie<V>>,
+}
+
+impl<V: Clone> SnapshotTrie<V> {
+    pub fn new() -> Self {
+        Self {
+            trie: Cache::new(Trie::new()),
+        }
+    }
+
+    // The trie as of now. Later changes publish a new version and leave
+    // this one untouched.
+    pub fn snapshot(&self) -> Trie<V> {
+        self.trie.get_data()
+    }
+
+    pub fn len(&self) -> usize {
+        self.trie.with_pinned(Trie::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trie.with_pinned(Trie::is_empty)
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<V> {
+        self.trie.with_pinned(|trie| trie.get(key).cloned())
+    }
+
+    // Value of the longest prefix of `key`, see `Trie::longest_match`
+    pub fn longest_match(&self, key: &[u8]) -> Option<V> {
+        self.trie
+            .with_pinned(|trie| trie.longest_match(key).map(|(_, value)| value.clone()))
+    }
+
+    pub fn insert(&self, key: &[u8], value: V) -> Option<V> {
+        self.modify(|trie| trie.insert(key, value))
+    }
+
+    pub fn insert_bits(&self, key: &[u8], bits: usize, value: V) -> Option<V> {
+        self.modify(|trie| trie.insert_bits(key, bits, value))
+    }
+
+    pub fn remove(&self, key: &[u8]) -> Option<V> {
+        self.modify(|trie| trie.remove(key))
+    }
+
+    pub fn remove_bits(&self, key: &[u8], bits: usize) -> Option<V> {
+        self.modify(|trie| trie.remove_bits(key, bits))
+    }
+
+    // Applies `f` to the current version and publishes the result, so a
+    // batch of changes lands at once. Writers are serialized.
+    pub fn modify<R>(&self, f: impl FnOnce(&mut Trie<V>) -> R) -> R {
+        let mut result = None;
+
+        self.trie.update_with(|current| {
+            let mut trie = current.cloned().unwrap_or_default();
+            result = Some(f(&mut trie));
+            trie
+        });
+
+        result.expect("`update_with` always runs its closure")
+    }
+}
+
+impl<V: Clone> Default for SnapshotTrie<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_longest_match() {
+        let routes: SnapshotTrie<&str> = SnapshotTrie::new();
+
+        routes.insert_bits(&[10, 0, 0, 0], 8, "10/8");
+        routes.insert_bits(&[10, 1, 0, 0], 16, "10.1/16");
+        routes.insert_bits(&[10, 1, 128, 0], 17, "10.1.128/17");
+
+        assert_eq!(routes.longest_match(&[10, 1, 200, 7]), Some("10.1.128/17"));
+        assert_eq!(routes.longest_match(&[10, 1, 2, 3]), Some("10.1/16"));
+        assert_eq!(routes.longest_match(&[10, 2, 2, 3]), Some("10/8"));
+        assert_eq!(routes.longest_match(&[11, 0, 0, 1]), None);
+
+        // A default route matches everything
+        routes.insert_bits(&[], 0, "default");
+        assert_eq!(routes.longest_match(&[11, 0, 0, 1]), Some("default"));
+        assert_eq!(routes.len(), 4);
+
+        // Old versions keep their routes
+        let before = routes.snapshot();
+        assert_eq!(routes.remove_bits(&[10, 1, 0, 0], 16), Some("10.1/16"));
+        assert_eq!(routes.remove_bits(&[10, 1, 0, 0], 16), None);
+        assert_eq!(routes.longest_match(&[10, 1, 2, 3]), Some("10/8"));
+        assert_eq!(before.longest_match(&[10, 1, 2, 3]), Some((16, &"10.1/16")));
+        assert_eq!(routes.len(), 3);
+
+        // Byte keys for topic prefixes
+        let topics: SnapshotTrie<u32> = SnapshotTrie::new();
+        topics.modify(|trie| {
+            trie.insert(b"orders.", 1);
+            trie.insert(b"orders.eu.", 2);
+        });
+        assert_eq!(topics.longest_match(b"orders.eu.created"), Some(2));
+        assert_eq!(topics.longest_match(b"orders.us.created"), Some(1));
+        assert_eq!(topics.longest_match(b"order"), None);
+        assert_eq!(topics.get(b"orders."), Some(1));
+
+        assert_eq!(topics.remove(b"orders.eu."), Some(2));
+        assert_eq!(topics.remove(b"orders."), Some(1));
+        assert!(topics.is_empty());
+        assert!(topics.snapshot().root.is_none());
+    }
+
+    #[test]
+    fn test_concurrent_lookups() {
+        let trie: SnapshotTrie<u32> = SnapshotTrie::new();
+        trie.insert(b"a", 0);
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 1..500u32 {
+                    trie.modify(|trie| {
+                        trie.insert(b"a", i);
+                        trie.insert(b"ab", i);
+                    });
+                }
+            });
+
+            // Both prefixes always come from the same version
+            for _ in 0..2_000 {
+                let snapshot = trie.snapshot();
+                let short = snapshot.get(b"a").copied();
+                let long = snapshot.get(b"ab").copied();
+                assert!(long.is_none() || long == short);
+                assert!(snapshot.longest_match(b"abc").is_some());
+            }
+        });
+    }
+}
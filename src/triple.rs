@@ -0,0 +1,151 @@
+use alloc::sync::Arc;
+
+use crate::{
+    sync::{
+        UnsafeCell,
+        atomic::{AtomicUsize, Ordering},
+    },
+    utils::Padded,
+};
+
+// Set on `back` when it holds a value the consumer hasn't picked up yet
+const DIRTY: usize = 0b100;
+const INDEX_MASK: usize = 0b11;
+
+// Single-producer, single-consumer triple buffer. The producer writes into
+// its own buffer and swaps it with the spare one on `publish`, the consumer
+// swaps its buffer with the spare one when it holds something newer. Both
+// sides are wait-free and never copy the value.
+pub struct TripleBuffer<T> {
+    // Index of the spare buffer, plus `DIRTY`
+    back: Padded<AtomicUsize>,
+    buffers: [UnsafeCell<T>; 3],
+}
+
+// Safety: each buffer is owned by exactly one of the producer, the consumer
+// and `back` at any time, and ownership only moves through the `back` swap,
+// which is AcqRel so buffer contents move with it
+unsafe impl<T: Send> Sync for TripleBuffer<T> {}
+
+impl<T: Clone> TripleBuffer<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            back: Padded::new(AtomicUsize::new(1)),
+            buffers: [
+                UnsafeCell::new(data.clone()),
+                UnsafeCell::new(data.clone()),
+                UnsafeCell::new(data),
+            ],
+        }
+    }
+}
+
+impl<T> TripleBuffer<T> {
+    pub fn split(self) -> (Producer<T>, Consumer<T>) {
+        let shared = Arc::new(self);
+
+        let consumer = Consumer {
+            shared: shared.clone(),
+            read: 0,
+        };
+
+        (Producer { shared, write: 2 }, consumer)
+    }
+}
+
+pub struct Producer<T> {
+    shared: Arc<TripleBuffer<T>>,
+    write: usize,
+}
+
+impl<T> Producer<T> {
+    // The buffer being written. It holds whatever was published two
+    // publishes ago, or the initial value, so overwrite what matters.
+    pub fn input(&mut self) -> &mut T {
+        // Safety: the producer owns `write`
+        self.shared.buffers[self.write].with_mut(|data| unsafe { &mut *data })
+    }
+
+    pub fn write(&mut self, data: T) {
+        *self.input() = data;
+        self.publish();
+    }
+
+    // Hands the input buffer to the consumer, replacing any value it hasn't
+    // picked up yet
+    pub fn publish(&mut self) {
+        let back = self.shared.back.swap(self.write | DIRTY, Ordering::AcqRel);
+        self.write = back & INDEX_MASK;
+    }
+}
+
+pub struct Consumer<T> {
+    shared: Arc<TripleBuffer<T>>,
+    read: usize,
+}
+
+impl<T> Consumer<T> {
+    pub fn has_update(&self) -> bool {
+        self.shared.back.load(Ordering::Relaxed) & DIRTY != 0
+    }
+
+    // Freshest published value, or the one read last if nothing was
+    // published since
+    pub fn read(&mut self) -> &T {
+        if self.has_update() {
+            let back = self.shared.back.swap(self.read, Ordering::AcqRel);
+            self.read = back & INDEX_MASK;
+        }
+
+        // Safety: the consumer owns `read`
+        self.shared.buffers[self.read].with(|data| unsafe { &*data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_triple_buffer() {
+        let (mut producer, mut consumer) = TripleBuffer::new(0).split();
+        assert!(!consumer.has_update());
+        assert_eq!(*consumer.read(), 0);
+
+        producer.write(1);
+        producer.write(2);
+        assert!(consumer.has_update());
+        assert_eq!(*consumer.read(), 2);
+        assert_eq!(*consumer.read(), 2);
+
+        *producer.input() = 3;
+        assert_eq!(*consumer.read(), 2);
+        producer.publish();
+        assert_eq!(*consumer.read(), 3);
+    }
+
+    #[test]
+    fn test_frames_are_never_torn() {
+        let (mut producer, mut consumer) = TripleBuffer::new([0_u64; 16]).split();
+
+        thread::scope(|s| {
+            s.spawn(move || {
+                for frame in 1..=10_000 {
+                    producer.input().fill(frame);
+                    producer.publish();
+                }
+            });
+
+            let mut last = 0;
+
+            while last != 10_000 {
+                let frame = consumer.read();
+                assert!(frame.iter().all(|value| *value == frame[0]));
+                assert!(frame[0] >= last);
+                last = frame[0];
+            }
+        });
+    }
+}
@@ -0,0 +1,155 @@
+// `CachePadded` and `Backoff`, taken from crossbeam when the `crossbeam`
+// feature is enabled and from the minimal fallbacks below otherwise.
+//
+// Hot atomics are wrapped in `Padded`, which is `CachePadded` with the
+// `padding` feature and a plain wrapper without it, trading false-sharing
+// avoidance for a much smaller footprint.
+
+#[cfg(feature = "crossbeam")]
+pub(crate) use crossbeam::utils::Backoff;
+#[cfg(all(feature = "crossbeam", feature = "padding"))]
+use crossbeam::utils::CachePadded;
+
+#[cfg(not(feature = "crossbeam"))]
+pub(crate) use fallback::Backoff;
+#[cfg(all(not(feature = "crossbeam"), feature = "padding"))]
+use fallback::CachePadded;
+
+#[cfg(feature = "padding")]
+pub(crate) type Padded<T> = CachePadded<T>;
+
+#[cfg(not(feature = "padding"))]
+pub(crate) type Padded<T> = unpadded::Unpadded<T>;
+
+#[cfg(not(feature = "padding"))]
+mod unpadded {
+    use core::ops::{Deref, DerefMut};
+
+    #[derive(Debug, Default)]
+    #[repr(transparent)]
+    pub(crate) struct Unpadded<T> {
+        value: T,
+    }
+
+    impl<T> Unpadded<T> {
+        pub(crate) const fn new(value: T) -> Self {
+            Self { value }
+        }
+    }
+
+    impl<T> Deref for Unpadded<T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            &self.value
+        }
+    }
+
+    impl<T> DerefMut for Unpadded<T> {
+        fn deref_mut(&mut self) -> &mut T {
+            &mut self.value
+        }
+    }
+}
+
+#[cfg(not(feature = "crossbeam"))]
+mod fallback {
+    use core::cell::Cell;
+    #[cfg(feature = "padding")]
+    use core::ops::{Deref, DerefMut};
+
+    // Same alignment crossbeam picks for the most common targets
+    #[cfg(feature = "padding")]
+    #[cfg_attr(
+        any(
+            target_arch = "x86_64",
+            target_arch = "aarch64",
+            target_arch = "powerpc64"
+        ),
+        repr(align(128))
+    )]
+    #[cfg_attr(
+        not(any(
+            target_arch = "x86_64",
+            target_arch = "aarch64",
+            target_arch = "powerpc64"
+        )),
+        repr(align(64))
+    )]
+    #[derive(Debug, Default)]
+    pub(crate) struct CachePadded<T> {
+        value: T,
+    }
+
+    #[cfg(feature = "padding")]
+    impl<T> CachePadded<T> {
+        pub(crate) const fn new(value: T) -> Self {
+            Self { value }
+        }
+    }
+
+    #[cfg(feature = "padding")]
+    impl<T> Deref for CachePadded<T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            &self.value
+        }
+    }
+
+    #[cfg(feature = "padding")]
+    impl<T> DerefMut for CachePadded<T> {
+        fn deref_mut(&mut self) -> &mut T {
+            &mut self.value
+        }
+    }
+
+    const SPIN_LIMIT: u32 = 6;
+    const YIELD_LIMIT: u32 = 10;
+
+    // Exponential backoff with the same limits as crossbeam's
+    pub(crate) struct Backoff {
+        step: Cell<u32>,
+    }
+
+    impl Backoff {
+        pub(crate) fn new() -> Self {
+            Self { step: Cell::new(0) }
+        }
+
+        pub(crate) fn spin(&self) {
+            for _ in 0..1 << self.step.get().min(SPIN_LIMIT) {
+                core::hint::spin_loop();
+            }
+
+            if self.step.get() <= SPIN_LIMIT {
+                self.step.set(self.step.get() + 1);
+            }
+        }
+
+        pub(crate) fn snooze(&self) {
+            if self.step.get() <= SPIN_LIMIT {
+                for _ in 0..1 << self.step.get() {
+                    core::hint::spin_loop();
+                }
+            } else {
+                #[cfg(feature = "std")]
+                std::thread::yield_now();
+
+                #[cfg(not(feature = "std"))]
+                for _ in 0..1 << self.step.get() {
+                    core::hint::spin_loop();
+                }
+            }
+
+            if self.step.get() <= YIELD_LIMIT {
+                self.step.set(self.step.get() + 1);
+            }
+        }
+
+        #[cfg(feature = "std")]
+        pub(crate) fn is_completed(&self) -> bool {
+            self.step.get() > YIELD_LIMIT
+        }
+    }
+}
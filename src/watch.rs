@@ -0,0 +1,269 @@
+use core::{
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use crate::cache::Cache;
+
+// Single-producer, multi-consumer channel that only keeps the latest value,
+// like `tokio::sync::watch`. Values live in a `Cache`, so receivers read
+// without locking and only the waiting paths take a mutex.
+pub fn channel<T: Clone>(initial: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        cache: Cache::new(initial),
+        state: Mutex::new(State {
+            closed: false,
+            wakers: Vec::new(),
+        }),
+        changed: Condvar::new(),
+    });
+
+    let receiver = Receiver {
+        shared: shared.clone(),
+        seen: 0,
+    };
+
+    (Sender { shared }, receiver)
+}
+
+struct Shared<T: Clone> {
+    cache: Cache<T>,
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+struct State {
+    closed: bool,
+    wakers: Vec<Waker>,
+}
+
+impl<T: Clone> Shared<T> {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn notify(&self, close: bool) {
+        let mut state = self.state();
+        state.closed |= close;
+
+        let wakers = std::mem::take(&mut state.wakers);
+        drop(state);
+
+        self.changed.notify_all();
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+// Returned once the sender is gone and every value has been seen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("watch channel closed")
+    }
+}
+
+impl Error for RecvError {}
+
+pub struct Sender<T: Clone> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Clone> Sender<T> {
+    pub fn send(&self, data: T) {
+        // Publishing before taking the state lock means a receiver that saw
+        // no change under the lock is already registered to be woken
+        self.shared.cache.update(data);
+        self.shared.notify(false);
+    }
+
+    pub fn get(&self) -> T {
+        self.shared.cache.get_data()
+    }
+
+    // New receiver that has already seen the current value
+    pub fn subscribe(&self) -> Receiver<T> {
+        Receiver {
+            shared: self.shared.clone(),
+            seen: self.shared.cache.generation(),
+        }
+    }
+}
+
+impl<T: Clone> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.notify(true);
+    }
+}
+
+#[derive(Clone)]
+pub struct Receiver<T: Clone> {
+    shared: Arc<Shared<T>>,
+    // Generation of the last value handed out by `get`
+    seen: u64,
+}
+
+impl<T: Clone> Receiver<T> {
+    // Latest value, without marking it as seen
+    pub fn borrow(&self) -> T {
+        self.shared.cache.get_data()
+    }
+
+    // Latest value, marking it as seen
+    pub fn get(&mut self) -> T {
+        let (data, generation) = self
+            .shared
+            .cache
+            .try_get_with_generation()
+            .expect("watch values are never cleared");
+
+        self.seen = generation;
+        data
+    }
+
+    pub fn has_changed(&self) -> bool {
+        self.shared.cache.generation() != self.seen
+    }
+
+    // Blocks until a value newer than the last one seen is sent and returns
+    // it, or fails once the sender is dropped
+    pub fn wait_changed(&mut self) -> Result<T, RecvError> {
+        let mut state = self.shared.state();
+
+        while !self.has_changed() {
+            if state.closed {
+                return Err(RecvError);
+            }
+
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+
+        drop(state);
+
+        Ok(self.get())
+    }
+
+    // Async version of `wait_changed`. Resolves without marking the value
+    // as seen, call `get` to read it.
+    pub fn changed(&mut self) -> Changed<'_, T> {
+        Changed { receiver: self }
+    }
+}
+
+pub struct Changed<'a, T: Clone> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T: Clone> Future for Changed<'_, T> {
+    type Output = Result<(), RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let receiver = &*self.receiver;
+        let mut state = receiver.shared.state();
+
+        if receiver.has_changed() {
+            return Poll::Ready(Ok(()));
+        }
+
+        if state.closed {
+            return Poll::Ready(Err(RecvError));
+        }
+
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::pin,
+        task::Wake,
+        thread::{self, Thread},
+    };
+
+    use super::*;
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn test_watch() {
+        let (sender, mut receiver) = channel(0);
+        assert!(!receiver.has_changed());
+        assert_eq!(receiver.borrow(), 0);
+
+        sender.send(1);
+        assert!(receiver.has_changed());
+        assert_eq!(receiver.get(), 1);
+        assert!(!receiver.has_changed());
+
+        let mut late = sender.subscribe();
+        assert!(!late.has_changed());
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 2..=100 {
+                    sender.send(i);
+                }
+            });
+
+            while receiver.wait_changed().unwrap() != 100 {}
+        });
+
+        assert_eq!(late.get(), 100);
+
+        drop(sender);
+        assert_eq!(receiver.wait_changed(), Err(RecvError));
+        assert_eq!(receiver.borrow(), 100);
+    }
+
+    #[test]
+    fn test_changed_future() {
+        let (sender, mut receiver) = channel("first");
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                thread::sleep(std::time::Duration::from_millis(5));
+                sender.send("second");
+            });
+
+            assert_eq!(block_on(receiver.changed()), Ok(()));
+        });
+        assert_eq!(receiver.get(), "second");
+
+        drop(sender);
+        assert_eq!(block_on(receiver.changed()), Err(RecvError));
+    }
+}
@@ -0,0 +1,31 @@
+// `#[derive(SlothSnapshot)]`, only built with the `derive` feature
+#![cfg(feature = "derive")]
+
+use sloth::SlothSnapshot;
+
+#[derive(Debug, Clone, PartialEq, SlothSnapshot)]
+pub struct Limits {
+    pub connections: u32,
+    pub hosts: Vec<String>,
+}
+
+#[test]
+fn derive_snapshot() {
+    let limits = LimitsCache::new(Limits {
+        connections: 10,
+        hosts: vec![String::from("a")],
+    });
+
+    limits.set_connections(20);
+    assert_eq!(limits.connections(), 20);
+    assert_eq!(limits.hosts(), ["a"]);
+
+    limits.set_hosts(vec![String::from("b")]);
+    assert_eq!(
+        limits.snapshot(),
+        Limits {
+            connections: 20,
+            hosts: vec![String::from("b")],
+        }
+    );
+}
@@ -0,0 +1,115 @@
+// Model-checked tests of the cache internals. Only built with the `loom`
+// feature or `RUSTFLAGS="--cfg loom"`, see `make test-loom`.
+#![cfg(loom)]
+
+use loom::{sync::Arc, thread};
+use sloth::cache::Cache;
+
+#[test]
+fn loom_read_during_update() {
+    loom::model(|| {
+        let cache = Arc::new(Cache::<u64, 2>::new(0));
+
+        let writer = {
+            let cache = cache.clone();
+            thread::spawn(move || cache.update(1))
+        };
+
+        let value = cache.get_data();
+        assert!(value == 0 || value == 1);
+
+        writer.join().unwrap();
+        assert_eq!(cache.get_data(), 1);
+    });
+}
+
+#[test]
+fn loom_reader_survives_writer_lapping_the_ring() {
+    loom::model(|| {
+        let cache = Arc::new(Cache::<u64, 2>::new(0));
+
+        // Three updates on two slots overwrite the slot a slow reader may
+        // have loaded from the index before it pinned it
+        let writer = {
+            let cache = cache.clone();
+            thread::spawn(move || {
+                for value in 1..=3 {
+                    cache.update(value);
+                }
+            })
+        };
+
+        let first = cache.get_data();
+        let second = cache.get_data();
+        assert!(first <= 3);
+        assert!(first <= second);
+
+        writer.join().unwrap();
+        assert_eq!(cache.get_data(), 3);
+    });
+}
+
+#[test]
+fn loom_concurrent_writers() {
+    loom::model(|| {
+        let cache = Arc::new(Cache::<u64, 2>::new(0));
+
+        let writers: Vec<_> = (1..=2)
+            .map(|value| {
+                let cache = cache.clone();
+                thread::spawn(move || cache.update(value))
+            })
+            .collect();
+
+        let value = cache.get_data();
+        assert!(value <= 2);
+
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let value = cache.get_data();
+        assert!(value == 1 || value == 2);
+    });
+}
+
+#[test]
+fn loom_clear_during_read() {
+    loom::model(|| {
+        let cache = Arc::new(Cache::<u64, 2>::new(7));
+
+        let writer = {
+            let cache = cache.clone();
+            thread::spawn(move || cache.clear())
+        };
+
+        let value = cache.try_get_data();
+        assert!(value.is_none() || value == Some(7));
+
+        writer.join().unwrap();
+        assert_eq!(cache.try_get_data(), None);
+    });
+}
+
+#[test]
+fn loom_reader_unpins_before_slot_reuse() {
+    loom::model(|| {
+        let cache = Arc::new(Cache::<Vec<u64>, 2>::new(vec![0]));
+
+        // The second update reuses the slot the reader may still be cloning
+        // out of, so the unpin must be ordered after the clone
+        let writer = {
+            let cache = cache.clone();
+            thread::spawn(move || {
+                cache.update(vec![1]);
+                cache.update(vec![2]);
+            })
+        };
+
+        let value = cache.get_data();
+        assert!(value == [0] || value == [1] || value == [2]);
+
+        writer.join().unwrap();
+        assert_eq!(cache.get_data(), [2]);
+    });
+}
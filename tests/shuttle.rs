@@ -0,0 +1,135 @@
+// Randomized schedule exploration of the cache with many readers and writers.
+// Only built with the `shuttle` feature or `RUSTFLAGS="--cfg shuttle"`, see
+// `make test-shuttle`.
+#![cfg(shuttle)]
+
+use shuttle::{sync::Arc, thread};
+use sloth::cache::Cache;
+
+const ITERATIONS: usize = 5_000;
+
+// Writers publish values tagged with their id, so every read can be checked
+// against the set of values that were actually published.
+fn is_published(value: (usize, usize), writers: usize, updates: usize) -> bool {
+    value == (0, 0) || (value.0 < writers && (1..=updates).contains(&value.1))
+}
+
+#[test]
+fn shuttle_readers_only_see_published_values() {
+    const WRITERS: usize = 2;
+    const READERS: usize = 3;
+    const UPDATES: usize = 4;
+
+    shuttle::check_random(
+        || {
+            let cache = Arc::new(Cache::<(usize, usize), 2>::new((0, 0)));
+
+            let writers: Vec<_> = (0..WRITERS)
+                .map(|id| {
+                    let cache = cache.clone();
+                    thread::spawn(move || {
+                        for i in 1..=UPDATES {
+                            cache.update((id, i));
+                        }
+                    })
+                })
+                .collect();
+
+            let readers: Vec<_> = (0..READERS)
+                .map(|_| {
+                    let cache = cache.clone();
+                    thread::spawn(move || {
+                        for _ in 0..UPDATES {
+                            let value = cache.get_data();
+                            assert!(is_published(value, WRITERS, UPDATES), "{value:?}");
+                        }
+                    })
+                })
+                .collect();
+
+            for handle in writers.into_iter().chain(readers) {
+                handle.join().unwrap();
+            }
+
+            assert_eq!(cache.get_data().1, UPDATES);
+        },
+        ITERATIONS,
+    );
+}
+
+#[test]
+fn shuttle_reads_never_go_back_in_time() {
+    const READERS: usize = 3;
+    const UPDATES: usize = 8;
+
+    shuttle::check_random(
+        || {
+            let cache = Arc::new(Cache::<usize, 2>::new(0));
+
+            let writer = {
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    for i in 1..=UPDATES {
+                        cache.update(i);
+                    }
+                })
+            };
+
+            let readers: Vec<_> = (0..READERS)
+                .map(|_| {
+                    let cache = cache.clone();
+                    thread::spawn(move || {
+                        let mut last = 0;
+
+                        for _ in 0..UPDATES {
+                            let value = cache.get_data();
+                            assert!(value >= last, "read {value} after {last}");
+                            last = value;
+                        }
+                    })
+                })
+                .collect();
+
+            for handle in readers.into_iter().chain([writer]) {
+                handle.join().unwrap();
+            }
+        },
+        ITERATIONS,
+    );
+}
+
+#[test]
+fn shuttle_maintenance_during_reads() {
+    shuttle::check_random(
+        || {
+            let cache = Arc::new(Cache::<usize, 4>::new(1));
+
+            let writer = {
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    cache.update(2);
+                    cache.drop_stale();
+                    cache.update(3);
+                    cache.clear();
+                    cache.update(4);
+                })
+            };
+
+            let reader = {
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    for _ in 0..4 {
+                        if let Some(value) = cache.try_get_data() {
+                            assert!((1..=4).contains(&value), "{value}");
+                        }
+                    }
+                })
+            };
+
+            writer.join().unwrap();
+            reader.join().unwrap();
+            assert_eq!(cache.get_data(), 4);
+        },
+        ITERATIONS,
+    );
+}